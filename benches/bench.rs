@@ -67,5 +67,51 @@ fn encode(c: &mut Criterion) {
   group.finish();
 }
 
-criterion::criterion_group!(benches, decode, encode);
+/// Encoding with line breaks, at PEM's and MIME's line lengths, against
+/// encoding without them.
+fn encode_wrapped(c: &mut Criterion) {
+  let mut group = c.benchmark_group("encode_wrapped");
+  let pem = vb64::Engine::STANDARD.wrap(64);
+  let mime = vb64::Engine::STANDARD
+    .wrap(76)
+    .line_ending(vb64::LineEnding::CrLf);
+  for len in [48, 1 << 10, 1 << 16] {
+    let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+    group
+      .warm_up_time(Duration::from_millis(250))
+      .measurement_time(Duration::from_millis(500))
+      .sample_size(250)
+      .throughput(Throughput::Bytes(len as u64))
+      .bench_with_input(BenchmarkId::new("unwrapped", len), &data, |b, data| {
+        b.iter(|| vb64::encode(data))
+      })
+      .bench_with_input(BenchmarkId::new("64", len), &data, |b, data| {
+        b.iter(|| pem.encode(data))
+      })
+      .bench_with_input(BenchmarkId::new("76-crlf", len), &data, |b, data| {
+        b.iter(|| mime.encode(data))
+      });
+  }
+  group.finish();
+}
+
+fn validate(c: &mut Criterion) {
+  let mut group = c.benchmark_group("validate");
+  for (i, enc, _) in tests() {
+    group
+      .warm_up_time(Duration::from_millis(250))
+      .measurement_time(Duration::from_millis(500))
+      .sample_size(250)
+      .throughput(Throughput::Bytes(enc.len() as u64))
+      .bench_with_input(BenchmarkId::new("vb64", i), enc, |b, enc| {
+        b.iter(|| vb64::is_base64(enc))
+      })
+      .bench_with_input(BenchmarkId::new("decode", i), enc, |b, enc| {
+        b.iter(|| vb64::decode(enc).is_ok())
+      });
+  }
+  group.finish();
+}
+
+criterion::criterion_group!(benches, decode, encode, encode_wrapped, validate);
 criterion::criterion_main!(benches);
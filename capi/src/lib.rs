@@ -0,0 +1,8 @@
+//! Builds `vb64`'s C API, declared in `include/vb64.h`, as a shared and a
+//! static library.
+//!
+//! The functions themselves live in `vb64::capi`; this crate only exists so
+//! that linking them from C doesn't force every Rust user of `vb64` to build
+//! a `cdylib` and a `staticlib` too.
+
+pub use vb64::capi::*;
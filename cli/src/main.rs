@@ -0,0 +1,209 @@
+//! `vb64`, a faster `base64(1)`.
+//!
+//! ```text
+//! vb64 [-d] [-u] [-n] [-s] [-w COLS] [FILE]
+//! ```
+//!
+//! Reads `FILE`, or standard input if it's missing or `-`, and writes the
+//! result to standard output.
+
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::process::ExitCode;
+
+use vb64::Alphabet;
+use vb64::Config;
+use vb64::Padding;
+
+const USAGE: &str = "\
+usage: vb64 [OPTIONS] [FILE]
+
+Encodes FILE, or standard input, as base64, or decodes it with -d.
+
+options:
+  -d, --decode      decode instead of encoding; whitespace is ignored
+  -u, --url-safe    use the URL-safe alphabet, `-_` instead of `+/`
+  -n, --no-padding  don't pad when encoding, and reject padding when decoding
+  -s, --strict      only accept canonical base64 when decoding
+  -w, --wrap COLS   wrap encoded lines after COLS characters, a multiple of
+                    four (default 76); 0 disables wrapping
+  -h, --help        print this message
+";
+
+/// The parsed command line.
+#[derive(Debug, PartialEq)]
+struct Args {
+  decode: bool,
+  url_safe: bool,
+  no_padding: bool,
+  strict: bool,
+  wrap: usize,
+  file: Option<String>,
+}
+
+impl Args {
+  /// Parses the arguments after the program name; returns `Ok(None)` if the
+  /// user asked for help.
+  fn parse(
+    args: impl IntoIterator<Item = String>,
+  ) -> Result<Option<Self>, String> {
+    let mut parsed = Args {
+      decode: false,
+      url_safe: false,
+      no_padding: false,
+      strict: false,
+      wrap: 76,
+      file: None,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "-d" | "--decode" => parsed.decode = true,
+        "-u" | "--url-safe" => parsed.url_safe = true,
+        "-n" | "--no-padding" => parsed.no_padding = true,
+        "-s" | "--strict" => parsed.strict = true,
+        "-h" | "--help" => return Ok(None),
+        "-w" | "--wrap" => {
+          let cols = args.next().ok_or("missing argument to --wrap")?;
+          parsed.wrap = match cols.parse() {
+            Ok(n) if n % 4 == 0 => n,
+            _ => return Err(format!("invalid line length: {cols}")),
+          };
+        }
+        "-" => parsed.file = Some(arg),
+        flag if flag.starts_with('-') => {
+          return Err(format!("unknown option: {flag}"))
+        }
+        _ if parsed.file.is_some() => {
+          return Err(format!("unexpected argument: {arg}"))
+        }
+        _ => parsed.file = Some(arg),
+      }
+    }
+    Ok(Some(parsed))
+  }
+
+  /// Encodes or decodes `input`, as the arguments ask.
+  fn run(&self, input: &[u8]) -> Result<Vec<u8>, vb64::Error> {
+    let alphabet = match self.url_safe {
+      true => Alphabet::UrlSafe,
+      false => Alphabet::Standard,
+    };
+    let padding = match self.no_padding {
+      true => Padding::Forbidden,
+      false => Padding::Optional,
+    };
+    let engine = Config::new()
+      .alphabet(alphabet)
+      .padding(padding)
+      .strict(self.strict)
+      .ignore_whitespace(true)
+      .wrap(self.wrap)
+      .build();
+
+    if self.decode {
+      engine.decode(input)
+    } else {
+      Ok(engine.encode(input).into_bytes())
+    }
+  }
+}
+
+fn main() -> ExitCode {
+  let args = match Args::parse(std::env::args().skip(1)) {
+    Ok(Some(args)) => args,
+    Ok(None) => {
+      print!("{USAGE}");
+      return ExitCode::SUCCESS;
+    }
+    Err(e) => {
+      eprint!("vb64: {e}\n\n{USAGE}");
+      return ExitCode::from(2);
+    }
+  };
+
+  let input = match args.file.as_deref() {
+    None | Some("-") => {
+      let mut buf = Vec::new();
+      io::stdin().read_to_end(&mut buf).map(|_| buf)
+    }
+    Some(path) => fs::read(path),
+  };
+  let input = match input {
+    Ok(input) => input,
+    Err(e) => {
+      let name = args.file.as_deref().unwrap_or("-");
+      eprintln!("vb64: {name}: {e}");
+      return ExitCode::FAILURE;
+    }
+  };
+
+  let output = match args.run(&input) {
+    Ok(output) => output,
+    Err(e) => {
+      eprintln!("vb64: {e}");
+      return ExitCode::FAILURE;
+    }
+  };
+  match io::stdout().lock().write_all(&output) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("vb64: {e}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+    Args::parse(args.iter().map(|s| s.to_string()))
+  }
+
+  #[test]
+  fn args() {
+    let args = parse(&["-d", "--url-safe", "-w", "8", "in.txt"]).unwrap();
+    assert_eq!(
+      args,
+      Some(Args {
+        decode: true,
+        url_safe: true,
+        no_padding: false,
+        strict: false,
+        wrap: 8,
+        file: Some("in.txt".into()),
+      })
+    );
+
+    assert_eq!(parse(&["--help"]).unwrap(), None);
+    assert!(parse(&["-w", "7"]).is_err());
+    assert!(parse(&["-w"]).is_err());
+    assert!(parse(&["-x"]).is_err());
+    assert!(parse(&["a", "b"]).is_err());
+  }
+
+  #[test]
+  fn run() {
+    let data = vec![0xfb; 100];
+    let encode = parse(&[]).unwrap().unwrap();
+    let text = encode.run(&data).unwrap();
+    assert_eq!(text.iter().filter(|&&b| b == b'\n').count(), 2);
+    assert!(text.split(|&b| b == b'\n').all(|line| line.len() <= 76));
+
+    let decode = parse(&["-d"]).unwrap().unwrap();
+    assert_eq!(decode.run(&text).unwrap(), data);
+
+    let encode = parse(&["-u", "-n", "-w", "0"]).unwrap().unwrap();
+    assert_eq!(encode.run(b"\xfb\xff").unwrap(), b"-_8");
+    let decode = parse(&["-d", "-u", "-n", "-s"]).unwrap().unwrap();
+    assert_eq!(decode.run(b"-_8").unwrap(), b"\xfb\xff");
+    assert!(decode.run(b"-_8=").is_err());
+    assert!(decode.run(b"-_9").is_err());
+  }
+}
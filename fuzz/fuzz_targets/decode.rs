@@ -0,0 +1,53 @@
+//! Checks that lenient decoding agrees with the `base64` crate configured the
+//! same way: padding is optional, and the unused bits of the last character
+//! are ignored.
+//!
+//! The one place we deliberately differ is padding that's present but doesn't
+//! round the input up to a multiple of four, like `QQ=`, which `base64`
+//! accepts and we reject.
+
+#![no_main]
+
+use base64::alphabet;
+use base64::engine::general_purpose::GeneralPurpose;
+use base64::engine::general_purpose::GeneralPurposeConfig;
+use base64::engine::DecodePaddingMode;
+use base64::Engine as _;
+use libfuzzer_sys::fuzz_target;
+
+const CONFIG: GeneralPurposeConfig = GeneralPurposeConfig::new()
+  .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+  .with_decode_allow_trailing_bits(true);
+const STANDARD: GeneralPurpose =
+  GeneralPurpose::new(&alphabet::STANDARD, CONFIG);
+const URL_SAFE: GeneralPurpose =
+  GeneralPurpose::new(&alphabet::URL_SAFE, CONFIG);
+
+fuzz_target!(|data: &[u8]| {
+  let short_padding = data.ends_with(b"=") && data.len() % 4 != 0;
+  let pairs = [
+    (vb64::decode(data), STANDARD.decode(data)),
+    (vb64::decode_url(data), URL_SAFE.decode(data)),
+  ];
+
+  for (ours, theirs) in pairs {
+    if short_padding {
+      assert!(ours.is_err(), "{ours:?}");
+      continue;
+    }
+
+    assert_eq!(ours.is_ok(), theirs.is_ok(), "{ours:?} vs {theirs:?}");
+    if let (Ok(ours), Ok(theirs)) = (ours, theirs) {
+      assert_eq!(ours, theirs);
+    }
+  }
+
+  // Validation must agree with decoding, and decoding into a buffer must
+  // leave it alone on failure.
+  let mut out = b"prefix".to_vec();
+  let res = vb64::decode_to(data, &mut out);
+  assert_eq!(res.is_ok(), vb64::is_base64(data));
+  if res.is_err() {
+    assert_eq!(out, b"prefix");
+  }
+});
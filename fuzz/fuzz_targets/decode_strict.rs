@@ -0,0 +1,39 @@
+//! Checks that strict decoding agrees with the `data-encoding` crate, which
+//! only accepts canonical encodings.
+//!
+//! `data-encoding` also accepts several padded encodings glued together, like
+//! `QQ==QQ==`, which we reject: once padding starts, the input must end.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vb64::Engine;
+
+fuzz_target!(|data: &[u8]| {
+  let pairs = [
+    (Engine::STANDARD.strict(), data_encoding::BASE64),
+    (Engine::STANDARD_NOPAD.strict(), data_encoding::BASE64_NOPAD),
+    (Engine::URL_SAFE.strict(), data_encoding::BASE64URL),
+    (
+      Engine::URL_SAFE_NOPAD.strict(),
+      data_encoding::BASE64URL_NOPAD,
+    ),
+  ];
+
+  let unpadded = data.iter().rposition(|&b| b != b'=').map_or(0, |i| i + 1);
+  let inner_padding = data[..unpadded].contains(&b'=');
+
+  for (ours, theirs) in pairs {
+    let ours = ours.decode(data);
+    if inner_padding {
+      assert!(ours.is_err(), "{ours:?}");
+      continue;
+    }
+
+    let theirs = theirs.decode(data);
+    assert_eq!(ours.is_ok(), theirs.is_ok(), "{ours:?} vs {theirs:?}");
+    if let (Ok(ours), Ok(theirs)) = (ours, theirs) {
+      assert_eq!(ours, theirs);
+    }
+  }
+});
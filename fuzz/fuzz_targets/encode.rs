@@ -0,0 +1,22 @@
+//! Checks that encoding agrees with the `base64` and `data-encoding` crates,
+//! and that everything we encode decodes back to the same bytes.
+
+#![no_main]
+
+use base64::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let enc = vb64::encode(data);
+  assert_eq!(enc, BASE64_STANDARD.encode(data));
+  assert_eq!(enc, data_encoding::BASE64.encode(data));
+  assert_eq!(vb64::decode(enc.as_bytes()).unwrap(), data);
+
+  let enc = vb64::encode_url_nopad(data);
+  assert_eq!(enc, BASE64_URL_SAFE_NO_PAD.encode(data));
+  assert_eq!(enc, data_encoding::BASE64URL_NOPAD.encode(data));
+  assert_eq!(vb64::decode_url_nopad(enc.as_bytes()).unwrap(), data);
+
+  let engine = vb64::Engine::STANDARD.strict();
+  assert_eq!(engine.decode(engine.encode(data).as_bytes()).unwrap(), data);
+});
@@ -0,0 +1,72 @@
+#ifndef VB64_H
+#define VB64_H
+
+/* Generated by cbindgen from src/capi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a call into the C API.
+typedef enum Vb64Status {
+  // The call succeeded.
+  VB64_STATUS_OK = 0,
+  // A byte that isn't part of the alphabet; see [`Error::InvalidByte`].
+  VB64_STATUS_INVALID_BYTE = 1,
+  // See [`Error::InvalidLength`].
+  VB64_STATUS_INVALID_LENGTH = 2,
+  // See [`Error::InvalidPadding`].
+  VB64_STATUS_INVALID_PADDING = 3,
+  // See [`Error::InvalidTrailingBits`].
+  VB64_STATUS_INVALID_TRAILING_BITS = 4,
+  // The output buffer is too short.
+  VB64_STATUS_BUFFER_TOO_SMALL = 5,
+  // A pointer argument was null when it wasn't allowed to be.
+  VB64_STATUS_NULL_POINTER = 6,
+  // Any other error.
+  VB64_STATUS_OTHER = 255,
+} Vb64Status;
+
+// Returns how long the padded base64 encoding of `len` bytes is, or zero if
+// that would overflow.
+size_t vb64_encoded_len(size_t len);
+
+// Returns an upper bound on how many bytes `len` bytes of base64 decode to.
+size_t vb64_max_decoded_len(size_t len);
+
+// Encodes `src_len` bytes at `src` as standard, padded base64 into `dst`.
+//
+// On success, writes the length of the encoding to `*dst_len`. `dst_cap`
+// must be at least [`vb64_encoded_len()`] of `src_len`.
+//
+// # Safety
+//
+// `src` must be valid for reads of `src_len` bytes, `dst` for writes of
+// `dst_cap` bytes, and `dst_len` for a write of a `size_t`. `src` and `dst`
+// may only be null if their lengths are zero.
+Vb64Status vb64_encode(const uint8_t *src,
+                       size_t src_len,
+                       uint8_t *dst,
+                       size_t dst_cap,
+                       size_t *dst_len);
+
+// Decodes `src_len` bytes of standard base64 at `src` into `dst`, following
+// the same rules as `vb64::decode()`.
+//
+// On success, writes the length of the decoded data to `*dst_len`. If the
+// input contains an invalid byte, writes that byte's offset there instead.
+// `dst_cap` must be at least [`vb64_max_decoded_len()`] of `src_len`.
+//
+// # Safety
+//
+// `src` must be valid for reads of `src_len` bytes, `dst` for writes of
+// `dst_cap` bytes, and `dst_len` for a write of a `size_t`. `src` and `dst`
+// may only be null if their lengths are zero.
+Vb64Status vb64_decode(const uint8_t *src,
+                       size_t src_len,
+                       uint8_t *dst,
+                       size_t dst_cap,
+                       size_t *dst_len);
+
+#endif /* VB64_H */
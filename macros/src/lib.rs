@@ -0,0 +1,119 @@
+//! Compile-time base64 literals for `vb64`.
+//!
+//! [`b64!`] decodes a string literal while your crate is being compiled, so
+//! embedded keys and fixtures cost nothing at runtime:
+//!
+//! ```
+//! use vb64_macros::b64;
+//!
+//! const GREETING: &[u8] = b64!("aGVsbG8=");
+//! assert_eq!(GREETING, b"hello");
+//! ```
+
+#![feature(proc_macro_span)]
+
+use proc_macro::Delimiter;
+use proc_macro::Group;
+use proc_macro::Literal;
+use proc_macro::Span;
+use proc_macro::TokenStream;
+use proc_macro::TokenTree;
+
+/// Decodes a string literal of standard base64 into a `&'static [u8]`.
+///
+/// This follows the same rules as `vb64::decode()`. If the literal isn't
+/// valid base64, compilation fails with an error pointing at the first bad
+/// character:
+///
+/// ```compile_fail
+/// let data = vb64_macros::b64!("aGVs!G8=");
+/// ```
+///
+/// The literal can also be a raw string, but it can't contain escapes, which
+/// base64 never needs anyway.
+///
+/// ```
+/// assert_eq!(vb64_macros::b64!(r"QUJD"), b"ABC");
+/// assert_eq!(vb64_macros::b64!(r#"QUJD"#), b"ABC");
+/// assert_eq!(vb64_macros::b64!(""), b"");
+/// ```
+#[proc_macro]
+pub fn b64(input: TokenStream) -> TokenStream {
+  match expand(input) {
+    Ok(tokens) => tokens,
+    Err((span, message)) => compile_error(span, &message),
+  }
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, (Span, String)> {
+  let lit = literal(input)?;
+  let text = lit.to_string();
+
+  // How many `#` a raw string has, and the offset of its contents within the
+  // literal's source text.
+  let (hashes, start) = match text.strip_prefix('r') {
+    Some(raw) => {
+      let hashes = raw.bytes().take_while(|&b| b == b'#').count();
+      (hashes, 2 + hashes)
+    }
+    None if text.starts_with('"') => (0, 1),
+    None => return Err((lit.span(), "expected a string literal".into())),
+  };
+  let data = &text[start..text.len() - 1 - hashes];
+  if start == 1 && data.contains('\\') {
+    return Err((lit.span(), "base64 literals can't contain escapes".into()));
+  }
+
+  let bytes = vb64::decode(data).map_err(|e| {
+    let span = e
+      .offset()
+      .and_then(|offset| lit.subspan(start + offset..start + offset + 1))
+      .unwrap_or(lit.span());
+    (span, e.to_string())
+  })?;
+
+  let bytes = Literal::byte_string(&bytes);
+  Ok(format!("({bytes} as &'static [u8])").parse().unwrap())
+}
+
+/// Extracts the single literal that makes up `input`, looking through any
+/// invisible groups left behind by `macro_rules!`.
+fn literal(input: TokenStream) -> Result<Literal, (Span, String)> {
+  let mut tokens = input.into_iter();
+  let token = tokens.next();
+  if let Some(extra) = tokens.next() {
+    return Err((extra.span(), "expected a single string literal".into()));
+  }
+
+  match token {
+    Some(TokenTree::Literal(lit)) => Ok(lit),
+    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::None => {
+      literal(g.stream())
+    }
+    Some(token) => Err((token.span(), "expected a string literal".into())),
+    None => Err((Span::call_site(), "expected a string literal".into())),
+  }
+}
+
+/// Expands to a `compile_error!` with the given message, reported at `span`.
+fn compile_error(span: Span, message: &str) -> TokenStream {
+  let tokens: TokenStream = format!("::core::compile_error!({message:?})")
+    .parse()
+    .unwrap();
+  respan(tokens, span)
+}
+
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+  tokens
+    .into_iter()
+    .map(|mut token| {
+      if let TokenTree::Group(g) = &token {
+        let mut group = Group::new(g.delimiter(), respan(g.stream(), span));
+        group.set_span(span);
+        token = TokenTree::Group(group);
+      }
+      token.set_span(span);
+      token
+    })
+    .collect()
+}
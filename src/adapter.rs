@@ -0,0 +1,183 @@
+//! The buffering behind the reader and writer adapters, which is the same
+//! whether they sit on `std::io`, `futures-io`, or `embedded-io`.
+//!
+//! The buffers are fixed-size arrays, so that this works without an allocator;
+//! the `std` adapters box them.
+
+use crate::Decoder;
+use crate::Encoder;
+use crate::Error;
+
+/// Room that [`Push`] keeps free for finishing its codec: at most a last group
+/// of four characters, plus line endings on either side of it.
+const FINISH_ROOM: usize = 8;
+
+/// A streaming [`Encoder`] or [`Decoder`], as the adapters drive it.
+pub(crate) trait Codec: Copy {
+  /// Returns the most input that can be processed in one go without producing
+  /// more than `out_len` bytes of output.
+  fn max_input(&self, out_len: usize) -> usize;
+
+  /// Processes the next piece of input, writing any output to the start of
+  /// `out` and returning how much there was.
+  fn update(&mut self, input: &[u8], out: &mut [u8]) -> Result<usize, Error>;
+
+  /// Processes the end of the input, writing any output to the start of `out`
+  /// and returning how much there was.
+  fn finish(self, out: &mut [u8]) -> Result<usize, Error>;
+}
+
+impl Codec for Decoder<'_> {
+  fn max_input(&self, out_len: usize) -> usize {
+    Decoder::max_input(self, out_len)
+  }
+
+  fn update(&mut self, input: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    self.update_to_slice(input, out)
+  }
+
+  fn finish(self, out: &mut [u8]) -> Result<usize, Error> {
+    self.finish_to_slice(out)
+  }
+}
+
+impl Codec for Encoder<'_> {
+  fn max_input(&self, out_len: usize) -> usize {
+    Encoder::max_input(self, out_len)
+  }
+
+  fn update(&mut self, input: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    self.update_to_slice(input, out)
+  }
+
+  fn finish(self, out: &mut [u8]) -> Result<usize, Error> {
+    self.finish_to_slice(out)
+  }
+}
+
+/// The state of a reader adapter: input read from the inner reader, and what
+/// the codec made of it.
+pub(crate) struct Pull<C, const N: usize> {
+  codec: C,
+  /// Input read from the inner reader.
+  input: [u8; N],
+  /// Output that hasn't been read yet, which is `output[pos..len]`.
+  output: [u8; N],
+  pos: usize,
+  len: usize,
+  /// Whether the inner reader has run out, and `codec` has been finished.
+  done: bool,
+}
+
+impl<C: Codec, const N: usize> Pull<C, N> {
+  pub(crate) fn new(codec: C) -> Self {
+    Self {
+      codec,
+      input: [0; N],
+      output: [0; N],
+      pos: 0,
+      len: 0,
+      done: false,
+    }
+  }
+
+  /// Returns the buffer to read more input into, if all the output so far has
+  /// been read and there might be more.
+  pub(crate) fn wants_input(&mut self) -> Option<&mut [u8]> {
+    if self.pos < self.len || self.done {
+      return None;
+    }
+    // Never empty, since an empty read looks like the end of the input.
+    let max = self.codec.max_input(N).clamp(1, N);
+    Some(&mut self.input[..max])
+  }
+
+  /// Processes `n` bytes that were just read into the buffer returned by
+  /// [`Pull::wants_input()`]; zero means the input has run out.
+  pub(crate) fn fill(&mut self, n: usize) -> Result<(), Error> {
+    self.pos = 0;
+    self.len = 0;
+    if n == 0 {
+      self.len = self.codec.finish(&mut self.output)?;
+      self.done = true;
+    } else {
+      self.len = self.codec.update(&self.input[..n], &mut self.output)?;
+    }
+    Ok(())
+  }
+
+  /// Copies as much output as fits into `buf`.
+  pub(crate) fn read(&mut self, buf: &mut [u8]) -> usize {
+    let n = buf.len().min(self.len - self.pos);
+    buf[..n].copy_from_slice(&self.output[self.pos..self.pos + n]);
+    self.pos += n;
+    n
+  }
+}
+
+/// The state of a writer adapter: output that the codec has produced but that
+/// hasn't been written to the inner writer yet.
+pub(crate) struct Push<C, const N: usize> {
+  codec: C,
+  /// Output, of which `output[pos..len]` hasn't been written yet.
+  output: [u8; N],
+  pos: usize,
+  len: usize,
+  /// Whether `codec` has been finished.
+  finished: bool,
+}
+
+impl<C: Codec, const N: usize> Push<C, N> {
+  pub(crate) fn new(codec: C) -> Self {
+    Self {
+      codec,
+      output: [0; N],
+      pos: 0,
+      len: 0,
+      finished: false,
+    }
+  }
+
+  /// Returns how much input fits in what's left of the buffer.
+  fn room(&self) -> usize {
+    let free = (N - self.len).saturating_sub(FINISH_ROOM);
+    self.codec.max_input(free)
+  }
+
+  /// Whether the output needs writing out before any more input fits.
+  pub(crate) fn is_full(&self) -> bool {
+    self.room() == 0
+  }
+
+  /// Processes as much of `input` as fits in one go, returning how much.
+  pub(crate) fn push(&mut self, input: &[u8]) -> Result<usize, Error> {
+    let n = input.len().min(self.room());
+    self.len += self
+      .codec
+      .update(&input[..n], &mut self.output[self.len..])?;
+    Ok(n)
+  }
+
+  /// Processes the end of the input, if that hasn't been done yet.
+  pub(crate) fn finish(&mut self) -> Result<(), Error> {
+    if !self.finished {
+      self.len += self.codec.finish(&mut self.output[self.len..])?;
+      self.finished = true;
+    }
+    Ok(())
+  }
+
+  /// Returns the output that hasn't been written yet.
+  pub(crate) fn unwritten(&self) -> &[u8] {
+    &self.output[self.pos..self.len]
+  }
+
+  /// Records that `n` more bytes of output have been written.
+  pub(crate) fn advance(&mut self, n: usize) {
+    self.pos += n;
+    if self.pos == self.len {
+      self.pos = 0;
+      self.len = 0;
+    }
+  }
+}
@@ -0,0 +1,157 @@
+//! Support for fuzzing code that uses `vb64`, via the `arbitrary` crate.
+//!
+//! Requires the `arbitrary` feature. [`Engine`], [`Config`], [`Alphabet`], and
+//! [`Padding`] all implement [`Arbitrary`], and [`arbitrary_base64()`]
+//! generates base64 text that is mostly well-formed, so that a fuzzer spends
+//! its time past the first invalid character:
+//!
+//! ```
+//! use arbitrary::Arbitrary;
+//! use arbitrary::Unstructured;
+//!
+//! let mut u = Unstructured::new(b"some fuzzer input, of any length at all");
+//! let engine = vb64::Engine::arbitrary(&mut u).unwrap();
+//! let sample = vb64::arbitrary::arbitrary_base64(&mut u, &engine, 2).unwrap();
+//! if !sample.corrupted {
+//!   assert_eq!(engine.decode(&sample.text).unwrap(), sample.data);
+//! }
+//! ```
+//!
+//! [`Alphabet`]: crate::Alphabet
+
+use ::arbitrary::Arbitrary;
+use ::arbitrary::Result;
+use ::arbitrary::Unstructured;
+
+use crate::Config;
+use crate::Engine;
+use crate::Padding;
+
+/// Bytes that [`arbitrary_base64()`] favors when inserting characters, since
+/// they sit on the edges of what decoders accept.
+const INTERESTING: &[u8] = b"=\n\r\t +/-_.\0\x7f\x80\xff";
+
+/// Base64 text generated by [`arbitrary_base64()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sample {
+  /// The data that was encoded.
+  pub data: Vec<u8>,
+  /// The encoding of `data`, possibly corrupted.
+  pub text: Vec<u8>,
+  /// Whether `text` was changed after encoding.
+  ///
+  /// Corrupted text may still decode, possibly to something other than
+  /// `data`; text that isn't corrupted always decodes to `data`.
+  pub corrupted: bool,
+}
+
+/// Encodes some arbitrary data with `engine`, then makes up to
+/// `max_corruptions` edits to the text, each of which overwrites, deletes, or
+/// inserts a byte.
+///
+/// With `max_corruptions` set to zero, this always produces valid base64.
+pub fn arbitrary_base64(
+  u: &mut Unstructured,
+  engine: &Engine,
+  max_corruptions: usize,
+) -> Result<Sample> {
+  let data = Vec::<u8>::arbitrary(u)?;
+  let clean = engine.encode(&data).into_bytes();
+
+  let mut text = clean.clone();
+  for _ in 0..u.int_in_range(0..=max_corruptions)? {
+    let at = u.int_in_range(0..=text.len())?;
+    match u.int_in_range(0..=2)? {
+      0 if at < text.len() => text[at] = u.arbitrary()?,
+      1 if at < text.len() => drop(text.remove(at)),
+      _ => text.insert(at, *u.choose(INTERESTING)?),
+    }
+  }
+
+  let corrupted = text != clean;
+  Ok(Sample {
+    data,
+    text,
+    corrupted,
+  })
+}
+
+impl<'a> Arbitrary<'a> for Config {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    Ok(
+      Config::new()
+        .alphabet(u.arbitrary()?)
+        .padding(u.arbitrary()?)
+        .strict(u.arbitrary()?)
+        .lenient_length(u.arbitrary()?)
+        .ignore_whitespace(u.arbitrary()?)
+        .wrap(u.int_in_range(0..=32)? * 4)
+        .line_ending(u.arbitrary()?),
+    )
+  }
+}
+
+/// Generates engines for the built-in alphabets, through [`Config`], and for
+/// random custom alphabets.
+impl<'a> Arbitrary<'a> for Engine {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    if u.ratio(3, 4)? {
+      return Ok(Config::arbitrary(u)?.build());
+    }
+
+    // Pick 64 distinct printable characters other than `=`.
+    let mut chars = (b'!'..=b'~').filter(|&c| c != b'=').collect::<Vec<_>>();
+    let mut alphabet = [0; 64];
+    for (i, c) in alphabet.iter_mut().enumerate() {
+      let j = u.int_in_range(i..=chars.len() - 1)?;
+      chars.swap(i, j);
+      *c = chars[i];
+    }
+    // Custom engines always pad, so they must accept their own padding.
+    let padding = *u.choose(&[Padding::Required, Padding::Optional])?;
+    let mut engine = Engine::new(&alphabet)
+      .expect("distinct printable characters make a valid alphabet")
+      .decode_padding(padding);
+
+    if u.arbitrary()? {
+      engine = engine.strict();
+    }
+    if u.arbitrary()? {
+      engine = engine.lenient_length();
+    }
+    if u.arbitrary()? {
+      engine = engine.ignore_whitespace();
+    }
+    if u.arbitrary()? {
+      engine = engine
+        .wrap(u.int_in_range(1..=32)? * 4)
+        .line_ending(u.arbitrary()?);
+    }
+    Ok(engine)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn arbitrary() {
+    let seed = (0..4096u32)
+      .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+      .collect::<Vec<_>>();
+    for start in 0..256 {
+      let mut u = Unstructured::new(&seed[start * 7..]);
+      let engine = Engine::arbitrary(&mut u).unwrap();
+
+      let sample = arbitrary_base64(&mut u, &engine, 0).unwrap();
+      assert!(!sample.corrupted, "seed {start}");
+      assert_eq!(engine.decode(&sample.text).unwrap(), sample.data);
+
+      let sample = arbitrary_base64(&mut u, &engine, 4).unwrap();
+      if !sample.corrupted {
+        assert_eq!(engine.decode(&sample.text).unwrap(), sample.data);
+      }
+    }
+  }
+}
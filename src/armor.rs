@@ -0,0 +1,243 @@
+//! OpenPGP ASCII armor, per [RFC 4880 §6].
+//!
+//! Armor is similar to PEM, but allows for `Key: Value` headers after the
+//! `-----BEGIN` line, and appends a CRC-24 checksum of the decoded data.
+//!
+//! ```
+//! let text = vb64::armor::encode("PGP MESSAGE", &[("Version", "vb64")], b"hi");
+//! assert_eq!(
+//!   text,
+//!   "-----BEGIN PGP MESSAGE-----\n\
+//!    Version: vb64\n\
+//!    \n\
+//!    aGk=\n\
+//!    =Um4c\n\
+//!    -----END PGP MESSAGE-----\n",
+//! );
+//!
+//! let armor = vb64::armor::decode(text.as_bytes()).unwrap();
+//! assert_eq!(armor.label(), "PGP MESSAGE");
+//! assert_eq!(armor.headers(), [("Version".into(), "vb64".into())]);
+//! assert_eq!(armor.data(), b"hi");
+//! ```
+//!
+//! [RFC 4880 §6]: https://datatracker.ietf.org/doc/html/rfc4880#section-6
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::pem::find;
+use crate::pem::split_line;
+use crate::pem::trim;
+use crate::Error;
+
+/// The number of base64 characters per line. RFC 4880 allows up to 76.
+const LINE_LEN: usize = 64;
+
+/// A decoded armored message.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Armor {
+  label: String,
+  headers: Vec<(String, String)>,
+  data: Vec<u8>,
+}
+
+impl Armor {
+  /// Returns this message's label, such as `PGP MESSAGE`.
+  pub fn label(&self) -> &str {
+    &self.label
+  }
+
+  /// Returns this message's headers, in order.
+  pub fn headers(&self) -> &[(String, String)] {
+    &self.headers
+  }
+
+  /// Returns this message's decoded data.
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+
+  /// Consumes this message, returning its decoded data.
+  pub fn into_data(self) -> Vec<u8> {
+    self.data
+  }
+}
+
+/// Armors `data` with the given label and headers.
+pub fn encode(label: &str, headers: &[(&str, &str)], data: &[u8]) -> String {
+  let mut out = String::new();
+  out.push_str("-----BEGIN ");
+  out.push_str(label);
+  out.push_str("-----\n");
+  for (key, value) in headers {
+    out.push_str(key);
+    out.push_str(": ");
+    out.push_str(value);
+    out.push('\n');
+  }
+  out.push('\n');
+
+  let mut line = Vec::with_capacity(LINE_LEN + 4);
+  for chunk in data.chunks(LINE_LEN / 4 * 3) {
+    line.clear();
+    crate::encode_to(chunk, &mut line);
+    line.push(b'\n');
+    out.push_str(unsafe { core::str::from_utf8_unchecked(&line) });
+  }
+
+  out.push('=');
+  out.push_str(&crate::encode(&crc24(data).to_be_bytes()[1..]));
+  out.push('\n');
+
+  out.push_str("-----END ");
+  out.push_str(label);
+  out.push_str("-----\n");
+  out
+}
+
+/// Decodes the first armored message in `text`.
+///
+/// Any text before the `-----BEGIN` line is ignored. If the message has a
+/// checksum, it must match the decoded data; a missing checksum is accepted.
+pub fn decode(text: &[u8]) -> Result<Armor, Error> {
+  const BEGIN: &[u8] = b"-----BEGIN ";
+  const END: &[u8] = b"-----END ";
+  const DASHES: &[u8] = b"-----";
+
+  let start = find(text, BEGIN).ok_or(Error::InvalidFormat)?;
+  let (header, mut text) = split_line(&text[start + BEGIN.len()..]);
+  let label = trim(header)
+    .strip_suffix(DASHES)
+    .ok_or(Error::InvalidFormat)?;
+  let label = core::str::from_utf8(label).map_err(|_| Error::InvalidFormat)?;
+
+  // Headers run until the first blank line.
+  let mut headers = Vec::new();
+  loop {
+    if text.is_empty() {
+      return Err(Error::InvalidFormat);
+    }
+
+    let (line, rest) = split_line(text);
+    text = rest;
+
+    let line = trim(line);
+    if line.is_empty() {
+      break;
+    }
+
+    let line = core::str::from_utf8(line).map_err(|_| Error::InvalidFormat)?;
+    let (key, value) = line.split_once(": ").ok_or(Error::InvalidFormat)?;
+    headers.push((key.to_string(), value.to_string()));
+  }
+
+  let mut body = Vec::with_capacity(text.len());
+  let mut checksum = None;
+  loop {
+    if text.is_empty() {
+      return Err(Error::InvalidFormat);
+    }
+
+    let (line, rest) = split_line(text);
+    text = rest;
+
+    let line = trim(line);
+    if let Some(footer) = line.strip_prefix(END) {
+      if footer.strip_suffix(DASHES) != Some(label.as_bytes()) {
+        return Err(Error::InvalidFormat);
+      }
+      break;
+    }
+
+    // The checksum is a line of exactly four characters after the `=`; this
+    // can't be confused for padding, since a padded line can't start with it.
+    match line {
+      [b'=', crc @ ..] if crc.len() == 4 => checksum = Some(crc),
+      _ if checksum.is_some() => return Err(Error::InvalidFormat),
+      _ => body.extend_from_slice(line),
+    }
+  }
+
+  let data = crate::decode(&body).map_err(Error::unlocated)?;
+  if let Some(checksum) = checksum {
+    let checksum = crate::decode(checksum).map_err(Error::unlocated)?;
+    if checksum[..] != crc24(&data).to_be_bytes()[1..] {
+      return Err(Error::InvalidChecksum);
+    }
+  }
+
+  Ok(Armor {
+    label: label.to_string(),
+    headers,
+    data,
+  })
+}
+
+/// Computes the CRC-24 checksum of `data`, as specified in
+/// [RFC 4880 §6.1](https://datatracker.ietf.org/doc/html/rfc4880#section-6.1).
+pub fn crc24(data: &[u8]) -> u32 {
+  const INIT: u32 = 0xb704ce;
+  const POLY: u32 = 0x1864cfb;
+
+  // A table of the CRC of each byte value, shifted into the top of the
+  // register, which lets us process a byte at a time.
+  const TABLE: [u32; 256] = array!(256; |i| {
+    let mut crc = (i as u32) << 16;
+    let mut bit = 0;
+    while bit < 8 {
+      crc <<= 1;
+      if crc & 0x1000000 != 0 {
+        crc ^= POLY;
+      }
+      bit += 1;
+    }
+    crc
+  });
+
+  let mut crc = INIT;
+  for &b in data {
+    crc = (crc << 8) ^ TABLE[(((crc >> 16) as u8) ^ b) as usize];
+  }
+  crc & 0xffffff
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn crc() {
+    assert_eq!(crc24(b""), 0xb704ce);
+    assert_eq!(crc24(b"123456789"), 0x21cf02);
+  }
+
+  #[test]
+  fn roundtrip() {
+    for len in [0, 1, 47, 48, 49, 1000] {
+      let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+      let text = encode("PGP PUBLIC KEY BLOCK", &[("Comment", "a: b")], &data);
+
+      let armor = decode(text.as_bytes()).unwrap();
+      assert_eq!(armor.label(), "PGP PUBLIC KEY BLOCK");
+      assert_eq!(armor.headers(), [("Comment".into(), "a: b".into())]);
+      assert_eq!(armor.data(), data);
+    }
+  }
+
+  #[test]
+  fn bad_checksum() {
+    let text = encode("PGP MESSAGE", &[], b"hello");
+    let text = text.replace("aGVsbG8=", "aGVsbm8=");
+    assert!(decode(text.as_bytes()).is_err());
+
+    let text = encode("PGP MESSAGE", &[], b"hello");
+    let no_crc = text
+      .lines()
+      .filter(|l| !l.starts_with('=') || l.len() != 5)
+      .collect::<Vec<_>>()
+      .join("\n");
+    assert_eq!(decode(no_crc.as_bytes()).unwrap().data(), b"hello");
+  }
+}
@@ -0,0 +1,240 @@
+//! Ascii85, as used by PostScript and PDF.
+//!
+//! Every four bytes are encoded as five characters in `!..=u`, with an
+//! all-zero group shortened to `z`.
+//!
+//! ```
+//! assert_eq!(vb64::ascii85::encode(b"hello"), "BOu!rDZ");
+//! assert_eq!(vb64::ascii85::decode(b"BOu!rDZ").unwrap(), b"hello");
+//!
+//! assert_eq!(vb64::ascii85::encode_adobe(b"\0\0\0\0x"), "<~zGQ~>");
+//! assert_eq!(vb64::ascii85::decode_adobe(b"<~zGQ~>").unwrap(), b"\0\0\0\0x");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::Simd;
+
+use crate::simd;
+use crate::Error;
+
+/// The number of groups we convert at a time.
+const LANES: usize = 8;
+
+/// Decodes some Ascii85 `data` to a fresh vector.
+///
+/// Whitespace is ignored.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as Ascii85.
+pub fn encode(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some Ascii85 data and appends it to `out`.
+pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  out.reserve(data.len() / 5 * 4);
+  let fail = |_| find_error(data);
+
+  // Gather up digits into groups of five, dropping whitespace and expanding
+  // `z`; anything else that isn't a digit gets caught by the range check.
+  let mut groups = [[0; 5]; LANES];
+  let mut len = 0;
+  let mut digit = 0;
+  for &c in data {
+    match c {
+      b'z' if digit == 0 => {
+        groups[len] = [b'!'; 5];
+        len += 1;
+      }
+      c if c.is_ascii_whitespace() => continue,
+      c => {
+        groups[len][digit] = c;
+        digit += 1;
+        if digit == 5 {
+          digit = 0;
+          len += 1;
+        }
+      }
+    }
+
+    if len == LANES {
+      decode_groups(&groups, out).map_err(fail)?;
+      len = 0;
+    }
+  }
+  decode_groups(&groups[..len], out).map_err(fail)?;
+
+  // A partial group is padded out with the largest digit, which ensures that
+  // the bytes we keep round-trip.
+  match digit {
+    0 => Ok(()),
+    1 => Err(Error::InvalidLength),
+    _ => {
+      let mut group = groups[len];
+      group[digit..].fill(b'u');
+
+      let start = out.len();
+      decode_groups(&[group], out).map_err(fail)?;
+      out.truncate(start + digit - 1);
+      Ok(())
+    }
+  }
+}
+
+/// Encodes arbitrary data as Ascii85 and appends it to `out`.
+pub fn encode_to(data: &[u8], out: &mut Vec<u8>) {
+  out.reserve(data.len().div_ceil(4) * 5);
+
+  let mut chunks = data.chunks_exact(4 * LANES);
+  for chunk in &mut chunks {
+    encode_groups(chunk, out, true);
+  }
+
+  let rest = chunks.remainder();
+  let (full, partial) = rest.split_at(rest.len() / 4 * 4);
+  encode_groups(full, out, true);
+
+  // A partial group is padded out with zeros, and then truncated to one more
+  // character than it has bytes. It is never shortened to `z`.
+  if !partial.is_empty() {
+    let mut group = [0; 4];
+    group[..partial.len()].copy_from_slice(partial);
+
+    let start = out.len();
+    encode_groups(&group, out, false);
+    out.truncate(start + partial.len() + 1);
+  }
+}
+
+/// Decodes some Ascii85 `data` framed by Adobe's `<~` and `~>` delimiters.
+///
+/// The leading `<~` is optional, as it is in PostScript, but the trailing `~>`
+/// is required. Whitespace around the delimiters is ignored.
+pub fn decode_adobe(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let data = crate::pem::trim(data);
+  let data = data.strip_suffix(b"~>").ok_or(Error::InvalidFormat)?;
+  let data = data.strip_prefix(b"<~").unwrap_or(data);
+  decode(data)
+}
+
+/// Encodes arbitrary data as Ascii85, framed by Adobe's `<~` and `~>`
+/// delimiters.
+pub fn encode_adobe(data: &[u8]) -> String {
+  let mut out = b"<~".to_vec();
+  encode_to(data, &mut out);
+  out.extend_from_slice(b"~>");
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes up to `LANES` groups of five digits.
+fn decode_groups(groups: &[[u8; 5]], out: &mut Vec<u8>) -> Result<(), Error> {
+  if groups.is_empty() {
+    return Ok(());
+  }
+
+  let mut digits = [Simd::splat(b'!'); 5];
+  for (i, group) in groups.iter().enumerate() {
+    for (d, &c) in group.iter().enumerate() {
+      digits[d][i] = c;
+    }
+  }
+
+  let (words, ok) = simd::decode85::<LANES>(digits);
+  if !ok {
+    return Err(Error::InvalidFormat);
+  }
+
+  for word in &words[..groups.len()] {
+    out.extend_from_slice(&word.to_be_bytes());
+  }
+  Ok(())
+}
+
+/// Finds the byte that made decoding `data` fail. If they're all digits, one of
+/// the groups must have been too large to fit in four bytes.
+#[cold]
+fn find_error(data: &[u8]) -> Error {
+  let mut digit = 0;
+  for (offset, &byte) in data.iter().enumerate() {
+    match byte {
+      b'z' if digit == 0 => {}
+      b'!'..=b'u' => digit = (digit + 1) % 5,
+      c if c.is_ascii_whitespace() => {}
+      _ => return Error::InvalidByte { offset, byte },
+    }
+  }
+  Error::InvalidFormat
+}
+
+/// Encodes up to `LANES` groups of four bytes; `data.len()` must be a multiple
+/// of four.
+fn encode_groups(data: &[u8], out: &mut Vec<u8>, allow_z: bool) {
+  if data.is_empty() {
+    return;
+  }
+
+  let mut words = [0; LANES];
+  for (word, group) in words.iter_mut().zip(data.chunks_exact(4)) {
+    *word = u32::from_be_bytes(group.try_into().unwrap());
+  }
+
+  let digits = simd::encode85(Simd::from_array(words));
+  for (i, &word) in words[..data.len() / 4].iter().enumerate() {
+    if word == 0 && allow_z {
+      out.push(b'z');
+    } else {
+      out.extend(digits.iter().map(|d| d[i]));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn known() {
+    let vectors: [(&[u8], &str); 5] = [
+      (b"Man is distinguished", "9jqo^BlbD-BleB1DJ+*+F(f,q"),
+      (b"\0\0\0\0", "z"),
+      (b"\0\0\0", "!!!!"),
+      (b"hello", "BOu!rDZ"),
+      (b"\0\0\0\0x", "zGQ"),
+    ];
+
+    for (dec, enc) in vectors {
+      assert_eq!(encode(dec), enc);
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec);
+    }
+  }
+
+  #[test]
+  fn roundtrip() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      let enc = encode(&dec);
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec, "case {i}");
+      assert_eq!(decode_adobe(encode_adobe(&dec).as_bytes()).unwrap(), dec);
+    }
+
+    let zeros = vec![0; 1000];
+    assert_eq!(encode(&zeros), "z".repeat(250));
+    assert_eq!(decode(encode(&zeros).as_bytes()).unwrap(), zeros);
+  }
+
+  #[test]
+  fn errors() {
+    assert!(decode(b"9jqo^B").is_err());
+    assert!(decode(b"9jq{^").is_err());
+    assert!(decode(b"9jzo^").is_err());
+    assert!(decode(b"s8W-\"").is_err());
+    assert!(decode_adobe(b"<~9jqo^").is_err());
+    assert_eq!(decode(b"9jq\no^ ").unwrap(), b"Man ");
+  }
+}
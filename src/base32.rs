@@ -0,0 +1,466 @@
+//! RFC 4648 base32 and base32hex, and Crockford's base32.
+//!
+//! These use the same SIMD kernels as the base64 codecs, except that every
+//! eight characters hold five bytes, rather than every four holding three.
+//!
+//! ```
+//! assert_eq!(vb64::base32::encode(b"hello"), "NBSWY3DP");
+//! assert_eq!(vb64::base32::decode(b"NBSWY3DP").unwrap(), b"hello");
+//!
+//! assert_eq!(vb64::base32::encode_hex(b"hi"), "D1KG====");
+//! assert_eq!(vb64::base32::decode_hex(b"D1KG").unwrap(), b"hi");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::LaneCount;
+use core::simd::Simd;
+use core::simd::SupportedLaneCount;
+
+use crate::read_slice_padded;
+use crate::simd;
+use crate::Error;
+
+/// Decodes some base32 `data` to a fresh vector.
+///
+/// This uses the alphabet from [RFC 4648 §6], `A-Z2-7`. Padding is optional.
+///
+/// [RFC 4648 §6]: https://datatracker.ietf.org/doc/html/rfc4648#section-6
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as base32.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some base32 data and appends it to `out`.
+pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  decode_with(data, out, &simd::BASE32, true)
+}
+
+/// Encodes arbitrary data as base32 and appends it to `out`.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_to(data: &[u8], out: &mut Vec<u8>) {
+  encode_with(data, out, &simd::BASE32, true)
+}
+
+/// Decodes some base32hex `data` to a fresh vector.
+///
+/// This uses the alphabet from [RFC 4648 §7], `0-9A-V`, which preserves the
+/// sort order of the encoded data. Padding is optional.
+///
+/// [RFC 4648 §7]: https://datatracker.ietf.org/doc/html/rfc4648#section-7
+pub fn decode_hex(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_hex_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as base32hex.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_hex(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_hex_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some base32hex data and appends it to `out`.
+pub fn decode_hex_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  decode_with(data, out, &simd::BASE32_HEX, true)
+}
+
+/// Encodes arbitrary data as base32hex and appends it to `out`.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_hex_to(data: &[u8], out: &mut Vec<u8>) {
+  encode_with(data, out, &simd::BASE32_HEX, true)
+}
+
+/// Decodes some Crockford base32 `data` to a fresh vector.
+///
+/// This uses the alphabet from [Crockford's spec], `0-9A-Z` without `ILOU`.
+/// Decoding is case-insensitive, and accepts `O` as `0`, and `I` and `L` as
+/// `1`. Padding is not allowed.
+///
+/// ```
+/// let data = vb64::base32::decode_crockford(b"d1jprv3f").unwrap();
+/// assert_eq!(data, b"hello");
+/// ```
+///
+/// [Crockford's spec]: https://www.crockford.com/base32.html
+pub fn decode_crockford(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_crockford_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as Crockford base32, without padding.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_crockford(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_crockford_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some Crockford base32 data and appends it to `out`.
+pub fn decode_crockford_to(
+  data: &[u8],
+  out: &mut Vec<u8>,
+) -> Result<(), Error> {
+  decode_with(data, out, &simd::CROCKFORD, false)
+}
+
+/// Encodes arbitrary data as Crockford base32 and appends it to `out`.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_crockford_to(data: &[u8], out: &mut Vec<u8>) {
+  encode_with(data, out, &simd::CROCKFORD, false)
+}
+
+/// Like [`decode_crockford()`], but expects `data` to end in a check symbol,
+/// which must match the decoded data.
+///
+/// ```
+/// let text = vb64::base32::encode_crockford_check(b"hello");
+/// assert_eq!(text, "D1JPRV3FJ");
+///
+/// let data = vb64::base32::decode_crockford_check(b"D1JPRV3FJ").unwrap();
+/// assert_eq!(data, b"hello");
+/// assert!(vb64::base32::decode_crockford_check(b"D1JPRV3FK").is_err());
+/// ```
+pub fn decode_crockford_check(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let (&byte, data) = data.split_last().ok_or(Error::InvalidLength)?;
+  let check = CHECK_SYMBOLS
+    .iter()
+    .position(|&c| c == byte.to_ascii_uppercase())
+    .ok_or(Error::InvalidByte {
+      offset: data.len(),
+      byte,
+    })?;
+
+  let out = decode_crockford(data)?;
+  if check != checksum(&out) {
+    return Err(Error::InvalidChecksum);
+  }
+  Ok(out)
+}
+
+/// Like [`encode_crockford()`], but appends a check symbol.
+///
+/// The check symbol is the decoded data, as a big-endian integer, modulo 37.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_crockford_check(data: &[u8]) -> String {
+  let mut out = encode_crockford(data);
+  out.push(CHECK_SYMBOLS[checksum(data)] as char);
+  out
+}
+
+/// The alphabet plus the five extra symbols used for the check symbol.
+const CHECK_SYMBOLS: &[u8; 37] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+
+fn checksum(data: &[u8]) -> usize {
+  data.iter().fold(0, |acc, &b| (acc * 256 + b as usize) % 37)
+}
+
+/// If `pad` is false, `=` is treated like any other invalid character.
+#[inline(always)]
+pub(crate) fn decode_with(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+  pad: bool,
+) -> Result<(), Error> {
+  let mut data = data;
+  if pad {
+    let pads = data.iter().rev().take_while(|&&c| c == b'=').count();
+    // Padding is optional, but if it's there, it has to fill out the last
+    // group, and only a group with a whole number of bytes in it at that.
+    if pads != 0 && (data.len() % 8 != 0 || !matches!(pads, 1 | 3 | 4 | 6)) {
+      return Err(Error::InvalidPadding);
+    }
+    data = &data[..data.len() - pads];
+  }
+
+  // These lengths would leave a partial byte at the end.
+  if matches!(data.len() % 8, 1 | 3 | 6) {
+    return Err(Error::InvalidLength);
+  }
+
+  if cfg!(target_feature = "avx2") {
+    decode_tunable::<32>(data, out, luts)
+  } else {
+    decode_tunable::<16>(data, out, luts)
+  }
+}
+
+#[inline(always)]
+pub(crate) fn encode_with(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+  pad: bool,
+) {
+  let start = out.len();
+  encode_tunable::<16>(data, out, luts);
+
+  if pad {
+    let rem = (out.len() - start) % 8;
+    if rem != 0 {
+      out.resize(out.len() + 8 - rem, b'=');
+    }
+  }
+}
+
+/// Decodes `data`, which must not contain any padding.
+fn decode_tunable<const N: usize>(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+) -> Result<(), Error>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  const { assert!(N % 8 == 0) };
+
+  if data.is_empty() {
+    return Ok(());
+  }
+
+  // NOTE: Always a full N bytes of slop so we can do full SIMD stores.
+  crate::reserve(out, decoded_len(data.len()).checked_add(N))?;
+  let out_start = out.spare_capacity_mut().as_mut_ptr().cast::<u8>();
+  let mut raw_out = out_start;
+
+  let mut chunks = data.chunks_exact(N);
+  let mut failed = false;
+  for chunk in &mut chunks {
+    let (decoded, ok) = simd::decode32(Simd::from_slice(chunk), luts);
+    failed |= !ok;
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(decoded);
+      raw_out = raw_out.add(decoded_len(N));
+    }
+  }
+
+  let rest = chunks.remainder();
+  if !rest.is_empty() {
+    let (decoded, ok) =
+      simd::decode32(unsafe { read_slice_padded::<N>(rest, luts.zero) }, luts);
+    failed |= !ok;
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(decoded);
+      raw_out = raw_out.add(decoded_len(rest.len()));
+    }
+  }
+
+  if failed {
+    return Err(crate::invalid_byte(data, luts));
+  }
+
+  unsafe {
+    let written = raw_out.offset_from(out_start);
+    out.set_len(out.len() + written as usize);
+  }
+
+  Ok(())
+}
+
+fn encode_tunable<const N: usize>(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+) where
+  LaneCount<N>: SupportedLaneCount,
+{
+  const { assert!(N % 8 == 0) };
+  let n5e = N / 8 * 5;
+
+  if data.is_empty() {
+    return;
+  }
+
+  // NOTE: Always a full N bytes of slop so we can do full SIMD stores.
+  let len = (data.len() / 5)
+    .checked_mul(8)
+    .and_then(|n| n.checked_add(N + 8));
+  out.reserve(len.expect("base32 output too large"));
+  let out_start = out.spare_capacity_mut().as_mut_ptr().cast::<u8>();
+  let mut raw_out = out_start;
+
+  // Each step consumes 5/8 of a vector, but we do full loads for as long as
+  // there's a full vector's worth of input left.
+  let mut rest = data;
+  while rest.len() >= N {
+    let encoded = simd::encode32(Simd::from_slice(&rest[..N]), luts);
+    rest = &rest[n5e..];
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(encoded);
+      raw_out = raw_out.add(N);
+    }
+  }
+
+  while !rest.is_empty() {
+    let chunk = &rest[..rest.len().min(n5e)];
+    let encoded =
+      simd::encode32(unsafe { read_slice_padded::<N>(chunk, 0) }, luts);
+    rest = &rest[chunk.len()..];
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(encoded);
+      raw_out = raw_out.add(encoded_len(chunk.len()));
+    }
+  }
+
+  unsafe {
+    let written = raw_out.offset_from(out_start);
+    out.set_len(out.len() + written as usize);
+  }
+}
+
+fn decoded_len(input: usize) -> usize {
+  input / 8 * 5 + [0, 0, 1, 1, 2, 3, 3, 4][input % 8]
+}
+
+fn encoded_len(input: usize) -> usize {
+  input / 5 * 8 + [0, 2, 4, 5, 7][input % 5]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rfc4648() {
+    let vectors = [
+      ("", "", ""),
+      ("f", "MY======", "CO======"),
+      ("fo", "MZXQ====", "CPNG===="),
+      ("foo", "MZXW6===", "CPNMU==="),
+      ("foob", "MZXW6YQ=", "CPNMUOG="),
+      ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+      ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+    ];
+
+    for (dec, enc, hex) in vectors {
+      assert_eq!(encode(dec.as_bytes()), enc);
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec.as_bytes());
+      assert_eq!(encode_hex(dec.as_bytes()), hex);
+      assert_eq!(decode_hex(hex.as_bytes()).unwrap(), dec.as_bytes());
+    }
+  }
+
+  #[test]
+  fn random() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      let enc = data_encoding::BASE32.encode(&dec);
+      assert_eq!(encode(&dec), enc, "case {i}");
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec, "case {i}");
+
+      let enc = data_encoding::BASE32HEX.encode(&dec);
+      assert_eq!(encode_hex(&dec), enc, "case {i}");
+      assert_eq!(decode_hex(enc.as_bytes()).unwrap(), dec, "case {i}");
+      assert_eq!(
+        decode_hex(enc.trim_end_matches('=').as_bytes()).unwrap(),
+        dec,
+        "case {i}"
+      );
+    }
+  }
+
+  #[test]
+  fn crockford() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      let enc = encode_crockford(&dec);
+      assert!(!enc.contains('='), "case {i}");
+      assert_eq!(decode_crockford(enc.as_bytes()).unwrap(), dec, "case {i}");
+      assert_eq!(
+        decode_crockford(enc.to_lowercase().as_bytes()).unwrap(),
+        dec,
+        "case {i}"
+      );
+
+      let enc = encode_crockford_check(&dec);
+      assert_eq!(decode_crockford_check(enc.as_bytes()).unwrap(), dec);
+    }
+
+    assert_eq!(
+      decode_crockford(b"0O1IiLl0").unwrap(),
+      b"\x00\x02\x10\x84\x20"
+    );
+    assert!(decode_crockford(b"0U").is_err());
+    assert!(decode_crockford(b"00======").is_err());
+    assert!(decode_crockford_check(b"").is_err());
+  }
+
+  #[test]
+  fn alphabet() {
+    for b in 0..255u8 {
+      let res = decode(&[b'A', b]);
+      if b.is_ascii_uppercase() || (b'2'..=b'7').contains(&b) {
+        assert!(res.is_ok(), "{b:#04x}");
+      } else {
+        assert!(res.is_err(), "{b:#04x}");
+      }
+    }
+  }
+
+  #[test]
+  fn bad_lengths() {
+    assert!(decode(b"A").is_err());
+    assert!(decode(b"AAA").is_err());
+    assert!(decode(b"AAAAAA==").is_err());
+    assert!(decode(b"AAAAAAAAA").is_err());
+  }
+
+  #[test]
+  fn bad_padding() {
+    for text in ["MZ======", "MZXQ====", "MZXW6===", "MZXW6YQ=", "MZXW6YTB"] {
+      assert!(decode(text.as_bytes()).is_ok(), "{text}");
+    }
+    for text in [
+      "MY=",
+      "MY=====",
+      "MZXW6YTB==",
+      "MZXW6Y==",
+      "M=======",
+      "========",
+      "MZXW6YTBMY=",
+    ] {
+      assert_eq!(
+        decode(text.as_bytes()),
+        Err(Error::InvalidPadding),
+        "{text}"
+      );
+    }
+    assert_eq!(decode_hex(b"CO======="), Err(Error::InvalidPadding));
+  }
+}
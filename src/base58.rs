@@ -0,0 +1,323 @@
+//! Base58, using the Bitcoin alphabet, and Base58Check.
+//!
+//! Unlike the other codecs in this crate, base58 treats its input as one big
+//! number, so every output digit depends on every input byte. This makes it
+//! quadratic and not amenable to SIMD, but we can still do much better than
+//! one digit at a time by working in large limbs.
+//!
+//! ```
+//! let text = vb64::base58::encode(b"Hello World!");
+//! assert_eq!(text, "2NEpo7TZRRrLZSi2U");
+//! assert_eq!(vb64::base58::decode(text.as_bytes()).unwrap(), b"Hello World!");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Error;
+
+/// The Bitcoin alphabet, which omits `0`, `O`, `I`, and `l`.
+const ALPHABET: &[u8; 58] =
+  b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Maps ASCII to digits; `!0` marks characters outside the alphabet.
+const DIGITS: [u8; 128] = {
+  let mut digits = [!0; 128];
+  let mut i = 0;
+  while i < 58 {
+    digits[ALPHABET[i] as usize] = i as u8;
+    i += 1;
+  }
+  digits
+};
+
+/// When encoding, we accumulate five digits per limb, which lets us shift in
+/// 32 bits at a time without overflowing a `u64`.
+const DIGITS_PER_LIMB: usize = 5;
+const LIMB: u64 = 58u64.pow(DIGITS_PER_LIMB as u32);
+
+/// Decodes some base58 `data` to a fresh vector.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as base58.
+pub fn encode(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some base58 data and appends it to `out`.
+///
+/// Each leading `1` decodes to a zero byte.
+pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  let zeros = data.iter().take_while(|&&c| c == b'1').count();
+  let data = &data[zeros..];
+
+  // Little-endian limbs of 32 bits each. We multiply in up to five digits at
+  // a time, which can't overflow a `u64`.
+  let mut limbs = Vec::<u32>::with_capacity(data.len() * 3 / 16 + 1);
+  for (i, chunk) in data.chunks(DIGITS_PER_LIMB).enumerate() {
+    let mut scale = 1;
+    let mut carry = 0;
+    for (j, &c) in chunk.iter().enumerate() {
+      let digit = DIGITS.get(c as usize).copied().unwrap_or(!0);
+      if digit == !0 {
+        return Err(Error::InvalidByte {
+          offset: zeros + i * DIGITS_PER_LIMB + j,
+          byte: c,
+        });
+      }
+      scale *= 58;
+      carry = carry * 58 + digit as u64;
+    }
+
+    for limb in &mut limbs {
+      carry += *limb as u64 * scale;
+      *limb = carry as u32;
+      carry >>= 32;
+    }
+    while carry != 0 {
+      limbs.push(carry as u32);
+      carry >>= 32;
+    }
+  }
+
+  out.resize(out.len() + zeros, 0);
+  let bytes = limbs.iter().rev().flat_map(|l| l.to_be_bytes());
+  out.extend(bytes.skip_while(|&b| b == 0));
+  Ok(())
+}
+
+/// Encodes arbitrary data as base58 and appends it to `out`.
+///
+/// Each leading zero byte encodes to a `1`.
+pub fn encode_to(data: &[u8], out: &mut Vec<u8>) {
+  let zeros = data.iter().take_while(|&&b| b == 0).count();
+  let data = &data[zeros..];
+
+  // Little-endian limbs of `LIMB`. We shift in 32 bits at a time, except at
+  // the very start, where we shift in whatever is left over so that the rest
+  // of the input lines up.
+  let mut limbs = Vec::<u64>::with_capacity(data.len() * 138 / 500 + 1);
+  let (head, rest) = data.split_at(data.len() % 4);
+  let words = core::iter::once(head)
+    .filter(|h| !h.is_empty())
+    .chain(rest.chunks_exact(4));
+  for word in words {
+    let shift = word.len() as u32 * 8;
+    let mut carry = word.iter().fold(0, |acc, &b| acc << 8 | b as u64);
+
+    for limb in &mut limbs {
+      carry += *limb << shift;
+      *limb = carry % LIMB;
+      carry /= LIMB;
+    }
+    while carry != 0 {
+      limbs.push(carry % LIMB);
+      carry /= LIMB;
+    }
+  }
+
+  out.resize(out.len() + zeros, b'1');
+  let start = out.len();
+  for &limb in limbs.iter().rev() {
+    let mut limb = limb;
+    let mut digits = [0; DIGITS_PER_LIMB];
+    for digit in digits.iter_mut().rev() {
+      *digit = ALPHABET[(limb % 58) as usize];
+      limb /= 58;
+    }
+
+    // Only the most significant limb has leading zeros to strip.
+    if out.len() == start {
+      let nonzero = digits.iter().position(|&d| d != b'1').unwrap_or(0);
+      out.extend_from_slice(&digits[nonzero..]);
+    } else {
+      out.extend_from_slice(&digits);
+    }
+  }
+}
+
+/// Decodes some Base58Check `data` to a fresh vector.
+///
+/// The last four decoded bytes must be the first four bytes of the double
+/// SHA-256 of the rest, which are then removed. Any version byte is left in
+/// place.
+///
+/// ```
+/// let addr = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+/// let data = vb64::base58::decode_check(addr.as_bytes()).unwrap();
+/// assert_eq!(data[0], 0);
+/// assert_eq!(vb64::base58::encode_check(&data), addr);
+/// ```
+pub fn decode_check(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = decode(data)?;
+  if out.len() < 4 {
+    return Err(Error::InvalidLength);
+  }
+
+  let (payload, check) = out.split_at(out.len() - 4);
+  if check != checksum(payload) {
+    return Err(Error::InvalidChecksum);
+  }
+  out.truncate(out.len() - 4);
+  Ok(out)
+}
+
+/// Encodes arbitrary data as Base58Check, by appending a checksum and then
+/// encoding as base58.
+pub fn encode_check(data: &[u8]) -> String {
+  let mut buf = data.to_vec();
+  buf.extend_from_slice(&checksum(data));
+  encode(&buf)
+}
+
+/// The first four bytes of the double SHA-256 of `data`.
+fn checksum(data: &[u8]) -> [u8; 4] {
+  let hash = sha256(&sha256(data));
+  [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// A minimal SHA-256, per FIPS 180-4. This is only used for Base58Check's
+/// checksum, so it doesn't need to be fast.
+fn sha256(data: &[u8]) -> [u8; 32] {
+  const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1,
+    0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3,
+    0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786,
+    0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147,
+    0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+    0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a,
+    0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208,
+    0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+  ];
+
+  let mut state: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c,
+    0x1f83d9ab, 0x5be0cd19,
+  ];
+
+  let mut padded = data.to_vec();
+  padded.push(0x80);
+  while padded.len() % 64 != 56 {
+    padded.push(0);
+  }
+  padded.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+  for block in padded.chunks_exact(64) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.chunks_exact(4).enumerate() {
+      w[i] = u32::from_be_bytes(word.try_into().unwrap());
+    }
+    for i in 16..64 {
+      let s0 = w[i - 15].rotate_right(7)
+        ^ w[i - 15].rotate_right(18)
+        ^ (w[i - 15] >> 3);
+      let s1 = w[i - 2].rotate_right(17)
+        ^ w[i - 2].rotate_right(19)
+        ^ (w[i - 2] >> 10);
+      w[i] = w[i - 16]
+        .wrapping_add(s0)
+        .wrapping_add(w[i - 7])
+        .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    for i in 0..64 {
+      let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+      let ch = (e & f) ^ (!e & g);
+      let t1 = h
+        .wrapping_add(s1)
+        .wrapping_add(ch)
+        .wrapping_add(K[i])
+        .wrapping_add(w[i]);
+      let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+      let maj = (a & b) ^ (a & c) ^ (b & c);
+      let t2 = s0.wrapping_add(maj);
+
+      h = g;
+      g = f;
+      f = e;
+      e = d.wrapping_add(t1);
+      d = c;
+      c = b;
+      b = a;
+      a = t1.wrapping_add(t2);
+    }
+
+    for (s, x) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+      *s = s.wrapping_add(x);
+    }
+  }
+
+  let mut out = [0; 32];
+  for (chunk, s) in out.chunks_exact_mut(4).zip(state) {
+    chunk.copy_from_slice(&s.to_be_bytes());
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn known() {
+    let vectors: [(&[u8], &str); 5] = [
+      (b"", ""),
+      (b"\0", "1"),
+      (b"Hello World!", "2NEpo7TZRRrLZSi2U"),
+      (b"\0\0\x28\x7f\xb4\xcd", "11233QC4"),
+      (b"\xff", "5Q"),
+    ];
+
+    for (dec, enc) in vectors {
+      assert_eq!(encode(dec), enc);
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec);
+    }
+  }
+
+  #[test]
+  fn roundtrip() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      let enc = encode(&dec);
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec, "case {i}");
+    }
+
+    for len in 0..64 {
+      let data = (0..len).map(|i| (i * 37) as u8).collect::<Vec<_>>();
+      assert_eq!(decode(encode(&data).as_bytes()).unwrap(), data);
+    }
+  }
+
+  #[test]
+  fn check() {
+    let data = decode_check(b"1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").unwrap();
+    assert_eq!(
+      data,
+      b"\x00\x77\xbf\xf2\x0c\x60\xe5\x22\xdf\xaa\x33\x50\xc3\x9b\x03\x0a\x5d\x00\x4e\x83\x9a"
+    );
+    assert!(decode_check(b"1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3").is_err());
+    assert!(decode_check(b"1").is_err());
+
+    assert_eq!(
+      sha256(b"abc"),
+      *b"\xba\x78\x16\xbf\x8f\x01\xcf\xea\x41\x41\x40\xde\x5d\xae\x22\x23\
+         \xb0\x03\x61\xa3\x96\x17\x7a\x9c\xb4\x10\xff\x61\xf2\x00\x15\xad"
+    );
+  }
+
+  #[test]
+  fn errors() {
+    assert!(decode(b"0").is_err());
+    assert!(decode(b"Il").is_err());
+    assert!(decode(b"2NEpo7TZRRrLZSi2U\xff").is_err());
+  }
+}
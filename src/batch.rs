@@ -0,0 +1,185 @@
+//! Decoding and encoding lots of small values at once.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::Error;
+
+/// Decodes each of `inputs` as base64, like [`crate::decode()`] would.
+///
+/// Rather than allocating a vector per item, this sizes a single buffer for
+/// all of them up front and decodes them into it back to back, which makes a
+/// big difference when there are many small items, such as an array of blobs
+/// in a JSON document. A bad item doesn't stop the others from being decoded.
+///
+/// ```
+/// let batch = vb64::decode_many(&["aGk=", "!!", "eW8="]);
+/// assert_eq!(batch.len(), 3);
+/// assert_eq!(batch.get(0).unwrap().unwrap(), b"hi");
+/// assert!(batch.get(1).unwrap().is_err());
+/// assert_eq!(batch.get(2).unwrap().unwrap(), b"yo");
+/// ```
+pub fn decode_many<T: AsRef<[u8]>>(inputs: &[T]) -> DecodedBatch {
+  let len = inputs.iter().try_fold(0usize, |len, input| {
+    len.checked_add(crate::max_decoded_len(input.as_ref().len()))
+  });
+
+  let mut buf = Vec::new();
+  let mut items = Vec::with_capacity(inputs.len());
+  if let Err(e) = crate::reserve(&mut buf, len.and_then(|n| n.checked_add(32)))
+  {
+    items.resize(inputs.len(), Err(e));
+    return DecodedBatch { buf, items };
+  }
+
+  for input in inputs {
+    let start = buf.len();
+    items.push(crate::decode_to(input, &mut buf).map(|n| start..start + n));
+  }
+  DecodedBatch { buf, items }
+}
+
+/// Encodes each of `inputs` as standard, padded base64.
+///
+/// Like [`decode_many()`], this encodes everything into one buffer.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+///
+/// ```
+/// let batch = vb64::encode_many(&["hi", "yo"]);
+/// assert_eq!(batch.iter().collect::<Vec<_>>(), ["aGk=", "eW8="]);
+/// ```
+pub fn encode_many<T: AsRef<[u8]>>(inputs: &[T]) -> EncodedBatch {
+  let len = inputs.iter().try_fold(0usize, |len, input| {
+    len.checked_add(crate::encoded_len(input.as_ref().len(), true)?)
+  });
+
+  let mut buf = Vec::new();
+  crate::reserve(&mut buf, len.and_then(|n| n.checked_add(32)))
+    .expect("base64 output too large");
+
+  let mut ends = Vec::with_capacity(inputs.len());
+  for input in inputs {
+    crate::encode_to(input, &mut buf);
+    ends.push(buf.len());
+  }
+
+  // SAFETY: Encoders only produce ASCII.
+  let buf = unsafe { String::from_utf8_unchecked(buf) };
+  EncodedBatch { buf, ends }
+}
+
+/// The results of [`decode_many()`].
+#[derive(Clone, Debug)]
+pub struct DecodedBatch {
+  buf: Vec<u8>,
+  items: Vec<Result<Range<usize>, Error>>,
+}
+
+impl DecodedBatch {
+  /// Returns how many items there are.
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+
+  /// Returns whether there are no items.
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  /// Returns how the `i`th item decoded, or `None` if it's out of bounds.
+  pub fn get(&self, i: usize) -> Option<Result<&[u8], Error>> {
+    let item = self.items.get(i)?;
+    Some(item.clone().map(|range| &self.buf[range]))
+  }
+
+  /// Returns how each item decoded, in order.
+  pub fn iter(&self) -> impl Iterator<Item = Result<&[u8], Error>> + '_ {
+    (0..self.len()).map(|i| self.get(i).unwrap())
+  }
+
+  /// Returns the decoded bytes of every item that decoded successfully, back
+  /// to back.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.buf
+  }
+}
+
+/// The results of [`encode_many()`].
+#[derive(Clone, Debug)]
+pub struct EncodedBatch {
+  buf: String,
+  ends: Vec<usize>,
+}
+
+impl EncodedBatch {
+  /// Returns how many items there are.
+  pub fn len(&self) -> usize {
+    self.ends.len()
+  }
+
+  /// Returns whether there are no items.
+  pub fn is_empty(&self) -> bool {
+    self.ends.is_empty()
+  }
+
+  /// Returns the `i`th item's encoding, or `None` if it's out of bounds.
+  pub fn get(&self, i: usize) -> Option<&str> {
+    let end = *self.ends.get(i)?;
+    let start = i.checked_sub(1).map_or(0, |j| self.ends[j]);
+    Some(&self.buf[start..end])
+  }
+
+  /// Returns each item's encoding, in order.
+  pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+    (0..self.len()).map(|i| self.get(i).unwrap())
+  }
+
+  /// Returns the encodings of every item, back to back.
+  pub fn as_str(&self) -> &str {
+    &self.buf
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn batch() {
+    let tests = crate::tests::random_tests();
+    let mut inputs = tests.iter().map(|(_, enc, _)| *enc).collect::<Vec<_>>();
+    inputs.insert(3, b"QUJD!");
+    inputs.insert(7, b"");
+    inputs.insert(9, b"Q");
+
+    let batch = decode_many(&inputs);
+    assert_eq!(batch.len(), inputs.len());
+    for (i, (input, got)) in inputs.iter().zip(batch.iter()).enumerate() {
+      match crate::decode(input) {
+        Ok(want) => assert_eq!(got.unwrap(), want, "item {i}"),
+        Err(want) => {
+          assert_eq!(got.unwrap_err().to_string(), want.to_string(), "item {i}")
+        }
+      }
+    }
+    assert!(batch.get(inputs.len()).is_none());
+
+    let decoded = tests.iter().map(|(_, _, dec)| dec).collect::<Vec<_>>();
+    let batch = encode_many(&decoded);
+    assert_eq!(batch.len(), decoded.len());
+    for (i, got) in batch.iter().enumerate() {
+      assert_eq!(got.as_bytes(), tests[i].1, "case {i}");
+    }
+    assert_eq!(
+      batch.as_str().len(),
+      tests.iter().map(|t| t.1.len()).sum::<usize>()
+    );
+
+    assert!(decode_many::<&str>(&[]).is_empty());
+    assert!(encode_many::<&str>(&[]).is_empty());
+  }
+}
@@ -0,0 +1,98 @@
+//! Decoding and encoding straight into a `bumpalo` arena.
+//!
+//! Like `Bump::alloc()`, these hand out fresh, unaliased memory through a
+//! shared reference to the arena, hence the `mut_from_ref` allows.
+
+use core::alloc::Layout;
+use core::mem::MaybeUninit;
+
+use bumpalo::Bump;
+
+use crate::Error;
+
+/// Decodes some base64 `data` into a slice allocated in `bump`.
+///
+/// Requires the `bumpalo` feature. The slice is allocated at exactly the
+/// decoded length, so nothing is copied or wasted. If decoding fails, the
+/// space is still taken up until the arena is reset.
+///
+/// ```
+/// let bump = bumpalo::Bump::new();
+/// let data = vb64::decode_in_bump(&bump, b"aGVsbG8=").unwrap();
+/// assert_eq!(data, b"hello");
+/// ```
+#[allow(clippy::mut_from_ref)]
+pub fn decode_in_bump(
+  bump: &Bump,
+  data: impl AsRef<[u8]>,
+) -> Result<&mut [u8], Error> {
+  let data = data.as_ref();
+  let out = alloc(bump, crate::decoded_len(data))?;
+  let len = crate::decode_to_uninit(data, out)?;
+  // SAFETY: The decoder initialized the first `len` bytes.
+  Ok(unsafe { &mut *(&mut out[..len] as *mut [MaybeUninit<u8>] as *mut [u8]) })
+}
+
+/// Encodes arbitrary data as base64 into a string allocated in `bump`.
+///
+/// Requires the `bumpalo` feature.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+///
+/// ```
+/// let bump = bumpalo::Bump::new();
+/// assert_eq!(vb64::encode_in_bump(&bump, b"hello"), "aGVsbG8=");
+/// ```
+#[allow(clippy::mut_from_ref)]
+pub fn encode_in_bump(bump: &Bump, data: impl AsRef<[u8]>) -> &mut str {
+  let data = data.as_ref();
+  let out = crate::encoded_len(data.len(), true)
+    .ok_or(Error::CapacityOverflow)
+    .and_then(|len| alloc(bump, len))
+    .expect("base64 output too large");
+  let len = crate::encode_to_uninit(data, out).unwrap();
+  // SAFETY: The encoder initialized the first `len` bytes, all with ASCII.
+  unsafe {
+    let out = &mut *(&mut out[..len] as *mut [MaybeUninit<u8>] as *mut [u8]);
+    core::str::from_utf8_unchecked_mut(out)
+  }
+}
+
+/// Allocates room for `len` bytes in `bump`.
+#[allow(clippy::mut_from_ref)]
+fn alloc(bump: &Bump, len: usize) -> Result<&mut [MaybeUninit<u8>], Error> {
+  let layout = Layout::array::<u8>(len).map_err(|_| Error::CapacityOverflow)?;
+  let ptr = bump
+    .try_alloc_layout(layout)
+    .map_err(|_| Error::CapacityOverflow)?;
+  // SAFETY: `ptr` points to `len` fresh bytes that live as long as `bump`
+  // does, and `MaybeUninit` doesn't care what's in them.
+  Ok(unsafe { core::slice::from_raw_parts_mut(ptr.as_ptr().cast(), len) })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bump() {
+    let bump = Bump::new();
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let decoded = decode_in_bump(&bump, enc).unwrap();
+      assert_eq!(decoded, dec, "case {i}");
+      let encoded = encode_in_bump(&bump, &dec);
+      assert_eq!(encoded.as_bytes(), enc, "case {i}");
+    }
+
+    assert!(decode_in_bump(&bump, b"QUJD!").is_err());
+    assert!(decode_in_bump(&bump, b"QUJ!").is_err());
+
+    // Neighboring allocations mustn't get clobbered by vector stores.
+    let bump = Bump::new();
+    let a = decode_in_bump(&bump, b"QUJD").unwrap();
+    let b = decode_in_bump(&bump, b"REVG").unwrap();
+    assert_eq!((&*a, &*b), (&b"ABC"[..], &b"DEF"[..]));
+  }
+}
@@ -0,0 +1,103 @@
+//! Decoding and encoding with the `bytes` crate's buffers.
+//!
+//! Requires the `bytes` feature. These write straight into a [`BytesMut`]'s
+//! spare capacity, so data that is headed for the rest of a `bytes`-based
+//! pipeline doesn't need to be copied out of a `Vec` first. Anything that
+//! derefs to `[u8]`, including [`Bytes`], can be used as input.
+
+use ::bytes::BufMut;
+use ::bytes::Bytes;
+use ::bytes::BytesMut;
+
+use crate::Error;
+
+/// Decodes some base64 `data` into a new [`Bytes`].
+///
+/// ```
+/// let data = vb64::decode_bytes(bytes::Bytes::from("aGVsbG8=")).unwrap();
+/// assert_eq!(data, "hello");
+/// ```
+pub fn decode_bytes(data: impl AsRef<[u8]>) -> Result<Bytes, Error> {
+  let mut out = BytesMut::new();
+  decode_to_bytes(data, &mut out)?;
+  Ok(out.freeze())
+}
+
+/// Decodes some base64 `data` and appends it to `out`, returning how many
+/// bytes were appended.
+///
+/// If decoding fails, `out` is left as it was, although it may have grown
+/// its capacity.
+///
+/// ```
+/// let mut buf = bytes::BytesMut::from("data: ");
+/// vb64::decode_to_bytes(b"aGVsbG8=", &mut buf).unwrap();
+/// assert_eq!(buf, "data: hello");
+/// ```
+pub fn decode_to_bytes(
+  data: impl AsRef<[u8]>,
+  out: &mut BytesMut,
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  out.reserve(crate::decoded_len(data));
+  // SAFETY: The decoder only ever writes initialized bytes.
+  let spare = unsafe { out.chunk_mut().as_uninit_slice_mut() };
+  let len = crate::decode_to_uninit(data, spare)?;
+  // SAFETY: The decoder initialized this many bytes.
+  unsafe { out.advance_mut(len) };
+  Ok(len)
+}
+
+/// Encodes arbitrary data as base64 into a new [`Bytes`].
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+///
+/// ```
+/// assert_eq!(vb64::encode_bytes(b"hello"), "aGVsbG8=");
+/// ```
+pub fn encode_bytes(data: impl AsRef<[u8]>) -> Bytes {
+  let mut out = BytesMut::new();
+  encode_to_bytes(data, &mut out);
+  out.freeze()
+}
+
+/// Encodes arbitrary data as base64 and appends it to `out`, returning how
+/// many bytes were appended.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_to_bytes(data: impl AsRef<[u8]>, out: &mut BytesMut) -> usize {
+  let data = data.as_ref();
+  let len =
+    crate::encoded_len(data.len(), true).expect("base64 output too large");
+  out.reserve(len);
+  // SAFETY: The encoder only ever writes initialized bytes.
+  let spare = unsafe { out.chunk_mut().as_uninit_slice_mut() };
+  let len = crate::encode_to_uninit(data, spare).unwrap();
+  // SAFETY: The encoder initialized this many bytes.
+  unsafe { out.advance_mut(len) };
+  len
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bytes() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      assert_eq!(decode_bytes(enc).unwrap(), dec, "case {i}");
+      assert_eq!(encode_bytes(&dec), enc, "case {i}");
+    }
+
+    let mut buf = BytesMut::from("xyz");
+    assert!(decode_to_bytes(b"QUJD!", &mut buf).is_err());
+    assert_eq!(buf, "xyz");
+    assert_eq!(encode_to_bytes(b"ABCD", &mut buf), 8);
+    assert_eq!(decode_to_bytes(b"QUJD", &mut buf).unwrap(), 3);
+    assert_eq!(buf, "xyzQUJDRA==ABC");
+  }
+}
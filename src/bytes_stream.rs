@@ -0,0 +1,263 @@
+//! Encoding and decoding streams of [`Bytes`], such as HTTP or gRPC bodies.
+//!
+//! Requires the `stream` feature. Chunks can be split anywhere; whatever
+//! doesn't make a whole group is carried over to the next one.
+
+use std::pin::Pin;
+use std::task::ready;
+use std::task::Context;
+use std::task::Poll;
+
+use ::bytes::Bytes;
+use futures_core::FusedStream;
+use futures_core::Stream;
+
+use crate::Decoder;
+use crate::Encoder;
+use crate::Engine;
+use crate::Error;
+
+/// A stream that encodes the chunks of another stream as base64.
+///
+/// Each chunk of input produces a chunk of text, except for ones too short to
+/// complete a group, which are held back until the next one.
+pub struct EncodeStream<'a, S> {
+  inner: S,
+  encoder: Encoder<'a>,
+  done: bool,
+}
+
+impl<S> EncodeStream<'static, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  /// Creates a stream that encodes standard, padded base64.
+  pub fn new(inner: S) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, S> EncodeStream<'a, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  /// Creates a stream that encodes with the given engine's alphabet, padding,
+  /// and line wrapping.
+  pub fn with_engine(inner: S, engine: &'a Engine) -> Self {
+    Self {
+      inner,
+      encoder: Encoder::with_engine(engine),
+      done: false,
+    }
+  }
+
+  /// Returns the inner stream, discarding any data that was taken from it but
+  /// not yet encoded.
+  pub fn into_inner(self) -> S {
+    self.inner
+  }
+}
+
+impl<S> Stream for EncodeStream<'_, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  type Item = Bytes;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Bytes>> {
+    let this = self.get_mut();
+    let mut out = Vec::new();
+    while !this.done {
+      match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+        Some(chunk) => this.encoder.update(chunk, &mut out),
+        None => {
+          this.done = true;
+          this.encoder.finish(&mut out)
+        }
+      };
+      if !out.is_empty() {
+        return Poll::Ready(Some(out.into()));
+      }
+    }
+    Poll::Ready(None)
+  }
+}
+
+impl<S> FusedStream for EncodeStream<'_, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  fn is_terminated(&self) -> bool {
+    self.done
+  }
+}
+
+/// A stream that decodes the base64 chunks of another stream.
+///
+/// Once an error is found, it is yielded, and the stream ends. Its offset
+/// counts from the start of the first chunk.
+pub struct DecodeStream<'a, S> {
+  inner: S,
+  decoder: Decoder<'a>,
+  done: bool,
+}
+
+impl<S> DecodeStream<'static, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  /// Creates a stream that decodes standard base64.
+  pub fn new(inner: S) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, S> DecodeStream<'a, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  /// Creates a stream that decodes with the given engine's rules.
+  pub fn with_engine(inner: S, engine: &'a Engine) -> Self {
+    Self::with_decoder(inner, Decoder::with_engine(engine))
+  }
+
+  /// Creates a stream that feeds its input through `decoder`, such as one
+  /// with a [limit][Decoder::limit()].
+  pub fn with_decoder(inner: S, decoder: Decoder<'a>) -> Self {
+    Self {
+      inner,
+      decoder,
+      done: false,
+    }
+  }
+
+  /// Returns the inner stream, discarding any text that was taken from it but
+  /// not yet decoded.
+  pub fn into_inner(self) -> S {
+    self.inner
+  }
+}
+
+impl<S> Stream for DecodeStream<'_, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  type Item = Result<Bytes, Error>;
+
+  fn poll_next(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+  ) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+    let mut out = Vec::new();
+    while !this.done {
+      let result = match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+        Some(chunk) => this.decoder.update(chunk, &mut out),
+        None => {
+          this.done = true;
+          this.decoder.finish(&mut out)
+        }
+      };
+      if let Err(e) = result {
+        this.done = true;
+        return Poll::Ready(Some(Err(e)));
+      }
+      if !out.is_empty() {
+        return Poll::Ready(Some(Ok(out.into())));
+      }
+    }
+    Poll::Ready(None)
+  }
+}
+
+impl<S> FusedStream for DecodeStream<'_, S>
+where
+  S: Stream + Unpin,
+  S::Item: AsRef<[u8]>,
+{
+  fn is_terminated(&self) -> bool {
+    self.done
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::task::Waker;
+
+  use super::*;
+
+  /// A stream of `.1`-byte chunks of `.0`, which is pending every other poll.
+  struct Chunks<'a>(&'a [u8], usize, bool);
+
+  impl<'a> Stream for Chunks<'a> {
+    type Item = &'a [u8];
+
+    fn poll_next(
+      mut self: Pin<&mut Self>,
+      _: &mut Context,
+    ) -> Poll<Option<&'a [u8]>> {
+      self.2 = !self.2;
+      if self.2 {
+        return Poll::Pending;
+      }
+      if self.0.is_empty() {
+        return Poll::Ready(None);
+      }
+      let (chunk, rest) = self.0.split_at(self.0.len().min(self.1));
+      self.0 = rest;
+      Poll::Ready(Some(chunk))
+    }
+  }
+
+  /// Polls `s` to the end, collecting what it yields.
+  fn collect<S: Stream + Unpin>(mut s: S) -> Vec<S::Item> {
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut items = Vec::new();
+    loop {
+      match Pin::new(&mut s).poll_next(&mut cx) {
+        Poll::Ready(Some(item)) => items.push(item),
+        Poll::Ready(None) => return items,
+        Poll::Pending => {}
+      }
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      for n in [1, 5, 4096] {
+        let chunks = collect(EncodeStream::new(Chunks(&dec, n, false)));
+        assert!(chunks.iter().all(|c| !c.is_empty()));
+        assert_eq!(chunks.concat(), enc, "case {i}, chunks of {n}");
+
+        let chunks = collect(DecodeStream::new(Chunks(enc, n, false)));
+        let chunks = chunks.into_iter().collect::<Result<Vec<_>, _>>();
+        assert_eq!(chunks.unwrap().concat(), dec, "case {i}, chunks of {n}");
+      }
+    }
+  }
+
+  #[test]
+  fn errors() {
+    let items =
+      collect(DecodeStream::new(Chunks(b"aGVsbG8gd2!ybGQ=", 3, false)));
+    assert_eq!(
+      items.last().unwrap().clone().unwrap_err(),
+      Error::InvalidByte {
+        offset: 10,
+        byte: b'!'
+      }
+    );
+    assert!(items[..items.len() - 1].iter().all(Result::is_ok));
+
+    let items = collect(DecodeStream::new(Chunks(b"aGVsb", 2, false)));
+    assert!(items.last().unwrap().is_err());
+  }
+}
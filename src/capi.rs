@@ -0,0 +1,211 @@
+//! A C API for the standard codecs.
+//!
+//! Requires the `capi` feature. Build the `vb64-capi` crate, in `capi/`, for a
+//! `cdylib` and a `staticlib` to link against; `include/vb64.h` declares
+//! everything here. Regenerate it with `cbindgen --crate vb64 -o
+//! include/vb64.h` after changing this file.
+//!
+//! Every function takes its input as a pointer and length, and writes to a
+//! caller-owned buffer; nothing is ever allocated on the caller's behalf.
+
+use core::slice;
+
+use crate::Error;
+
+/// The result of a call into the C API.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Vb64Status {
+  /// The call succeeded.
+  Ok = 0,
+  /// A byte that isn't part of the alphabet; see [`Error::InvalidByte`].
+  InvalidByte = 1,
+  /// See [`Error::InvalidLength`].
+  InvalidLength = 2,
+  /// See [`Error::InvalidPadding`].
+  InvalidPadding = 3,
+  /// See [`Error::InvalidTrailingBits`].
+  InvalidTrailingBits = 4,
+  /// The output buffer is too short.
+  BufferTooSmall = 5,
+  /// A pointer argument was null when it wasn't allowed to be.
+  NullPointer = 6,
+  /// Any other error.
+  Other = 255,
+}
+
+impl From<Error> for Vb64Status {
+  fn from(e: Error) -> Self {
+    match e {
+      Error::InvalidByte { .. } => Self::InvalidByte,
+      Error::InvalidLength => Self::InvalidLength,
+      Error::InvalidPadding => Self::InvalidPadding,
+      Error::InvalidTrailingBits => Self::InvalidTrailingBits,
+      Error::BufferTooSmall => Self::BufferTooSmall,
+      _ => Self::Other,
+    }
+  }
+}
+
+/// Returns how long the padded base64 encoding of `len` bytes is, or zero if
+/// that would overflow.
+#[no_mangle]
+pub extern "C" fn vb64_encoded_len(len: usize) -> usize {
+  crate::encoded_len(len, true).unwrap_or(0)
+}
+
+/// Returns an upper bound on how many bytes `len` bytes of base64 decode to.
+#[no_mangle]
+pub extern "C" fn vb64_max_decoded_len(len: usize) -> usize {
+  crate::max_decoded_len(len)
+}
+
+/// Encodes `src_len` bytes at `src` as standard, padded base64 into `dst`.
+///
+/// On success, writes the length of the encoding to `*dst_len`. `dst_cap`
+/// must be at least [`vb64_encoded_len()`] of `src_len`.
+///
+/// # Safety
+///
+/// `src` must be valid for reads of `src_len` bytes, `dst` for writes of
+/// `dst_cap` bytes, and `dst_len` for a write of a `size_t`. `src` and `dst`
+/// may only be null if their lengths are zero.
+#[no_mangle]
+pub unsafe extern "C" fn vb64_encode(
+  src: *const u8,
+  src_len: usize,
+  dst: *mut u8,
+  dst_cap: usize,
+  dst_len: *mut usize,
+) -> Vb64Status {
+  let buffers =
+    unsafe { (input(src, src_len), output(dst, dst_cap), dst_len.as_mut()) };
+  let (Some(src), Some(dst), Some(dst_len)) = buffers else {
+    return Vb64Status::NullPointer;
+  };
+
+  match crate::encode_to_slice(src, dst) {
+    Ok(len) => {
+      *dst_len = len;
+      Vb64Status::Ok
+    }
+    Err(e) => e.into(),
+  }
+}
+
+/// Decodes `src_len` bytes of standard base64 at `src` into `dst`, following
+/// the same rules as `vb64::decode()`.
+///
+/// On success, writes the length of the decoded data to `*dst_len`. If the
+/// input contains an invalid byte, writes that byte's offset there instead.
+/// `dst_cap` must be at least [`vb64_max_decoded_len()`] of `src_len`.
+///
+/// # Safety
+///
+/// `src` must be valid for reads of `src_len` bytes, `dst` for writes of
+/// `dst_cap` bytes, and `dst_len` for a write of a `size_t`. `src` and `dst`
+/// may only be null if their lengths are zero.
+#[no_mangle]
+pub unsafe extern "C" fn vb64_decode(
+  src: *const u8,
+  src_len: usize,
+  dst: *mut u8,
+  dst_cap: usize,
+  dst_len: *mut usize,
+) -> Vb64Status {
+  let buffers =
+    unsafe { (input(src, src_len), output(dst, dst_cap), dst_len.as_mut()) };
+  let (Some(src), Some(dst), Some(dst_len)) = buffers else {
+    return Vb64Status::NullPointer;
+  };
+
+  match crate::decode_to_slice(src, dst) {
+    Ok(len) => {
+      *dst_len = len;
+      Vb64Status::Ok
+    }
+    Err(e) => {
+      if let Some(offset) = e.offset() {
+        *dst_len = offset;
+      }
+      e.into()
+    }
+  }
+}
+
+/// Views a C buffer as a slice, allowing null when it's empty.
+unsafe fn input<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+  match (ptr.is_null(), len) {
+    (true, 0) => Some(&[]),
+    (true, _) => None,
+    (false, _) => Some(unsafe { slice::from_raw_parts(ptr, len) }),
+  }
+}
+
+/// Like [`input()`], but for output buffers.
+unsafe fn output<'a>(ptr: *mut u8, len: usize) -> Option<&'a mut [u8]> {
+  match (ptr.is_null(), len) {
+    (true, 0) => Some(&mut []),
+    (true, _) => None,
+    (false, _) => Some(unsafe { slice::from_raw_parts_mut(ptr, len) }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use core::ptr;
+
+  use super::*;
+
+  #[test]
+  fn capi() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let mut buf = vec![0; vb64_encoded_len(dec.len())];
+      let mut len = 0;
+      let status = unsafe {
+        vb64_encode(
+          dec.as_ptr(),
+          dec.len(),
+          buf.as_mut_ptr(),
+          buf.len(),
+          &mut len,
+        )
+      };
+      assert_eq!(status, Vb64Status::Ok, "case {i}");
+      assert_eq!(&buf[..len], enc, "case {i}");
+
+      let mut buf = vec![0; vb64_max_decoded_len(enc.len())];
+      let status = unsafe {
+        vb64_decode(
+          enc.as_ptr(),
+          enc.len(),
+          buf.as_mut_ptr(),
+          buf.len(),
+          &mut len,
+        )
+      };
+      assert_eq!(status, Vb64Status::Ok, "case {i}");
+      assert_eq!(&buf[..len], dec, "case {i}");
+    }
+
+    let mut buf = [0; 16];
+    let mut len = 0;
+    let bad = b"QUJD!UJD";
+    let status = unsafe {
+      vb64_decode(bad.as_ptr(), bad.len(), buf.as_mut_ptr(), 16, &mut len)
+    };
+    assert_eq!((status, len), (Vb64Status::InvalidByte, 4));
+
+    let status = unsafe {
+      vb64_encode(bad.as_ptr(), bad.len(), buf.as_mut_ptr(), 4, &mut len)
+    };
+    assert_eq!(status, Vb64Status::BufferTooSmall);
+
+    let status =
+      unsafe { vb64_encode(ptr::null(), 0, ptr::null_mut(), 0, &mut len) };
+    assert_eq!((status, len), (Vb64Status::Ok, 0));
+    let status =
+      unsafe { vb64_decode(ptr::null(), 4, buf.as_mut_ptr(), 16, &mut len) };
+    assert_eq!(status, Vb64Status::NullPointer);
+  }
+}
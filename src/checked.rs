@@ -0,0 +1,276 @@
+//! Strings that are known to hold valid base64.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
+
+use crate::Error;
+
+/// A string slice that is known to be valid standard base64.
+///
+/// This is checked once, when the `Base64Str` is created, after which
+/// decoding can't fail. That makes it a good fit for APIs that hold on to
+/// encoded data and decode it later, or more than once.
+///
+/// ```
+/// use vb64::Base64Str;
+///
+/// let text = Base64Str::new("aGVsbG8=").unwrap();
+/// assert_eq!(text.decode(), b"hello");
+/// assert!(Base64Str::new("aGVsbG8!").is_err());
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Base64Str(str);
+
+impl Base64Str {
+  /// Checks that `text` is valid base64, with the same rules as
+  /// [`crate::decode()`].
+  pub fn new(text: &str) -> Result<&Base64Str, Error> {
+    Self::from_bytes(text.as_bytes())
+  }
+
+  /// Checks that `data` is valid base64, with the same rules as
+  /// [`crate::decode()`].
+  pub fn from_bytes(data: &[u8]) -> Result<&Base64Str, Error> {
+    crate::check(data)?;
+    // SAFETY: Valid base64 is ASCII.
+    let text = unsafe { core::str::from_utf8_unchecked(data) };
+    Ok(unsafe { Self::new_unchecked(text) })
+  }
+
+  /// # Safety
+  ///
+  /// `text` must be valid base64.
+  unsafe fn new_unchecked(text: &str) -> &Base64Str {
+    // SAFETY: `Base64Str` is a transparent wrapper around `str`.
+    unsafe { &*(text as *const str as *const Base64Str) }
+  }
+
+  /// Returns the underlying text.
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  /// Returns how many bytes this decodes to.
+  pub fn decoded_len(&self) -> usize {
+    crate::decoded_len(self.as_str())
+  }
+
+  /// Decodes this string to a fresh vector.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the decoded data wouldn't fit in memory.
+  pub fn decode(&self) -> Vec<u8> {
+    let mut out = Vec::new();
+    self.decode_to(&mut out);
+    out
+  }
+
+  /// Decodes this string and appends it to `out`, returning how many bytes
+  /// were appended.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the decoded data wouldn't fit in memory.
+  pub fn decode_to(&self, out: &mut Vec<u8>) -> usize {
+    crate::decode_valid(self.as_bytes(), out)
+  }
+}
+
+impl Deref for Base64Str {
+  type Target = str;
+  fn deref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl AsRef<str> for Base64Str {
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl AsRef<[u8]> for Base64Str {
+  fn as_ref(&self) -> &[u8] {
+    self.0.as_bytes()
+  }
+}
+
+impl ToOwned for Base64Str {
+  type Owned = Base64String;
+  fn to_owned(&self) -> Base64String {
+    Base64String(self.0.to_owned())
+  }
+}
+
+impl<'a> TryFrom<&'a str> for &'a Base64Str {
+  type Error = Error;
+  fn try_from(text: &'a str) -> Result<Self, Error> {
+    Base64Str::new(text)
+  }
+}
+
+impl fmt::Display for Base64Str {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.0, f)
+  }
+}
+
+impl fmt::Debug for Base64Str {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(&self.0, f)
+  }
+}
+
+/// An owned string that is known to be valid standard base64.
+///
+/// This is to [`Base64Str`] what `String` is to `str`.
+///
+/// ```
+/// use vb64::Base64String;
+///
+/// let text = Base64String::encode(b"hello");
+/// assert_eq!(text.as_str(), "aGVsbG8=");
+/// assert_eq!(text.decode(), b"hello");
+///
+/// let text: Base64String = "aGVsbG8=".parse().unwrap();
+/// assert_eq!(text.decode(), b"hello");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Base64String(String);
+
+impl Base64String {
+  /// Checks that `text` is valid base64, with the same rules as
+  /// [`crate::decode()`].
+  pub fn new(text: String) -> Result<Base64String, Error> {
+    crate::check(text.as_bytes())?;
+    Ok(Base64String(text))
+  }
+
+  /// Encodes `data` as standard, padded base64.
+  ///
+  /// This doesn't need to check anything, since we know what the encoder
+  /// produces.
+  pub fn encode(data: impl AsRef<[u8]>) -> Base64String {
+    Base64String(crate::encode(data))
+  }
+
+  /// Returns this string as a [`Base64Str`].
+  pub fn as_base64_str(&self) -> &Base64Str {
+    // SAFETY: We only ever hold valid base64.
+    unsafe { Base64Str::new_unchecked(&self.0) }
+  }
+
+  /// Consumes this string, returning the underlying `String`.
+  pub fn into_string(self) -> String {
+    self.0
+  }
+}
+
+impl Deref for Base64String {
+  type Target = Base64Str;
+  fn deref(&self) -> &Base64Str {
+    self.as_base64_str()
+  }
+}
+
+impl Borrow<Base64Str> for Base64String {
+  fn borrow(&self) -> &Base64Str {
+    self.as_base64_str()
+  }
+}
+
+impl AsRef<str> for Base64String {
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl AsRef<[u8]> for Base64String {
+  fn as_ref(&self) -> &[u8] {
+    self.0.as_bytes()
+  }
+}
+
+impl From<&Base64Str> for Base64String {
+  fn from(text: &Base64Str) -> Self {
+    text.to_owned()
+  }
+}
+
+impl From<Base64String> for String {
+  fn from(text: Base64String) -> Self {
+    text.0
+  }
+}
+
+impl TryFrom<String> for Base64String {
+  type Error = Error;
+  fn try_from(text: String) -> Result<Self, Error> {
+    Base64String::new(text)
+  }
+}
+
+impl FromStr for Base64String {
+  type Err = Error;
+  fn from_str(text: &str) -> Result<Self, Error> {
+    Base64Str::new(text).map(ToOwned::to_owned)
+  }
+}
+
+impl fmt::Display for Base64String {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.0, f)
+  }
+}
+
+impl fmt::Debug for Base64String {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(&self.0, f)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn checked() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let text = Base64Str::from_bytes(enc).unwrap();
+      assert_eq!(text.decoded_len(), dec.len(), "case {i}");
+      assert_eq!(text.decode(), dec, "case {i}");
+
+      let owned = Base64String::encode(&dec);
+      assert_eq!(&*owned, text, "case {i}");
+      assert_eq!(owned.decode(), dec, "case {i}");
+
+      let nopad = crate::encode_nopad(&dec);
+      assert_eq!(Base64Str::new(&nopad).unwrap().decode(), dec, "case {i}");
+    }
+
+    for text in ["QQ", "QQ==", "QUI=", ""] {
+      let owned = Base64String::new(text.to_owned()).unwrap();
+      assert_eq!(owned.decode(), crate::decode(text).unwrap(), "{text}");
+    }
+
+    for text in ["Q", "QQ=", "QUJD=", "QUJ!", "Q=Q="] {
+      assert!(Base64Str::new(text).is_err(), "{text}");
+      assert!(text.parse::<Base64String>().is_err(), "{text}");
+    }
+    let err = Base64Str::new("QUJD!QUJ").unwrap_err();
+    assert_eq!(err.offset(), Some(4));
+
+    let mut out = b"x".to_vec();
+    let text = Base64Str::new("QUI=").unwrap();
+    assert_eq!(text.decode_to(&mut out), 2);
+    assert_eq!(out, b"xAB");
+    assert_eq!(format!("{text} {text:?}"), "QUI= \"QUI=\"");
+  }
+}
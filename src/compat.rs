@@ -0,0 +1,229 @@
+//! A drop-in stand-in for the `base64` crate's engines.
+//!
+//! Requires the `base64` feature. [`GeneralPurpose`] has the same methods as
+//! `base64::Engine`, returning the same error types, and this module has the
+//! same constants as `base64::engine::general_purpose`, so switching usually
+//! only means changing where the engine comes from:
+//!
+//! ```
+//! // use base64::engine::general_purpose::STANDARD;
+//! use vb64::compat::STANDARD;
+//!
+//! let mut buf = String::new();
+//! STANDARD.encode_string(b"hello", &mut buf);
+//! assert_eq!(buf, "aGVsbG8=");
+//! assert_eq!(STANDARD.decode(&buf).unwrap(), b"hello");
+//! assert_eq!(
+//!   STANDARD.decode("aGVsbG8"),
+//!   Err(base64::DecodeError::InvalidPadding),
+//! );
+//! ```
+//!
+//! Note that this does not implement the `base64::Engine` trait itself: that
+//! crate doesn't allow implementing it elsewhere, since its decoding hook
+//! returns a type that only it can construct. Code that is generic over
+//! `base64::Engine` needs to be made concrete to use this.
+
+use base64::DecodeError;
+use base64::DecodeSliceError;
+use base64::EncodeSliceError;
+
+use crate::Engine;
+use crate::Error;
+
+/// The standard alphabet, with canonical padding required when decoding.
+pub const STANDARD: GeneralPurpose = GeneralPurpose::new(Engine::STANDARD);
+
+/// The standard alphabet, with no padding allowed.
+pub const STANDARD_NO_PAD: GeneralPurpose =
+  GeneralPurpose::new(Engine::STANDARD_NOPAD);
+
+/// The URL-safe alphabet, with canonical padding required when decoding.
+pub const URL_SAFE: GeneralPurpose = GeneralPurpose::new(Engine::URL_SAFE);
+
+/// The URL-safe alphabet, with no padding allowed.
+pub const URL_SAFE_NO_PAD: GeneralPurpose =
+  GeneralPurpose::new(Engine::URL_SAFE_NOPAD);
+
+/// An [`Engine`] that speaks the `base64` crate's API.
+#[derive(Copy, Clone, Debug)]
+pub struct GeneralPurpose {
+  engine: Engine,
+}
+
+impl GeneralPurpose {
+  /// Wraps `engine`, making it strict like `base64`'s engines are by default.
+  ///
+  /// See [`Engine::strict()`]; pass the result of [`GeneralPurpose::engine()`]
+  /// through [`Engine::decode_padding()`] to relax the padding rules.
+  pub const fn new(engine: Engine) -> Self {
+    Self::with_engine(engine.strict())
+  }
+
+  /// Wraps `engine` as-is.
+  pub const fn with_engine(engine: Engine) -> Self {
+    Self { engine }
+  }
+
+  /// Returns the engine this wraps.
+  pub const fn engine(&self) -> Engine {
+    self.engine
+  }
+
+  /// Encodes arbitrary data as base64.
+  pub fn encode<T: AsRef<[u8]>>(&self, input: T) -> String {
+    self.engine.encode(input)
+  }
+
+  /// Encodes arbitrary data as base64 and appends it to `output_buf`.
+  pub fn encode_string<T: AsRef<[u8]>>(
+    &self,
+    input: T,
+    output_buf: &mut String,
+  ) {
+    self.engine.encode_to_string(input, output_buf);
+  }
+
+  /// Encodes arbitrary data as base64 into `output_buf`, returning how many
+  /// bytes were written.
+  pub fn encode_slice<T: AsRef<[u8]>>(
+    &self,
+    input: T,
+    output_buf: &mut [u8],
+  ) -> Result<usize, EncodeSliceError> {
+    self
+      .engine
+      .encode_to_slice(input, output_buf)
+      .map_err(|_| EncodeSliceError::OutputSliceTooSmall)
+  }
+
+  /// Decodes some base64 `input` to a fresh vector.
+  pub fn decode<T: AsRef<[u8]>>(
+    &self,
+    input: T,
+  ) -> Result<Vec<u8>, DecodeError> {
+    let input = input.as_ref();
+    self.engine.decode(input).map_err(|e| convert(input, e))
+  }
+
+  /// Decodes some base64 `input` and appends it to `buffer`.
+  pub fn decode_vec<T: AsRef<[u8]>>(
+    &self,
+    input: T,
+    buffer: &mut Vec<u8>,
+  ) -> Result<(), DecodeError> {
+    let input = input.as_ref();
+    match self.engine.decode_to(input, buffer) {
+      Ok(_) => Ok(()),
+      Err(e) => Err(convert(input, e)),
+    }
+  }
+
+  /// Decodes some base64 `input` into `output`, returning how many bytes were
+  /// written.
+  pub fn decode_slice<T: AsRef<[u8]>>(
+    &self,
+    input: T,
+    output: &mut [u8],
+  ) -> Result<usize, DecodeSliceError> {
+    let input = input.as_ref();
+    self
+      .engine
+      .decode_to_slice(input, output)
+      .map_err(|e| match e {
+        Error::BufferTooSmall => DecodeSliceError::OutputSliceTooSmall,
+        e => DecodeSliceError::DecodeError(convert(input, e)),
+      })
+  }
+}
+
+impl From<Engine> for GeneralPurpose {
+  fn from(engine: Engine) -> Self {
+    Self::new(engine)
+  }
+}
+
+/// Converts a decoding error for `input` into the closest `base64` error.
+fn convert(input: &[u8], e: Error) -> DecodeError {
+  match e {
+    Error::InvalidByte { offset, byte } => {
+      DecodeError::InvalidByte(offset, byte)
+    }
+    Error::InvalidPadding => {
+      // `base64` treats `=` followed by more data as a bad byte instead.
+      let first = input.iter().position(|&b| b == b'=');
+      match first {
+        Some(i) if input[i..].iter().any(|&b| b != b'=') => {
+          DecodeError::InvalidByte(i, b'=')
+        }
+        _ => DecodeError::InvalidPadding,
+      }
+    }
+    Error::InvalidTrailingBits => {
+      // `base64` also says which character had the stray bits, which is the
+      // last one that isn't whitespace or padding.
+      let offset = input
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace() && *b != b'=')
+        .unwrap_or(0);
+      DecodeError::InvalidLastSymbol(
+        offset,
+        input.get(offset).copied().unwrap_or(0),
+      )
+    }
+    _ => DecodeError::InvalidLength,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use base64::engine::general_purpose;
+  use base64::Engine as _;
+
+  use super::*;
+
+  #[test]
+  fn matches_base64() {
+    let engines = [
+      (STANDARD, general_purpose::STANDARD),
+      (STANDARD_NO_PAD, general_purpose::STANDARD_NO_PAD),
+      (URL_SAFE, general_purpose::URL_SAFE),
+      (URL_SAFE_NO_PAD, general_purpose::URL_SAFE_NO_PAD),
+    ];
+    for (ours, theirs) in engines {
+      for (i, _, dec) in crate::tests::random_tests() {
+        let enc = theirs.encode(&dec);
+        assert_eq!(ours.encode(&dec), enc, "case {i}");
+        assert_eq!(ours.decode(&enc).unwrap(), dec, "case {i}");
+      }
+
+      for data in [
+        &b"QQ"[..],
+        b"QQ==",
+        b"QR==",
+        b"QUJ!",
+        b"QUJDR",
+        b"Q=Q=",
+        b"QUI=QQ==",
+      ] {
+        assert_eq!(ours.decode(data), theirs.decode(data), "{data:?}");
+      }
+    }
+
+    let mut buf = [0; 4];
+    assert_eq!(STANDARD.encode_slice(b"AB", &mut buf), Ok(4));
+    assert_eq!(
+      STANDARD.encode_slice(b"ABCD", &mut buf),
+      Err(EncodeSliceError::OutputSliceTooSmall),
+    );
+    assert_eq!(STANDARD.decode_slice(b"QUJD", &mut buf), Ok(3));
+    assert_eq!(
+      STANDARD.decode_slice(b"QUJDQUJD", &mut buf),
+      Err(DecodeSliceError::OutputSliceTooSmall),
+    );
+
+    let mut out = b"x".to_vec();
+    STANDARD.decode_vec(b"QUI=", &mut out).unwrap();
+    assert_eq!(out, b"xAB");
+  }
+}
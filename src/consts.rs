@@ -0,0 +1,173 @@
+//! Scalar codecs that can run during constant evaluation.
+//!
+//! These are much slower than the SIMD ones, but they can decode embedded
+//! keys and assets into `const`s and `static`s, so that doesn't matter.
+
+use crate::Error;
+
+const ALPHABET: &[u8; 64] =
+  b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Returns how many bytes `data` decodes to, like [`crate::decoded_len()`],
+/// for sizing the array passed to [`const_decode()`].
+pub const fn const_decoded_len(data: &[u8]) -> usize {
+  crate::max_decoded_len(crate::strip_padding(data).len())
+}
+
+/// Decodes some base64 `data` into an array, which it must exactly fill,
+/// during constant evaluation.
+///
+/// This follows the same rules as [`crate::decode()`].
+///
+/// # Panics
+///
+/// Panics if `data` isn't valid base64 or doesn't decode to exactly `N`
+/// bytes; in a `const`, that's a compile-time error.
+///
+/// ```
+/// const B64: &[u8] = b"3q2+7w==";
+/// static NONCE: [u8; vb64::const_decoded_len(B64)] = vb64::const_decode(B64);
+/// assert_eq!(NONCE, [0xde, 0xad, 0xbe, 0xef]);
+/// ```
+pub const fn const_decode<const N: usize>(data: &[u8]) -> [u8; N] {
+  let data = match crate::unpad(data, true) {
+    Ok(data) => data,
+    Err(e) => fail(e),
+  };
+  if crate::max_decoded_len(data.len()) != N {
+    fail(Error::InvalidLength);
+  }
+
+  let mut out = [0; N];
+  let mut acc = 0u32;
+  let mut i = 0;
+  while i < data.len() {
+    let sextet = match data[i] {
+      b @ b'A'..=b'Z' => b - b'A',
+      b @ b'a'..=b'z' => b - b'a' + 26,
+      b @ b'0'..=b'9' => b - b'0' + 52,
+      b'+' => 62,
+      b'/' => 63,
+      byte => fail(Error::InvalidByte { offset: i, byte }),
+    };
+    acc = acc << 6 | sextet as u32;
+
+    // Every fourth character completes three bytes; a partial group at the
+    // end completes one or two, and its leftover bits are dropped.
+    let n = i + 1;
+    if n % 4 == 0 {
+      let j = n / 4 * 3;
+      out[j - 3] = (acc >> 16) as u8;
+      out[j - 2] = (acc >> 8) as u8;
+      out[j - 1] = acc as u8;
+      acc = 0;
+    } else if n == data.len() {
+      let j = n / 4 * 3;
+      match n % 4 {
+        2 => out[j] = (acc >> 4) as u8,
+        _ => {
+          out[j] = (acc >> 10) as u8;
+          out[j + 1] = (acc >> 2) as u8;
+        }
+      }
+    }
+    i += 1;
+  }
+  out
+}
+
+/// Encodes arbitrary data as standard, padded base64 into an array, which it
+/// must exactly fill, during constant evaluation.
+///
+/// # Panics
+///
+/// Panics if `N` isn't the length of the encoding; in a `const`, that's a
+/// compile-time error.
+///
+/// ```
+/// const DATA: &[u8] = b"hello";
+/// const LEN: usize = vb64::encoded_len(DATA.len(), true).unwrap();
+/// const TEXT: [u8; LEN] = vb64::const_encode(DATA);
+/// assert_eq!(&TEXT, b"aGVsbG8=");
+/// ```
+pub const fn const_encode<const N: usize>(data: &[u8]) -> [u8; N] {
+  match crate::encoded_len(data.len(), true) {
+    Some(len) if len == N => {}
+    _ => fail(Error::BufferTooSmall),
+  }
+
+  let mut out = [b'='; N];
+  let mut i = 0;
+  while i < data.len() {
+    let rest = data.len() - i;
+    let mut group = 0u32;
+    let mut k = 0;
+    while k < 3 {
+      group <<= 8;
+      if k < rest {
+        group |= data[i + k] as u32;
+      }
+      k += 1;
+    }
+
+    // A partial group at the end gets one more character than it has bytes;
+    // padding fills the rest.
+    let j = i / 3 * 4;
+    let chars = if rest >= 3 { 4 } else { rest + 1 };
+    let mut k = 0;
+    while k < chars {
+      out[j + k] = ALPHABET[(group >> (18 - 6 * k) & 63) as usize];
+      k += 1;
+    }
+    i += 3;
+  }
+  out
+}
+
+/// Panics with a message describing `e`, since `const` panics can't format.
+const fn fail(e: Error) -> ! {
+  match e {
+    Error::InvalidByte { .. } => panic!("invalid byte in base64"),
+    Error::InvalidLength => panic!("base64 decodes to the wrong length"),
+    Error::InvalidPadding => panic!("invalid base64 padding"),
+    Error::BufferTooSmall => panic!("array is the wrong size for base64"),
+    _ => panic!("invalid base64"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn const_codec() {
+    macro_rules! check {
+      ($($n:literal),*) => {$({
+        let data = (0..$n).map(|i| (i * 97 + 13) as u8).collect::<Vec<_>>();
+        let text = crate::encode(&data);
+        const LEN: usize = crate::encoded_len($n, true).unwrap();
+        assert_eq!(const_encode::<LEN>(&data), text.as_bytes(), "{}", $n);
+        assert_eq!(const_decoded_len(text.as_bytes()), $n);
+        assert_eq!(const_decode::<$n>(text.as_bytes()), &data[..], "{}", $n);
+        let unpadded = text.trim_end_matches('=');
+        assert_eq!(const_decode::<$n>(unpadded.as_bytes()), &data[..]);
+      })*};
+    }
+    check!(0, 1, 2, 3, 4, 5, 6, 7, 8, 31, 32, 33, 100);
+
+    // Trailing bits are allowed, just like in `decode()`.
+    assert_eq!(const_decode::<1>(b"QR=="), *b"A");
+  }
+
+  #[test]
+  #[should_panic = "invalid byte in base64"]
+  fn const_decode_invalid() {
+    const_decode::<3>(b"QU!D");
+  }
+
+  #[test]
+  #[should_panic = "base64 decodes to the wrong length"]
+  fn const_decode_wrong_len() {
+    const_decode::<4>(b"QUJD");
+  }
+}
@@ -0,0 +1,137 @@
+//! `data:` URIs, per [RFC 2397].
+//!
+//! [RFC 2397]: https://datatracker.ietf.org/doc/html/rfc2397
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Engine;
+use crate::Error;
+
+/// Encodes `data` as a base64 `data:` URI with the given media type, such as
+/// `image/png` or `text/plain;charset=utf-8`.
+///
+/// ```
+/// let uri = vb64::encode_data_uri("text/plain", b"hello");
+/// assert_eq!(uri, "data:text/plain;base64,aGVsbG8=");
+/// ```
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_data_uri(mime: &str, data: impl AsRef<[u8]>) -> String {
+  const BASE64: &str = ";base64,";
+  let data = data.as_ref();
+  let len = crate::encoded_len(data.len(), true)
+    .and_then(|len| len.checked_add(5 + mime.len() + BASE64.len()))
+    .expect("base64 output too large");
+
+  let mut out = String::with_capacity(len);
+  out.push_str("data:");
+  out.push_str(mime);
+  out.push_str(BASE64);
+  crate::encode_to_string(data, &mut out);
+  out
+}
+
+/// Decodes a base64 `data:` URI, returning its media type and contents.
+///
+/// The media type is returned as written, parameters and all; per RFC 2397,
+/// an empty one means `text/plain;charset=US-ASCII`. URIs without `;base64`
+/// are rejected with [`Error::InvalidFormat`], as are ones whose contents
+/// aren't base64.
+///
+/// Like browsers do, this accepts percent-escapes and whitespace in the
+/// contents, and the scheme and `;base64` marker in any case.
+///
+/// ```
+/// let uri = "data:image/gif;base64,R0lG";
+/// let (mime, data) = vb64::decode_data_uri(uri).unwrap();
+/// assert_eq!(mime, "image/gif");
+/// assert_eq!(data, b"GIF");
+/// ```
+pub fn decode_data_uri(uri: &str) -> Result<(&str, Vec<u8>), Error> {
+  let engine = Engine::STANDARD.ignore_whitespace();
+
+  let (header, body) = uri
+    .get(..5)
+    .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+    .and_then(|_| uri[5..].split_once(','))
+    .ok_or(Error::InvalidFormat)?;
+
+  let mime = header
+    .len()
+    .checked_sub(7)
+    .and_then(|i| header.get(i..).map(|marker| (i, marker)))
+    .filter(|(_, marker)| marker.eq_ignore_ascii_case(";base64"))
+    .map(|(i, _)| header[..i].trim_ascii())
+    .ok_or(Error::InvalidFormat)?;
+
+  let start = uri.len() - body.len();
+  let data = if body.contains('%') {
+    let body =
+      crate::percent::decode(body.as_bytes()).map_err(|e| e.shift(start))?;
+    // Offsets into the unescaped contents don't mean anything to the caller.
+    engine.decode(body).map_err(Error::unlocated)?
+  } else {
+    engine.decode(body).map_err(|e| e.shift(start))?
+  };
+  Ok((mime, data))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn roundtrip() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let uri = encode_data_uri("application/octet-stream", &dec);
+      assert_eq!(uri.len(), 37 + enc.len(), "case {i}");
+      let (mime, data) = decode_data_uri(&uri).unwrap();
+      assert_eq!(mime, "application/octet-stream", "case {i}");
+      assert_eq!(data, dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn decode() {
+    let cases: [(&str, &str, &[u8]); 5] = [
+      ("data:;base64,aGk=", "", b"hi"),
+      (
+        "DATA:text/plain;charset=utf-8;BASE64,aGk=",
+        "text/plain;charset=utf-8",
+        b"hi",
+      ),
+      ("data:image/png ;base64,aG k=\n", "image/png", b"hi"),
+      ("data:x;base64,%2B%2F8%3D", "x", b"\xfb\xff"),
+      ("data:x;base64,", "x", b""),
+    ];
+    for (uri, mime, data) in cases {
+      assert_eq!(
+        decode_data_uri(uri).unwrap(),
+        (mime, data.to_vec()),
+        "{uri}"
+      );
+    }
+
+    for uri in [
+      "data:text/plain,hi",
+      "data:base64,aGk=",
+      "http:;base64,aGk=",
+      "data:;base64",
+      "dat",
+      "data:x;base64,%21%21",
+    ] {
+      assert!(
+        matches!(decode_data_uri(uri), Err(Error::InvalidFormat)),
+        "{uri}"
+      );
+    }
+
+    let err = decode_data_uri("data:x;base64,aG!=").unwrap_err();
+    assert_eq!(err.offset(), Some(16));
+    let err = decode_data_uri("data:x;base64,aG%zz").unwrap_err();
+    assert_eq!(err.offset(), Some(17));
+  }
+}
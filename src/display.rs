@@ -0,0 +1,223 @@
+//! Encoding base64 straight into `fmt` sinks.
+
+use core::fmt;
+
+use crate::Engine;
+
+/// How much data we encode at a time. A multiple of three, so that we never
+/// have to pad in the middle.
+const CHUNK: usize = 768;
+
+/// Formats some bytes as base64, without allocating a `String` first.
+///
+/// This encodes a chunk at a time into a buffer on the stack, which makes it a
+/// cheap way to log binary data.
+///
+/// ```
+/// use vb64::Base64Display;
+///
+/// let key = b"hello";
+/// assert_eq!(format!("key={}", Base64Display::new(key)), "key=aGVsbG8=");
+/// ```
+#[derive(Copy, Clone)]
+pub struct Base64Display<'a> {
+  data: &'a [u8],
+  engine: &'a Engine,
+}
+
+impl<'a> Base64Display<'a> {
+  /// Formats `data` as standard, padded base64.
+  pub fn new(data: &'a [u8]) -> Self {
+    Self::with_engine(data, &Engine::STANDARD)
+  }
+
+  /// Formats `data` with the given engine's alphabet and padding.
+  pub fn with_engine(data: &'a [u8], engine: &'a Engine) -> Self {
+    Self { data, engine }
+  }
+}
+
+impl fmt::Display for Base64Display<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut enc = FmtEncoder::with_engine(f, self.engine);
+    enc.write(self.data)?;
+    enc.finish().map(drop)
+  }
+}
+
+impl fmt::Debug for Base64Display<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "\"{self}\"")
+  }
+}
+
+/// An encoder that writes base64 into a [`fmt::Write`], such as a `String` or
+/// a [`fmt::Formatter`].
+///
+/// Data can be fed in in pieces of any size; they are encoded through a small
+/// buffer on the stack. Any leftover partial group, and its padding, is only
+/// written once [`FmtEncoder::finish()`] is called.
+///
+/// ```
+/// use vb64::FmtEncoder;
+///
+/// let mut enc = FmtEncoder::new(String::new());
+/// enc.write(b"hel").unwrap();
+/// enc.write(b"lo").unwrap();
+/// assert_eq!(enc.finish().unwrap(), "aGVsbG8=");
+/// ```
+pub struct FmtEncoder<'a, W> {
+  out: W,
+  engine: &'a Engine,
+  tail: [u8; 3],
+  tail_len: usize,
+  /// How far into the current line we are, if the engine wraps.
+  col: usize,
+}
+
+impl<W: fmt::Write> FmtEncoder<'static, W> {
+  /// Creates an encoder for standard, padded base64.
+  pub fn new(out: W) -> Self {
+    Self::with_engine(out, &Engine::STANDARD)
+  }
+}
+
+impl<'a, W: fmt::Write> FmtEncoder<'a, W> {
+  /// Creates an encoder with the given engine's alphabet and padding.
+  pub fn with_engine(out: W, engine: &'a Engine) -> Self {
+    Self {
+      out,
+      engine,
+      tail: [0; 3],
+      tail_len: 0,
+      col: 0,
+    }
+  }
+
+  /// Encodes `data` and writes it to the underlying writer.
+  pub fn write(&mut self, mut data: &[u8]) -> fmt::Result {
+    // Finish off the group left over from last time, if there is one.
+    if self.tail_len > 0 {
+      let n = data.len().min(3 - self.tail_len);
+      self.tail[self.tail_len..self.tail_len + n].copy_from_slice(&data[..n]);
+      self.tail_len += n;
+      data = &data[n..];
+
+      if self.tail_len < 3 {
+        return Ok(());
+      }
+      self.tail_len = 0;
+      let tail = self.tail;
+      self.emit(&tail)?;
+    }
+
+    let (body, rest) = data.split_at(data.len() / 3 * 3);
+    for chunk in body.chunks(CHUNK) {
+      self.emit(chunk)?;
+    }
+
+    self.tail[..rest.len()].copy_from_slice(rest);
+    self.tail_len = rest.len();
+    Ok(())
+  }
+
+  /// Writes out any leftover partial group and returns the underlying writer.
+  pub fn finish(mut self) -> Result<W, fmt::Error> {
+    let tail = self.tail;
+    self.emit(&tail[..self.tail_len])?;
+    if self.col > 0 {
+      self.out.write_str(self.engine.eol())?;
+    }
+    Ok(self.out)
+  }
+
+  fn emit(&mut self, data: &[u8]) -> fmt::Result {
+    let mut buf = [0; CHUNK / 3 * 4];
+    let len = self
+      .engine
+      .encode_unwrapped(data, crate::as_uninit(&mut buf))
+      .unwrap();
+    // SAFETY: Encoders only produce ASCII.
+    let mut text = unsafe { core::str::from_utf8_unchecked(&buf[..len]) };
+
+    // Chunks don't line up with lines, so break the lines up ourselves.
+    let line_len = self.engine.line_len();
+    while line_len > 0 && text.len() >= line_len - self.col {
+      let (line, rest) = text.split_at(line_len - self.col);
+      self.out.write_str(line)?;
+      self.out.write_str(self.engine.eol())?;
+      self.col = 0;
+      text = rest;
+    }
+    if line_len > 0 {
+      self.col += text.len();
+    }
+    self.out.write_str(text)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn display() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let text = Base64Display::new(&dec).to_string();
+      assert_eq!(text.as_bytes(), enc, "case {i}");
+    }
+
+    let data = vec![0xfb; 3000];
+    let engine = Engine::URL_SAFE_NOPAD;
+    assert_eq!(
+      Base64Display::with_engine(&data, &engine).to_string(),
+      engine.encode(&data),
+    );
+    assert_eq!(format!("{:?}", Base64Display::new(b"hi")), "\"aGk=\"");
+  }
+
+  #[test]
+  fn fmt_encoder() {
+    let data = (0..3000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    for piece in [1, 2, 3, 5, 767, 768, 769, 3000] {
+      let mut enc = FmtEncoder::new(String::from("x"));
+      for chunk in data.chunks(piece) {
+        enc.write(chunk).unwrap();
+      }
+      let text = enc.finish().unwrap();
+      assert_eq!(text, format!("x{}", crate::encode(&data)), "piece {piece}");
+    }
+
+    let engine = Engine::URL_SAFE_NOPAD;
+    let mut enc = FmtEncoder::with_engine(String::new(), &engine);
+    enc.write(b"\xfb").unwrap();
+    enc.write(b"\xff").unwrap();
+    assert_eq!(enc.finish().unwrap(), "-_8");
+  }
+
+  #[test]
+  fn wrapped() {
+    let data = (0..3000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engines = [
+      Engine::STANDARD.wrap(76),
+      Engine::STANDARD
+        .wrap(76)
+        .line_ending(crate::LineEnding::CrLf),
+    ];
+    for engine in &engines {
+      for len in [0, 1, 56, 57, 58, 114, 768, 3000] {
+        let text = Base64Display::with_engine(&data[..len], engine).to_string();
+        assert_eq!(text, engine.encode(&data[..len]), "len {len}");
+      }
+
+      for piece in [1, 2, 57, 769] {
+        let mut enc = FmtEncoder::with_engine(String::new(), engine);
+        for chunk in data.chunks(piece) {
+          enc.write(chunk).unwrap();
+        }
+        let text = enc.finish().unwrap();
+        assert_eq!(text, engine.encode(&data), "piece {piece}");
+      }
+    }
+  }
+}
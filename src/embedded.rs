@@ -0,0 +1,575 @@
+//! Adapters between base64 and `embedded-io` readers and writers, such as
+//! UARTs and USB serial ports.
+//!
+//! Requires the `embedded-io` feature. These mirror the [`std::io`] adapters,
+//! such as [`crate::DecoderReader`], but implement `embedded_io`'s
+//! [`Read`][embedded_io::Read] and [`Write`][embedded_io::Write]. With the
+//! `embedded-io-async` feature, they also implement `embedded_io_async`'s
+//! traits, whenever the inner reader or writer does.
+//!
+//! None of this needs `std` or an allocator: each adapter buffers into a
+//! couple of small arrays of its own.
+
+use core::fmt;
+
+use embedded_io::ErrorKind;
+use embedded_io::ErrorType;
+
+use crate::adapter::Codec;
+use crate::adapter::Pull;
+use crate::adapter::Push;
+use crate::Decoder;
+use crate::Encoder;
+use crate::Engine;
+
+/// How much each adapter buffers. These often live on the stack of a device
+/// with little memory to spare, so this is much less than the `std` adapters
+/// use.
+const BUF_LEN: usize = 256;
+
+/// An error from one of the adapters in this module: either from the inner
+/// reader or writer, or from decoding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error<E> {
+  /// The inner reader or writer failed.
+  Io(E),
+  /// The input wasn't valid base64.
+  Base64(crate::Error),
+}
+
+impl<E> From<crate::Error> for Error<E> {
+  fn from(e: crate::Error) -> Self {
+    Self::Base64(e)
+  }
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Io(e) => e.fmt(f),
+      Self::Base64(e) => e.fmt(f),
+    }
+  }
+}
+
+impl<E: core::error::Error> core::error::Error for Error<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for Error<E> {
+  fn kind(&self) -> ErrorKind {
+    match self {
+      Self::Io(e) => e.kind(),
+      Self::Base64(_) => ErrorKind::InvalidData,
+    }
+  }
+}
+
+/// A reader that decodes the base64 text read from another reader.
+pub struct DecoderReader<'a, R> {
+  inner: R,
+  state: Pull<Decoder<'a>, BUF_LEN>,
+}
+
+impl<R> DecoderReader<'static, R> {
+  /// Creates a reader that decodes standard base64.
+  pub fn new(inner: R) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, R> DecoderReader<'a, R> {
+  /// Creates a reader that decodes with the given engine's rules.
+  pub fn with_engine(inner: R, engine: &'a Engine) -> Self {
+    Self::with_decoder(inner, Decoder::with_engine(engine))
+  }
+
+  /// Creates a reader that feeds its input through `decoder`, such as one
+  /// with a [limit][Decoder::limit()].
+  pub fn with_decoder(inner: R, decoder: Decoder<'a>) -> Self {
+    Self {
+      inner,
+      state: Pull::new(decoder),
+    }
+  }
+
+  /// Returns the inner reader, discarding any text that was read from it but
+  /// not yet decoded.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: ErrorType> ErrorType for DecoderReader<'_, R> {
+  type Error = Error<R::Error>;
+}
+
+impl<R: embedded_io::Read> embedded_io::Read for DecoderReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    read(&mut self.state, &mut self.inner, buf)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: embedded_io_async::Read> embedded_io_async::Read
+  for DecoderReader<'_, R>
+{
+  async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    read_async(&mut self.state, &mut self.inner, buf).await
+  }
+}
+
+/// A reader that encodes the data read from another reader as base64.
+pub struct EncoderReader<'a, R> {
+  inner: R,
+  state: Pull<Encoder<'a>, BUF_LEN>,
+}
+
+impl<R> EncoderReader<'static, R> {
+  /// Creates a reader that encodes standard, padded base64.
+  pub fn new(inner: R) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, R> EncoderReader<'a, R> {
+  /// Creates a reader that encodes with the given engine's alphabet, padding,
+  /// and line wrapping.
+  pub fn with_engine(inner: R, engine: &'a Engine) -> Self {
+    Self {
+      inner,
+      state: Pull::new(Encoder::with_engine(engine)),
+    }
+  }
+
+  /// Returns the inner reader, discarding any data that was read from it but
+  /// not yet encoded.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: ErrorType> ErrorType for EncoderReader<'_, R> {
+  type Error = Error<R::Error>;
+}
+
+impl<R: embedded_io::Read> embedded_io::Read for EncoderReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    read(&mut self.state, &mut self.inner, buf)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: embedded_io_async::Read> embedded_io_async::Read
+  for EncoderReader<'_, R>
+{
+  async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+    read_async(&mut self.state, &mut self.inner, buf).await
+  }
+}
+
+/// A writer that encodes everything written to it as base64 and writes the
+/// text to another writer.
+///
+/// The last partial group is only encoded by [`EncoderWriter::finish()`], or
+/// `finish_async()`; dropping the writer loses it.
+pub struct EncoderWriter<'a, W> {
+  inner: W,
+  state: Push<Encoder<'a>, BUF_LEN>,
+}
+
+impl<W> EncoderWriter<'static, W> {
+  /// Creates a writer that encodes standard, padded base64.
+  pub fn new(inner: W) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, W> EncoderWriter<'a, W> {
+  /// Creates a writer that encodes with the given engine's alphabet, padding,
+  /// and line wrapping.
+  pub fn with_engine(inner: W, engine: &'a Engine) -> Self {
+    Self {
+      inner,
+      state: Push::new(Encoder::with_engine(engine)),
+    }
+  }
+
+  /// Encodes the last partial group, with its padding, writes out all the
+  /// remaining text, and returns the inner writer.
+  pub fn finish(mut self) -> Result<W, Error<W::Error>>
+  where
+    W: embedded_io::Write,
+  {
+    self.state.finish()?;
+    write_out(&mut self.state, &mut self.inner)?;
+    Ok(self.inner)
+  }
+
+  /// Like [`EncoderWriter::finish()`], for an async writer.
+  #[cfg(feature = "embedded-io-async")]
+  pub async fn finish_async(mut self) -> Result<W, Error<W::Error>>
+  where
+    W: embedded_io_async::Write,
+  {
+    self.state.finish()?;
+    write_out_async(&mut self.state, &mut self.inner).await?;
+    Ok(self.inner)
+  }
+}
+
+impl<W: ErrorType> ErrorType for EncoderWriter<'_, W> {
+  type Error = Error<W::Error>;
+}
+
+impl<W: embedded_io::Write> embedded_io::Write for EncoderWriter<'_, W> {
+  fn write(&mut self, data: &[u8]) -> Result<usize, Self::Error> {
+    if self.state.is_full() {
+      write_out(&mut self.state, &mut self.inner)?;
+    }
+    Ok(self.state.push(data)?)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::Error> {
+    write_out(&mut self.state, &mut self.inner)?;
+    self.inner.flush().map_err(Error::Io)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<W: embedded_io_async::Write> embedded_io_async::Write
+  for EncoderWriter<'_, W>
+{
+  async fn write(&mut self, data: &[u8]) -> Result<usize, Self::Error> {
+    if self.state.is_full() {
+      write_out_async(&mut self.state, &mut self.inner).await?;
+    }
+    Ok(self.state.push(data)?)
+  }
+
+  async fn flush(&mut self) -> Result<(), Self::Error> {
+    write_out_async(&mut self.state, &mut self.inner).await?;
+    self.inner.flush().await.map_err(Error::Io)
+  }
+}
+
+/// A writer that decodes the base64 text written to it and writes the data to
+/// another writer.
+///
+/// Problems with the end of the input are only reported by
+/// [`DecoderWriter::finish()`], or `finish_async()`;
+/// dropping the writer skips those checks, and loses whatever was left over.
+pub struct DecoderWriter<'a, W> {
+  inner: W,
+  state: Push<Decoder<'a>, BUF_LEN>,
+}
+
+impl<W> DecoderWriter<'static, W> {
+  /// Creates a writer that decodes standard base64.
+  pub fn new(inner: W) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, W> DecoderWriter<'a, W> {
+  /// Creates a writer that decodes with the given engine's rules.
+  pub fn with_engine(inner: W, engine: &'a Engine) -> Self {
+    Self::with_decoder(inner, Decoder::with_engine(engine))
+  }
+
+  /// Creates a writer that feeds its input through `decoder`, such as one
+  /// with a [limit][Decoder::limit()].
+  pub fn with_decoder(inner: W, decoder: Decoder<'a>) -> Self {
+    Self {
+      inner,
+      state: Push::new(decoder),
+    }
+  }
+
+  /// Decodes whatever is left over, writes out all the remaining data, and
+  /// returns the inner writer.
+  pub fn finish(mut self) -> Result<W, Error<W::Error>>
+  where
+    W: embedded_io::Write,
+  {
+    self.state.finish()?;
+    write_out(&mut self.state, &mut self.inner)?;
+    Ok(self.inner)
+  }
+
+  /// Like [`DecoderWriter::finish()`], for an async writer.
+  #[cfg(feature = "embedded-io-async")]
+  pub async fn finish_async(mut self) -> Result<W, Error<W::Error>>
+  where
+    W: embedded_io_async::Write,
+  {
+    self.state.finish()?;
+    write_out_async(&mut self.state, &mut self.inner).await?;
+    Ok(self.inner)
+  }
+}
+
+impl<W: ErrorType> ErrorType for DecoderWriter<'_, W> {
+  type Error = Error<W::Error>;
+}
+
+impl<W: embedded_io::Write> embedded_io::Write for DecoderWriter<'_, W> {
+  fn write(&mut self, text: &[u8]) -> Result<usize, Self::Error> {
+    if self.state.is_full() {
+      write_out(&mut self.state, &mut self.inner)?;
+    }
+    Ok(self.state.push(text)?)
+  }
+
+  fn flush(&mut self) -> Result<(), Self::Error> {
+    write_out(&mut self.state, &mut self.inner)?;
+    self.inner.flush().map_err(Error::Io)
+  }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<W: embedded_io_async::Write> embedded_io_async::Write
+  for DecoderWriter<'_, W>
+{
+  async fn write(&mut self, text: &[u8]) -> Result<usize, Self::Error> {
+    if self.state.is_full() {
+      write_out_async(&mut self.state, &mut self.inner).await?;
+    }
+    Ok(self.state.push(text)?)
+  }
+
+  async fn flush(&mut self) -> Result<(), Self::Error> {
+    write_out_async(&mut self.state, &mut self.inner).await?;
+    self.inner.flush().await.map_err(Error::Io)
+  }
+}
+
+/// Implements [`embedded_io::Read::read()`], reading input from `inner` as
+/// needed.
+fn read<C: Codec, R: embedded_io::Read>(
+  state: &mut Pull<C, BUF_LEN>,
+  inner: &mut R,
+  buf: &mut [u8],
+) -> Result<usize, Error<R::Error>> {
+  while let Some(input) = state.wants_input() {
+    let n = inner.read(input).map_err(Error::Io)?;
+    state.fill(n)?;
+  }
+  Ok(state.read(buf))
+}
+
+/// Like [`read()`], for an async reader.
+#[cfg(feature = "embedded-io-async")]
+async fn read_async<C: Codec, R: embedded_io_async::Read>(
+  state: &mut Pull<C, BUF_LEN>,
+  inner: &mut R,
+  buf: &mut [u8],
+) -> Result<usize, Error<R::Error>> {
+  while let Some(input) = state.wants_input() {
+    let n = inner.read(input).await.map_err(Error::Io)?;
+    state.fill(n)?;
+  }
+  Ok(state.read(buf))
+}
+
+/// Writes out all the unwritten output, keeping track of how far we got if
+/// that fails partway through.
+fn write_out<C: Codec, W: embedded_io::Write>(
+  state: &mut Push<C, BUF_LEN>,
+  inner: &mut W,
+) -> Result<(), Error<W::Error>> {
+  while !state.unwritten().is_empty() {
+    // Same as `embedded_io::Write::write_all()`.
+    match inner.write(state.unwritten()).map_err(Error::Io)? {
+      0 => panic!("write() returned Ok(0)"),
+      n => state.advance(n),
+    }
+  }
+  Ok(())
+}
+
+/// Like [`write_out()`], for an async writer.
+#[cfg(feature = "embedded-io-async")]
+async fn write_out_async<C: Codec, W: embedded_io_async::Write>(
+  state: &mut Push<C, BUF_LEN>,
+  inner: &mut W,
+) -> Result<(), Error<W::Error>> {
+  while !state.unwritten().is_empty() {
+    match inner.write(state.unwritten()).await.map_err(Error::Io)? {
+      0 => panic!("write() returned Ok(0)"),
+      n => state.advance(n),
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::convert::Infallible;
+
+  use embedded_io::Read;
+  use embedded_io::Write;
+
+  use super::*;
+
+  /// A reader that hands out at most `.1` bytes at a time.
+  struct Trickle<'a>(&'a [u8], usize);
+
+  impl ErrorType for Trickle<'_> {
+    type Error = Infallible;
+  }
+
+  impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+      let n = buf.len().min(self.1).min(self.0.len());
+      buf[..n].copy_from_slice(&self.0[..n]);
+      self.0 = &self.0[n..];
+      Ok(n)
+    }
+  }
+
+  /// A writer that accepts at most `.1` bytes at a time.
+  struct Sip(Vec<u8>, usize);
+
+  impl ErrorType for Sip {
+    type Error = Infallible;
+  }
+
+  impl Write for Sip {
+    fn write(&mut self, data: &[u8]) -> Result<usize, Infallible> {
+      let n = data.len().min(self.1);
+      self.0.extend_from_slice(&data[..n]);
+      Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), Infallible> {
+      Ok(())
+    }
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  impl embedded_io_async::Read for Trickle<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+      Read::read(self, buf)
+    }
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  impl embedded_io_async::Write for Sip {
+    async fn write(&mut self, data: &[u8]) -> Result<usize, Infallible> {
+      Write::write(self, data)
+    }
+
+    async fn flush(&mut self) -> Result<(), Infallible> {
+      Ok(())
+    }
+  }
+
+  fn read_all<R: Read>(r: &mut R) -> Result<Vec<u8>, R::Error> {
+    let mut out = Vec::new();
+    let mut buf = [0; 100];
+    loop {
+      match r.read(&mut buf)? {
+        0 => return Ok(out),
+        n => out.extend_from_slice(&buf[..n]),
+      }
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let mut r = DecoderReader::new(Trickle(enc, 7));
+      assert_eq!(read_all(&mut r).unwrap(), dec, "case {i}");
+      let mut r = EncoderReader::new(Trickle(&dec, 7));
+      assert_eq!(read_all(&mut r).unwrap(), enc, "case {i}");
+
+      let mut w = EncoderWriter::new(Sip(Vec::new(), 5));
+      w.write_all(&dec).unwrap();
+      assert_eq!(w.finish().unwrap().0, enc, "case {i}");
+      let mut w = DecoderWriter::new(Sip(Vec::new(), 5));
+      w.write_all(enc).unwrap();
+      assert_eq!(w.finish().unwrap().0, dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn wrapped() {
+    // Lines that don't divide the buffer, ones as short as they get, and ones
+    // too long to ever fit in it whole.
+    let engines = [76, 4, BUF_LEN, 1000].map(|len| {
+      Engine::STANDARD
+        .wrap(len)
+        .line_ending(crate::LineEnding::CrLf)
+    });
+    let data = (0..1000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    for engine in &engines {
+      let text = engine.encode(&data);
+      let mut r = EncoderReader::with_engine(Trickle(&data, 100), engine);
+      assert_eq!(read_all(&mut r).unwrap(), text.as_bytes());
+
+      let mut w = EncoderWriter::with_engine(Sip(Vec::new(), 5), engine);
+      w.write_all(&data).unwrap();
+      assert_eq!(w.finish().unwrap().0, text.as_bytes());
+    }
+  }
+
+  #[test]
+  fn errors() {
+    let mut r = DecoderReader::new(Trickle(b"aGVs!G8=", 3));
+    let err = read_all(&mut r).unwrap_err();
+    assert_eq!(
+      err,
+      Error::Base64(crate::Error::InvalidByte {
+        offset: 4,
+        byte: b'!'
+      })
+    );
+    assert_eq!(embedded_io::Error::kind(&err), ErrorKind::InvalidData);
+
+    let mut w = DecoderWriter::new(Sip(Vec::new(), 5));
+    w.write_all(b"aGVsb").unwrap();
+    assert!(matches!(w.finish(), Err(Error::Base64(_))));
+  }
+
+  #[cfg(feature = "embedded-io-async")]
+  #[test]
+  fn round_trip_async() {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::Context;
+    use std::task::Poll;
+    use std::task::Waker;
+
+    /// Polls `f` until it's ready; nothing here ever waits for real.
+    fn block_on<T>(f: impl Future<Output = T>) -> T {
+      let mut f = pin!(f);
+      let mut cx = Context::from_waker(Waker::noop());
+      loop {
+        if let Poll::Ready(x) = f.as_mut().poll(&mut cx) {
+          return x;
+        }
+      }
+    }
+
+    for (i, enc, dec) in crate::tests::random_tests() {
+      block_on(async {
+        let mut r = DecoderReader::new(Trickle(enc, 7));
+        let mut out = vec![0; dec.len() + 1];
+        let mut len = 0;
+        loop {
+          match embedded_io_async::Read::read(&mut r, &mut out[len..]).await {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) => panic!("case {i}: {e:?}"),
+          }
+        }
+        assert_eq!(out[..len], dec, "case {i}");
+
+        let mut w = EncoderWriter::new(Sip(Vec::new(), 5));
+        embedded_io_async::Write::write_all(&mut w, &dec)
+          .await
+          .unwrap();
+        assert_eq!(w.finish_async().await.unwrap().0, enc, "case {i}");
+      });
+    }
+  }
+}
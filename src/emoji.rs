@@ -0,0 +1,276 @@
+//! An Ecoji-style emoji encoding.
+//!
+//! Like [Ecoji], this packs every five bytes into four emoji, each carrying
+//! ten bits, which makes for text that survives copy/paste through chat apps
+//! and is easy to eyeball. The alphabet is three contiguous runs of code
+//! points, rather than Ecoji's hand-picked list, so the output is **not**
+//! interchangeable with Ecoji's:
+//!
+//! - `U+1F300..=U+1F64F`, pictographs and emoticons, for values 0 to 847.
+//! - `U+1F680..=U+1F6C5`, transport and map symbols, for values 848 to 917.
+//! - `U+1F910..=U+1F979`, supplemental symbols, for values 918 to 1023.
+//!
+//! A final partial group is padded out with `☕`, except that a four-byte
+//! group ends in one of `♈♉♊♋`, which carries its last two bits.
+//!
+//! ```
+//! let text = vb64::emoji::encode(b"hi");
+//! assert_eq!(text, "💡🖐☕☕");
+//! assert_eq!(vb64::emoji::decode(text.as_bytes()).unwrap(), b"hi");
+//! ```
+//!
+//! [Ecoji]: https://github.com/keith-turner/ecoji
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::Simd;
+
+use crate::read_slice_padded;
+use crate::simd;
+use crate::Error;
+
+/// The number of bytes we encode at a time, of which we use the first 15.
+const N: usize = 16;
+
+/// The padding character.
+const PAD: char = '\u{2615}';
+
+/// The first of the four characters that end a four-byte group.
+const PAD4: char = '\u{2648}';
+
+/// The runs of code points that make up the alphabet, as `(first, len)`.
+const RUNS: [(u32, u32); 3] = [(0x1f300, 848), (0x1f680, 70), (0x1f910, 106)];
+
+/// Decodes some emoji-encoded `data` to a fresh vector.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as emoji.
+pub fn encode(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some emoji-encoded data and appends it to `out`.
+///
+/// `data` must be UTF-8, and padding is required.
+pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  let text = core::str::from_utf8(data).map_err(|e| Error::InvalidByte {
+    offset: e.valid_up_to(),
+    byte: data[e.valid_up_to()],
+  })?;
+  let start = out.len();
+  out.reserve(text.len() / 16 * 5 + 5);
+
+  let mut group = [0u64; 4];
+  let mut len = 0;
+  let mut emoji = 0;
+  let mut padding = false;
+  for (offset, c) in text.char_indices() {
+    let Some(symbol) = Symbol::from_char(c) else {
+      out.truncate(start);
+      return Err(Error::InvalidByte {
+        offset,
+        byte: data[offset],
+      });
+    };
+
+    // Once we've seen padding, only more padding can follow, up to the end of
+    // the group.
+    group[len] = match symbol {
+      Symbol::Value(v) if !padding => {
+        emoji += 1;
+        v
+      }
+      Symbol::Pad if len > 0 => {
+        padding = true;
+        0
+      }
+      Symbol::Pad4(bits) if len == 3 && !padding => {
+        padding = true;
+        bits << 8
+      }
+      _ => {
+        out.truncate(start);
+        return Err(Error::InvalidPadding);
+      }
+    };
+    len += 1;
+
+    if len == 4 {
+      // A group of k emoji and 4 - k padding characters holds k bytes.
+      let n = match symbol {
+        Symbol::Pad4(_) => 4,
+        _ if padding => emoji,
+        _ => 5,
+      };
+
+      let bits = group[0] << 30 | group[1] << 20 | group[2] << 10 | group[3];
+      out.extend_from_slice(&bits.to_be_bytes()[3..3 + n]);
+      group = [0; 4];
+      len = 0;
+      emoji = 0;
+    }
+  }
+
+  if len != 0 {
+    out.truncate(start);
+    return Err(Error::InvalidLength);
+  }
+  Ok(())
+}
+
+/// Encodes arbitrary data as emoji and appends it to `out`.
+pub fn encode_to(data: &[u8], out: &mut Vec<u8>) {
+  out.reserve(data.len().div_ceil(5) * 16);
+
+  let mut emit = |chunk: Simd<u8, N>, groups: usize| {
+    let (b2, b3) = simd::emoji_utf8(simd::split10(chunk));
+    for i in 0..groups * 4 {
+      out.extend_from_slice(&[0xf0, 0x9f, b2[i], b3[i]]);
+    }
+  };
+
+  let mut chunks = data.chunks_exact(N / 5 * 5);
+  for chunk in &mut chunks {
+    emit(unsafe { read_slice_padded::<N>(chunk, 0) }, N / 5);
+  }
+
+  let rest = chunks.remainder();
+  let full = rest.len() / 5;
+  if full > 0 {
+    emit(unsafe { read_slice_padded::<N>(rest, 0) }, full);
+  }
+
+  let tail = &rest[full * 5..];
+  if tail.is_empty() {
+    return;
+  }
+
+  let mut bits = [0; 8];
+  bits[3..3 + tail.len()].copy_from_slice(tail);
+  let bits = u64::from_be_bytes(bits);
+  let values = [bits >> 30, bits >> 20, bits >> 10, bits].map(|v| v & 0x3ff);
+
+  let mut buf = [0; 4];
+  let emoji = tail.len().min(3);
+  for &v in &values[..emoji] {
+    out.extend_from_slice(emoji_for(v).encode_utf8(&mut buf).as_bytes());
+  }
+  if tail.len() == 4 {
+    let pad = char::from_u32(PAD4 as u32 + (values[3] >> 8) as u32).unwrap();
+    out.extend_from_slice(pad.encode_utf8(&mut buf).as_bytes());
+  } else {
+    for _ in emoji..4 {
+      out.extend_from_slice(PAD.encode_utf8(&mut buf).as_bytes());
+    }
+  }
+}
+
+/// A character in emoji-encoded text.
+enum Symbol {
+  Value(u64),
+  Pad,
+  Pad4(u64),
+}
+
+impl Symbol {
+  fn from_char(c: char) -> Option<Self> {
+    if c == PAD {
+      return Some(Self::Pad);
+    }
+    if let Some(bits @ 0..=3) = (c as u32).checked_sub(PAD4 as u32) {
+      return Some(Self::Pad4(bits as u64));
+    }
+
+    let mut value = 0;
+    for (first, len) in RUNS {
+      match (c as u32).checked_sub(first) {
+        Some(offset) if offset < len => {
+          return Some(Self::Value((value + offset) as u64))
+        }
+        _ => value += len,
+      }
+    }
+    None
+  }
+}
+
+/// Returns the emoji for a ten-bit value.
+fn emoji_for(value: u64) -> char {
+  let mut value = value as u32;
+  for (first, len) in RUNS {
+    if value < len {
+      return char::from_u32(first + value).unwrap();
+    }
+    value -= len;
+  }
+  unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn alphabet() {
+    // The SIMD and scalar mappings must agree on every value.
+    let values = (0..1024).collect::<Vec<u16>>();
+    for chunk in values.chunks(N) {
+      let (b2, b3) = simd::emoji_utf8(Simd::<u16, N>::from_slice(chunk));
+      for (i, &v) in chunk.iter().enumerate() {
+        let mut buf = [0; 4];
+        let c = emoji_for(v as u64);
+        assert_eq!(
+          c.encode_utf8(&mut buf).as_bytes(),
+          [0xf0, 0x9f, b2[i], b3[i]]
+        );
+        assert!(
+          matches!(Symbol::from_char(c), Some(Symbol::Value(x)) if x == u64::from(v))
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn known() {
+    assert_eq!(encode(b""), "");
+    assert_eq!(encode(b"\0\0\0\0\0"), "🌀🌀🌀🌀");
+    assert_eq!(encode(b"\xff\xff\xff\xff\xff"), "🥹🥹🥹🥹");
+    assert_eq!(encode(b"\xff\xff\xff\xff"), "🥹🥹🥹♋");
+    assert_eq!(encode(b"\0"), "🌀☕☕☕");
+  }
+
+  #[test]
+  fn roundtrip() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      let enc = encode(&dec);
+      assert_eq!(enc.chars().count(), dec.len().div_ceil(5) * 4, "case {i}");
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec, "case {i}");
+    }
+
+    for len in 0..64 {
+      let data = (0..len).map(|i| (i * 37) as u8).collect::<Vec<_>>();
+      assert_eq!(decode(encode(&data).as_bytes()).unwrap(), data);
+    }
+  }
+
+  #[test]
+  fn errors() {
+    assert!(decode(b"\xff").is_err());
+    assert!(decode("🌀🌀🌀".as_bytes()).is_err());
+    assert!(decode("☕☕☕☕".as_bytes()).is_err());
+    assert!(decode("🌀☕🌀☕".as_bytes()).is_err());
+    assert!(decode("🌀♈☕☕".as_bytes()).is_err());
+    assert!(decode("🌀☕☕☕🌀🌀🌀🌀".as_bytes()).is_err());
+    assert!(decode("🌀🌀🌀a".as_bytes()).is_err());
+
+    let mut out = b"keep".to_vec();
+    assert!(decode_to("🌀🌀🌀🌀🌀".as_bytes(), &mut out).is_err());
+    assert_eq!(out, b"keep");
+  }
+}
@@ -0,0 +1,1572 @@
+//! Codecs for custom alphabets.
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::alloc::Allocator;
+use core::mem::MaybeUninit;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "alloc")]
+use crate::mime;
+use crate::simd;
+#[cfg(feature = "std")]
+use crate::Encoder;
+use crate::Error;
+
+/// How much data [`Engine::encode_to_writer()`] encodes at a time.
+#[cfg(feature = "std")]
+const WRITER_CHUNK: usize = 768;
+
+/// A base64 codec for an arbitrary alphabet.
+///
+/// The lookup tables used by the SIMD kernels are built once, when the
+/// `Engine` is constructed, so it's best to construct one up-front and reuse
+/// it.
+///
+/// ```
+/// let engine = vb64::Engine::new(
+///   b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789+/",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(engine.encode(b"hello"), "AgvSBg8=");
+/// assert_eq!(engine.decode(b"AgvSBg8=").unwrap(), b"hello");
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Engine {
+  luts: simd::Luts,
+  pad: bool,
+  padding: Padding,
+  check_length: bool,
+  strict: bool,
+  ignore_whitespace: bool,
+  /// Characters per line when encoding, or zero to not wrap.
+  wrap: usize,
+  /// What to end each line with when wrapping.
+  eol: LineEnding,
+}
+
+/// How an [`Engine`] treats `=` padding when decoding.
+///
+/// ```
+/// use vb64::Engine;
+/// use vb64::Padding;
+///
+/// let engine = Engine::STANDARD.decode_padding(Padding::Required);
+/// assert!(engine.decode(b"QUI=").is_ok());
+/// assert!(engine.decode(b"QUI").is_err());
+///
+/// let engine = Engine::STANDARD.decode_padding(Padding::Forbidden);
+/// assert!(engine.decode(b"QUI=").is_err());
+/// assert!(engine.decode(b"QUI").is_ok());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Padding {
+  /// The input must be padded out to a multiple of four characters.
+  Required,
+  /// Padding may be present or absent.
+  Optional,
+  /// The input must not contain any padding.
+  Forbidden,
+}
+
+/// What an [`Engine`] ends each line with when it wraps its output.
+///
+/// ```
+/// use vb64::Engine;
+/// use vb64::LineEnding;
+///
+/// let engine = Engine::STANDARD.wrap(8).line_ending(LineEnding::CrLf);
+/// assert_eq!(engine.encode(b"hello world"), "aGVsbG8g\r\nd29ybGQ=\r\n");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum LineEnding {
+  /// `\n`, as used by PEM and most Unix tools.
+  Lf,
+  /// `\r\n`, as required by MIME and HTTP.
+  CrLf,
+}
+
+impl LineEnding {
+  /// Returns the characters that make up this line ending.
+  pub const fn as_str(self) -> &'static str {
+    match self {
+      LineEnding::Lf => "\n",
+      LineEnding::CrLf => "\r\n",
+    }
+  }
+}
+
+impl Engine {
+  /// The standard alphabet, `A-Za-z0-9+/`.
+  pub const STANDARD: Engine = Engine {
+    luts: simd::STANDARD,
+    pad: true,
+    padding: Padding::Optional,
+    check_length: true,
+    strict: false,
+    ignore_whitespace: false,
+    wrap: 0,
+    eol: LineEnding::Lf,
+  };
+
+  /// The standard alphabet, without padding.
+  pub const STANDARD_NOPAD: Engine = Engine {
+    luts: simd::STANDARD,
+    pad: false,
+    padding: Padding::Forbidden,
+    check_length: true,
+    strict: false,
+    ignore_whitespace: false,
+    wrap: 0,
+    eol: LineEnding::Lf,
+  };
+
+  /// The URL-safe alphabet, `A-Za-z0-9-_`.
+  pub const URL_SAFE: Engine = Engine {
+    luts: simd::URL_SAFE,
+    pad: true,
+    padding: Padding::Optional,
+    check_length: true,
+    strict: false,
+    ignore_whitespace: false,
+    wrap: 0,
+    eol: LineEnding::Lf,
+  };
+
+  /// The URL-safe alphabet, without padding.
+  pub const URL_SAFE_NOPAD: Engine = Engine {
+    luts: simd::URL_SAFE,
+    pad: false,
+    padding: Padding::Forbidden,
+    check_length: true,
+    strict: false,
+    ignore_whitespace: false,
+    wrap: 0,
+    eol: LineEnding::Lf,
+  };
+
+  /// The alphabet used by `crypt(3)` password hashes, such as md5crypt and
+  /// sha512crypt, which is `./0-9A-Za-z`.
+  ///
+  /// Hashes in this format are never padded, so this engine neither emits nor
+  /// accepts `=`. Note that this only covers the alphabet: the bit order and
+  /// byte shuffling that each hash scheme applies to its digest are up to the
+  /// caller.
+  pub const CRYPT: Engine = Engine {
+    luts: luts(
+      b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+    ),
+    pad: false,
+    padding: Padding::Forbidden,
+    check_length: true,
+    strict: false,
+    ignore_whitespace: false,
+    wrap: 0,
+    eol: LineEnding::Lf,
+  };
+
+  /// The alphabet used by bcrypt for its salts and digests, which is
+  /// `./A-Za-z0-9`.
+  ///
+  /// Like [`Engine::CRYPT`], this never uses padding.
+  pub const BCRYPT: Engine = Engine {
+    luts: luts(
+      b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+    ),
+    pad: false,
+    padding: Padding::Forbidden,
+    check_length: true,
+    strict: false,
+    ignore_whitespace: false,
+    wrap: 0,
+    eol: LineEnding::Lf,
+  };
+
+  /// Builds a new engine for `alphabet`, which maps each sextet to the
+  /// character at that index.
+  ///
+  /// Returns `None` if `alphabet` is not made up of 64 distinct, printable
+  /// ASCII characters, or if it contains `=`, which is reserved for padding.
+  ///
+  /// Alphabets that are laid out similarly to the standard one get the
+  /// fastest decoder; others fall back to a slower, but still vectorized, one.
+  ///
+  /// This function can be called in `const` context; see also [`ConstEngine`].
+  pub const fn new(alphabet: &[u8; 64]) -> Option<Engine> {
+    match simd::Luts::new(alphabet) {
+      Some(luts) => Some(Engine {
+        luts,
+        pad: true,
+        padding: Padding::Optional,
+        check_length: true,
+        strict: false,
+        ignore_whitespace: false,
+        wrap: 0,
+        eol: LineEnding::Lf,
+      }),
+      None => None,
+    }
+  }
+
+  /// Returns a copy of this engine that only decodes canonical base64.
+  ///
+  /// By default, decoding is lenient: padding may be left off, and the unused
+  /// bits of the last character are ignored. A strict engine rejects `=`
+  /// anywhere but the end, padding that isn't exactly what [`Engine::encode()`]
+  /// would produce, and a last character with unused bits set, so that each
+  /// message has exactly one accepted encoding.
+  ///
+  /// This also sets the [`Padding`] policy to match the encoder; calling
+  /// [`Engine::decode_padding()`] afterwards relaxes that, but padding that is
+  /// present must still be the right length.
+  ///
+  /// ```
+  /// use vb64::Engine;
+  ///
+  /// let strict = Engine::STANDARD.strict();
+  /// assert_eq!(strict.decode(b"QUI=").unwrap(), b"AB");
+  /// assert!(strict.decode(b"QUI").is_err());
+  /// assert!(strict.decode(b"QUJ=").is_err());
+  /// assert!(Engine::STANDARD.decode(b"QUJ=").is_ok());
+  /// ```
+  pub const fn strict(self) -> Engine {
+    Engine {
+      padding: if self.pad {
+        Padding::Required
+      } else {
+        Padding::Forbidden
+      },
+      strict: true,
+      ..self
+    }
+  }
+
+  /// Returns a copy of this engine that decodes with the given padding
+  /// policy.
+  ///
+  /// This only affects decoding; whether the encoder emits padding is fixed by
+  /// which engine this is.
+  pub const fn decode_padding(self, padding: Padding) -> Engine {
+    Engine { padding, ..self }
+  }
+
+  /// Returns a copy of this engine that accepts inputs whose length no encoder
+  /// would produce, for compatibility with decoders that don't check.
+  ///
+  /// Normally, a lone character after the last group of four is rejected, as
+  /// is padding that doesn't round the input up to a multiple of four. With
+  /// this, a lone character decodes to one byte holding its six bits, and
+  /// padding is simply dropped. This has no effect on a strict engine.
+  ///
+  /// ```
+  /// use vb64::Engine;
+  ///
+  /// assert!(Engine::STANDARD.decode(b"QUJDR").is_err());
+  /// let engine = Engine::STANDARD.lenient_length();
+  /// assert_eq!(engine.decode(b"QUJDR").unwrap(), b"ABCD");
+  /// assert_eq!(engine.decode(b"QQ=").unwrap(), b"A");
+  /// ```
+  pub const fn lenient_length(self) -> Engine {
+    Engine {
+      check_length: false,
+      ..self
+    }
+  }
+
+  /// Returns a copy of this engine that skips over ASCII whitespace when
+  /// decoding, such as the line breaks in PEM and MIME bodies.
+  ///
+  /// The whitespace is filtered out in a vectorized pass before decoding, so
+  /// this is much faster than stripping it by hand. Error offsets still refer
+  /// to the original input.
+  ///
+  /// ```
+  /// use vb64::Engine;
+  ///
+  /// let engine = Engine::STANDARD.ignore_whitespace();
+  /// assert_eq!(engine.decode(b"aGVs\r\nbG8=\r\n").unwrap(), b"hello");
+  /// assert!(Engine::STANDARD.decode(b"aGVs\r\nbG8=\r\n").is_err());
+  /// ```
+  pub const fn ignore_whitespace(self) -> Engine {
+    Engine {
+      ignore_whitespace: true,
+      ..self
+    }
+  }
+
+  /// Returns a copy of this engine that breaks encoded output into lines of
+  /// `line_len` characters, each followed by a line break, as PEM does.
+  ///
+  /// Every line is terminated, including the last one. Since the decoder
+  /// would otherwise reject the line breaks, this also turns on
+  /// [`Engine::ignore_whitespace()`].
+  ///
+  /// Lines end in `\n` unless [`Engine::line_ending()`] says otherwise.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `line_len` is zero or not a multiple of four.
+  ///
+  /// ```
+  /// use vb64::Engine;
+  ///
+  /// let engine = Engine::STANDARD.wrap(8);
+  /// let text = engine.encode(b"hello world");
+  /// assert_eq!(text, "aGVsbG8g\nd29ybGQ=\n");
+  /// assert_eq!(engine.decode(&text).unwrap(), b"hello world");
+  /// ```
+  pub const fn wrap(self, line_len: usize) -> Engine {
+    assert!(
+      line_len > 0 && line_len % 4 == 0,
+      "base64 line length must be a nonzero multiple of four"
+    );
+    Engine {
+      wrap: line_len,
+      ignore_whitespace: true,
+      ..self
+    }
+  }
+
+  /// Sets what to end each line with when wrapping; the default is
+  /// [`LineEnding::Lf`].
+  ///
+  /// This has no effect unless the engine [wraps][Engine::wrap()] its output.
+  pub const fn line_ending(self, eol: LineEnding) -> Engine {
+    Engine { eol, ..self }
+  }
+
+  /// Returns how long `data_len` bytes are once encoded by this engine.
+  fn encoded_len(&self, data_len: usize) -> Option<usize> {
+    match self.wrap {
+      0 => crate::encoded_len(data_len, self.pad),
+      n => {
+        let eol_len = self.eol.as_str().len();
+        crate::encoded_len_wrapped(data_len, self.pad, n, eol_len)
+      }
+    }
+  }
+
+  /// Returns the line length this engine wraps its output at, or zero.
+  pub(crate) fn line_len(&self) -> usize {
+    self.wrap
+  }
+
+  /// Returns what this engine ends each line with when wrapping.
+  pub(crate) fn eol(&self) -> &'static str {
+    self.eol.as_str()
+  }
+
+  /// Returns whether this engine pads its output.
+  pub(crate) fn pads(&self) -> bool {
+    self.pad
+  }
+
+  /// Returns whether this engine skips whitespace when decoding.
+  pub(crate) fn skips_whitespace(&self) -> bool {
+    self.ignore_whitespace
+  }
+
+  /// Decodes `data`, which must not contain any whitespace, into `out`, without
+  /// allocating even if this engine skips whitespace.
+  pub(crate) fn decode_unspaced(
+    &self,
+    data: &[u8],
+    out: &mut [MaybeUninit<u8>],
+  ) -> Result<usize, Error> {
+    let len = crate::decoded_len(data);
+    crate::decode_checked_uninit(
+      data,
+      crate::prefix(out, len),
+      &self.luts,
+      self.padding,
+      self.check_length,
+      self.strict,
+    )
+  }
+
+  /// Decodes `data`, which may contain whitespace, into `out`, without
+  /// allocating.
+  pub(crate) fn decode_spaced(
+    &self,
+    data: &[u8],
+    out: &mut [MaybeUninit<u8>],
+  ) -> Result<usize, Error> {
+    if !data.iter().any(u8::is_ascii_whitespace) {
+      return self.decode_unspaced(data, out);
+    }
+
+    // Squeeze out the whitespace a piece at a time, rather than allocating
+    // somewhere to put the whole thing. Every piece but the last is a multiple
+    // of four long, so only the last one can end in a partial group.
+    let mut piece = [0; 256];
+    let (mut start, mut written) = (0, 0);
+    while start < data.len() {
+      let (mut len, mut end) = (0, start);
+      for &b in &data[start..] {
+        if !b.is_ascii_whitespace() {
+          if len == piece.len() {
+            break;
+          }
+          piece[len] = b;
+          len += 1;
+        }
+        end += 1;
+      }
+
+      let out = &mut out[written..];
+      written += self.decode_unspaced(&piece[..len], out).map_err(|e| {
+        match e {
+          // Find where the offending byte was before we removed whitespace.
+          Error::InvalidByte { offset, byte } => Error::InvalidByte {
+            offset: (start..end)
+              .filter(|&i| !data[i].is_ascii_whitespace())
+              .nth(offset)
+              .unwrap_or(offset),
+            byte,
+          },
+          e => e,
+        }
+      })?;
+      start = end;
+
+      // Padding ends the input, even if it happens to end a piece, so only
+      // whitespace may follow it. That's all that's left unless the piece
+      // filled up.
+      if piece[..len].ends_with(b"=") && start < data.len() {
+        return Err(Error::InvalidPadding);
+      }
+    }
+    Ok(written)
+  }
+
+  /// Encodes `data` into `out` with this engine's alphabet and padding, but
+  /// without line breaks.
+  pub(crate) fn encode_unwrapped(
+    &self,
+    data: &[u8],
+    out: &mut [MaybeUninit<u8>],
+  ) -> Result<usize, Error> {
+    crate::encode_slice_with(data, out, &self.luts, self.pad)
+  }
+
+  /// Decodes some base64 `data` to a fresh vector.
+  #[cfg(feature = "alloc")]
+  pub fn decode(&self, data: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+    let data = data.as_ref();
+    let mut out = Vec::new();
+    self.decode_to(data, &mut out)?;
+    Ok(out)
+  }
+
+  /// Encodes arbitrary data as base64.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory; see
+  /// [`Engine::try_encode()`].
+  #[cfg(feature = "alloc")]
+  pub fn encode(&self, data: impl AsRef<[u8]>) -> String {
+    self.try_encode(data).expect("base64 output too large")
+  }
+
+  /// Encodes arbitrary data as base64, returning
+  /// [`Error::CapacityOverflow`] if it wouldn't fit in memory.
+  #[cfg(feature = "alloc")]
+  pub fn try_encode(&self, data: impl AsRef<[u8]>) -> Result<String, Error> {
+    let data = data.as_ref();
+    let mut out = Vec::new();
+    self.try_encode_to(data, &mut out)?;
+    Ok(unsafe { String::from_utf8_unchecked(out) })
+  }
+
+  /// Decodes some base64 data and appends it to `out`, returning how many
+  /// bytes were appended.
+  ///
+  /// If decoding fails, the contents of `out` are left as they were.
+  #[cfg(feature = "alloc")]
+  pub fn decode_to(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    let decode = |data: &[u8], out: &mut Vec<u8>| {
+      crate::decode_checked(
+        data,
+        out,
+        &self.luts,
+        self.padding,
+        self.check_length,
+        self.strict,
+      )
+    };
+
+    if !self.ignore_whitespace {
+      return decode(data, out);
+    }
+
+    // Line-wrapped input can be decoded without copying it first.
+    let start = out.len();
+    if crate::decode_wrapped(data, out, &self.luts, self.padding, self.strict) {
+      return Ok(out.len() - start);
+    }
+
+    let mut compacted = Vec::with_capacity(data.len());
+    mime::compact(data, &mut compacted, mime::Keep::NonWhitespace);
+    decode(&compacted, out).map_err(|e| match e {
+      // Find where the offending byte was before we removed whitespace.
+      Error::InvalidByte { offset, byte } => Error::InvalidByte {
+        offset: (0..data.len())
+          .filter(|&i| !data[i].is_ascii_whitespace())
+          .nth(offset)
+          .unwrap_or(offset),
+        byte,
+      },
+      e => e,
+    })
+  }
+
+  /// Decodes some base64 `data` to a boxed slice with no spare capacity.
+  #[cfg(feature = "alloc")]
+  pub fn decode_boxed(
+    &self,
+    data: impl AsRef<[u8]>,
+  ) -> Result<Box<[u8]>, Error> {
+    let data = data.as_ref();
+    // This is exact unless we're skipping whitespace, in which case it's an
+    // overestimate that `boxed()` trims off.
+    let len = crate::decoded_len(data);
+    crate::boxed(len, |out| self.decode_to_uninit(data, out))
+  }
+
+  /// Encodes arbitrary data as base64 to a boxed string with no spare
+  /// capacity.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory; see
+  /// [`Engine::try_encode_boxed()`].
+  #[cfg(feature = "alloc")]
+  pub fn encode_boxed(&self, data: impl AsRef<[u8]>) -> Box<str> {
+    self
+      .try_encode_boxed(data)
+      .expect("base64 output too large")
+  }
+
+  /// Like [`Engine::encode_boxed()`], but returns
+  /// [`Error::CapacityOverflow`] if the encoded data wouldn't fit in memory.
+  #[cfg(feature = "alloc")]
+  pub fn try_encode_boxed(
+    &self,
+    data: impl AsRef<[u8]>,
+  ) -> Result<Box<str>, Error> {
+    let data = data.as_ref();
+    let len = self
+      .encoded_len(data.len())
+      .ok_or(Error::CapacityOverflow)?;
+    let out = crate::boxed(len, |out| self.encode_to_uninit(data, out))?;
+    // SAFETY: Encoders only produce ASCII.
+    Ok(unsafe { alloc::str::from_boxed_utf8_unchecked(out) })
+  }
+
+  /// Decodes some base64 `data` to a fresh vector allocated in `alloc`.
+  #[cfg(feature = "alloc")]
+  pub fn decode_in<A: Allocator>(
+    &self,
+    data: impl AsRef<[u8]>,
+    alloc: A,
+  ) -> Result<Vec<u8, A>, Error> {
+    let data = data.as_ref();
+    let mut out = Vec::new_in(alloc);
+    crate::append_with(&mut out, crate::decoded_len(data), |out| {
+      self.decode_to_uninit(data, out)
+    })?;
+    Ok(out)
+  }
+
+  /// Encodes arbitrary data as base64 to a fresh vector allocated in `alloc`.
+  ///
+  /// See [`crate::encode_in()`].
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory; see
+  /// [`Engine::try_encode_in()`].
+  #[cfg(feature = "alloc")]
+  pub fn encode_in<A: Allocator>(
+    &self,
+    data: impl AsRef<[u8]>,
+    alloc: A,
+  ) -> Vec<u8, A> {
+    self
+      .try_encode_in(data, alloc)
+      .expect("base64 output too large")
+  }
+
+  /// Like [`Engine::encode_in()`], but returns [`Error::CapacityOverflow`] if
+  /// the encoded data wouldn't fit in memory.
+  #[cfg(feature = "alloc")]
+  pub fn try_encode_in<A: Allocator>(
+    &self,
+    data: impl AsRef<[u8]>,
+    alloc: A,
+  ) -> Result<Vec<u8, A>, Error> {
+    let data = data.as_ref();
+    let len = self
+      .encoded_len(data.len())
+      .ok_or(Error::CapacityOverflow)?;
+    let mut out = Vec::new_in(alloc);
+    crate::append_with(&mut out, len, |out| self.encode_to_uninit(data, out))?;
+    Ok(out)
+  }
+
+  /// Decodes some base64 `data` into an array, which it must exactly fill.
+  ///
+  /// See [`crate::decode_exact()`].
+  pub fn decode_exact<const N: usize>(
+    &self,
+    data: impl AsRef<[u8]>,
+  ) -> Result<[u8; N], Error> {
+    let mut out = [0; N];
+    match self.decode_to_slice(data, &mut out) {
+      Ok(n) if n == N => Ok(out),
+      Ok(_) | Err(Error::BufferTooSmall) => Err(Error::InvalidLength),
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Decodes some base64 data into `out`, returning how many bytes were
+  /// written.
+  ///
+  /// See [`crate::decode_to_slice()`]. This only allocates if the engine
+  /// ignores whitespace.
+  pub fn decode_to_slice(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [u8],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    self.decode_to_uninit(data, crate::as_uninit(out))
+  }
+
+  /// Decodes some base64 data into possibly-uninitialized memory, returning
+  /// how many bytes were written.
+  ///
+  /// See [`crate::decode_to_uninit()`].
+  pub fn decode_to_uninit(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [MaybeUninit<u8>],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    if !self.ignore_whitespace {
+      return self.decode_unspaced(data, out);
+    }
+    self.decode_spaced(data, out)
+  }
+
+  /// Encodes arbitrary data as base64 and appends it to `out`, returning how
+  /// many bytes were appended.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory; see
+  /// [`Engine::try_encode_to()`].
+  #[cfg(feature = "alloc")]
+  pub fn encode_to(&self, data: impl AsRef<[u8]>, out: &mut Vec<u8>) -> usize {
+    self
+      .try_encode_to(data, out)
+      .expect("base64 output too large")
+  }
+
+  /// Like [`Engine::encode_to()`], but returns [`Error::CapacityOverflow`] if
+  /// the encoded data wouldn't fit in memory, leaving `out` as it was.
+  #[cfg(feature = "alloc")]
+  pub fn try_encode_to(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    if self.wrap == 0 {
+      return crate::encode_with(data, out, &self.luts, self.pad);
+    }
+
+    let len = self
+      .encoded_len(data.len())
+      .ok_or(Error::CapacityOverflow)?;
+    crate::append_with(out, len, |out| self.encode_to_uninit(data, out))
+  }
+
+  /// Encodes arbitrary data as base64 and appends it to `out`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory.
+  #[cfg(feature = "alloc")]
+  pub fn encode_to_string(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut String,
+  ) -> usize {
+    let data = data.as_ref();
+    // SAFETY: We only ever append ASCII.
+    self.encode_to(data, unsafe { out.as_mut_vec() })
+  }
+
+  /// Encodes arbitrary data as base64 and writes it to `out`, returning how
+  /// many bytes were written.
+  ///
+  /// See [`crate::encode_to_writer()`].
+  #[cfg(feature = "std")]
+  pub fn encode_to_writer(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut impl io::Write,
+  ) -> io::Result<usize> {
+    // Big enough for a chunk of text, even with every line only four
+    // characters long and ending in CRLF.
+    let mut buf = [0; WRITER_CHUNK / 3 * 4 * 3 / 2 + 6];
+    let mut enc = Encoder::with_engine(self);
+    let mut written = 0;
+    for chunk in data.as_ref().chunks(WRITER_CHUNK) {
+      let len = enc.update_to_slice(chunk, &mut buf).unwrap();
+      out.write_all(&buf[..len])?;
+      written += len;
+    }
+    let len = enc.finish_to_slice(&mut buf).unwrap();
+    out.write_all(&buf[..len])?;
+    Ok(written + len)
+  }
+
+  /// Encodes arbitrary data as base64 into `out`, returning how many bytes
+  /// were written.
+  ///
+  /// See [`crate::encode_to_slice()`].
+  pub fn encode_to_slice(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [u8],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    self.encode_to_uninit(data, crate::as_uninit(out))
+  }
+
+  /// Encodes arbitrary data as base64 into possibly-uninitialized memory,
+  /// returning how many bytes were written.
+  ///
+  /// See [`crate::encode_to_uninit()`].
+  pub fn encode_to_uninit(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [MaybeUninit<u8>],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    if self.wrap == 0 {
+      return self.encode_unwrapped(data, out);
+    }
+
+    // Check up front, so that we don't write anything if it won't all fit.
+    let len = match self.encoded_len(data.len()) {
+      Some(len) if len <= out.len() => len,
+      _ => return Err(Error::BufferTooSmall),
+    };
+
+    crate::encode_wrapped_uninit(
+      data,
+      crate::prefix(out, len),
+      &self.luts,
+      self.pad,
+      self.wrap,
+      self.eol().as_bytes(),
+    )
+  }
+}
+
+/// Builds tables for one of the built-in alphabets.
+const fn luts(alphabet: &[u8; 64]) -> simd::Luts {
+  match simd::Luts::new(alphabet) {
+    Some(luts) => luts,
+    None => panic!("invalid base64 alphabet"),
+  }
+}
+
+/// A base64 codec for an alphabet known at compile time.
+///
+/// The lookup tables are computed during constant evaluation, so this is
+/// exactly as fast as the built-in alphabets. Passing an invalid alphabet is a
+/// compile-time error.
+///
+/// ```
+/// use vb64::ConstEngine;
+///
+/// const ALPHABET: &[u8; 64] =
+///   b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789+/";
+/// const ENGINE: ConstEngine<ALPHABET> = ConstEngine::new();
+///
+/// assert_eq!(ENGINE.encode(b"hello"), "AgvSBg8=");
+/// assert_eq!(ENGINE.decode(b"AgvSBg8=").unwrap(), b"hello");
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ConstEngine<const ALPHABET: &'static [u8; 64]>;
+
+impl<const ALPHABET: &'static [u8; 64]> ConstEngine<ALPHABET> {
+  const ENGINE: Engine = match Engine::new(ALPHABET) {
+    Some(engine) => engine,
+    None => panic!("invalid base64 alphabet"),
+  };
+
+  /// Creates a new engine, checking that `ALPHABET` is valid.
+  pub const fn new() -> Self {
+    let _ = Self::ENGINE;
+    Self
+  }
+
+  /// Decodes some base64 `data` to a fresh vector.
+  #[cfg(feature = "alloc")]
+  pub fn decode(&self, data: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.decode(data)
+  }
+
+  /// Encodes arbitrary data as base64.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory; see
+  /// [`ConstEngine::try_encode()`].
+  #[cfg(feature = "alloc")]
+  pub fn encode(&self, data: impl AsRef<[u8]>) -> String {
+    let data = data.as_ref();
+    Self::ENGINE.encode(data)
+  }
+
+  /// Encodes arbitrary data as base64, returning
+  /// [`Error::CapacityOverflow`] if it wouldn't fit in memory.
+  #[cfg(feature = "alloc")]
+  pub fn try_encode(&self, data: impl AsRef<[u8]>) -> Result<String, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.try_encode(data)
+  }
+
+  /// Decodes some base64 data and appends it to `out`.
+  #[cfg(feature = "alloc")]
+  pub fn decode_to(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.decode_to(data, out)
+  }
+
+  /// Decodes some base64 `data` into an array, which it must exactly fill.
+  pub fn decode_exact<const N: usize>(
+    &self,
+    data: impl AsRef<[u8]>,
+  ) -> Result<[u8; N], Error> {
+    Self::ENGINE.decode_exact(data)
+  }
+
+  /// Decodes some base64 data into `out`, returning how many bytes were
+  /// written.
+  pub fn decode_to_slice(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [u8],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.decode_to_slice(data, out)
+  }
+
+  /// Decodes some base64 data into possibly-uninitialized memory, returning
+  /// how many bytes were written.
+  pub fn decode_to_uninit(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [MaybeUninit<u8>],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.decode_to_uninit(data, out)
+  }
+
+  /// Encodes arbitrary data as base64 and appends it to `out`, returning how
+  /// many bytes were appended.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory; see
+  /// [`ConstEngine::try_encode_to()`].
+  #[cfg(feature = "alloc")]
+  pub fn encode_to(&self, data: impl AsRef<[u8]>, out: &mut Vec<u8>) -> usize {
+    let data = data.as_ref();
+    Self::ENGINE.encode_to(data, out)
+  }
+
+  /// Like [`ConstEngine::encode_to()`], but returns
+  /// [`Error::CapacityOverflow`] if the encoded data wouldn't fit in memory.
+  #[cfg(feature = "alloc")]
+  pub fn try_encode_to(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.try_encode_to(data, out)
+  }
+
+  /// Encodes arbitrary data as base64 and appends it to `out`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory.
+  #[cfg(feature = "alloc")]
+  pub fn encode_to_string(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut String,
+  ) -> usize {
+    let data = data.as_ref();
+    Self::ENGINE.encode_to_string(data, out)
+  }
+
+  /// Encodes arbitrary data as base64 and writes it to `out`, returning how
+  /// many bytes were written.
+  #[cfg(feature = "std")]
+  pub fn encode_to_writer(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut impl io::Write,
+  ) -> io::Result<usize> {
+    let data = data.as_ref();
+    Self::ENGINE.encode_to_writer(data, out)
+  }
+
+  /// Encodes arbitrary data as base64 into `out`, returning how many bytes
+  /// were written.
+  pub fn encode_to_slice(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [u8],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.encode_to_slice(data, out)
+  }
+
+  /// Encodes arbitrary data as base64 into possibly-uninitialized memory,
+  /// returning how many bytes were written.
+  pub fn encode_to_uninit(
+    &self,
+    data: impl AsRef<[u8]>,
+    out: &mut [MaybeUninit<u8>],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    Self::ENGINE.encode_to_uninit(data, out)
+  }
+}
+
+/// One of the built-in alphabets, for use with [`Config`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum Alphabet {
+  /// `A-Za-z0-9+/`, as in [`Engine::STANDARD`].
+  Standard,
+  /// `A-Za-z0-9-_`, as in [`Engine::URL_SAFE`].
+  UrlSafe,
+  /// `./0-9A-Za-z`, as in [`Engine::CRYPT`].
+  Crypt,
+  /// `./A-Za-z0-9`, as in [`Engine::BCRYPT`].
+  Bcrypt,
+}
+
+/// A builder for an [`Engine`], for setting several options at once.
+///
+/// Unlike the methods on [`Engine`], the padding set here applies to both
+/// directions: the encoder emits `=` unless padding is
+/// [`Padding::Forbidden`]. A fresh `Config` builds [`Engine::STANDARD`].
+///
+/// ```
+/// use vb64::Alphabet;
+/// use vb64::Config;
+/// use vb64::Padding;
+///
+/// const ENGINE: vb64::Engine = Config::new()
+///   .alphabet(Alphabet::UrlSafe)
+///   .padding(Padding::Forbidden)
+///   .wrap(8)
+///   .build();
+///
+/// let text = ENGINE.encode(b"\xfb\xff hello");
+/// assert_eq!(text, "-_8gaGVs\nbG8\n");
+/// assert_eq!(ENGINE.decode(&text).unwrap(), b"\xfb\xff hello");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Config {
+  alphabet: Alphabet,
+  padding: Padding,
+  strict: bool,
+  lenient_length: bool,
+  ignore_whitespace: bool,
+  wrap: usize,
+  line_ending: LineEnding,
+}
+
+impl Config {
+  /// Creates a config for standard, padded base64.
+  pub const fn new() -> Config {
+    Config {
+      alphabet: Alphabet::Standard,
+      padding: Padding::Optional,
+      strict: false,
+      lenient_length: false,
+      ignore_whitespace: false,
+      wrap: 0,
+      line_ending: LineEnding::Lf,
+    }
+  }
+
+  /// Sets which alphabet to use.
+  pub const fn alphabet(self, alphabet: Alphabet) -> Config {
+    Config { alphabet, ..self }
+  }
+
+  /// Sets the padding policy for decoding, and whether the encoder pads.
+  pub const fn padding(self, padding: Padding) -> Config {
+    Config { padding, ..self }
+  }
+
+  /// Sets whether to only decode canonical base64; see [`Engine::strict()`].
+  ///
+  /// A strict engine requires padding unless it is [`Padding::Forbidden`].
+  pub const fn strict(self, strict: bool) -> Config {
+    Config { strict, ..self }
+  }
+
+  /// Sets whether to accept lengths that no encoder would produce; see
+  /// [`Engine::lenient_length()`].
+  pub const fn lenient_length(self, lenient_length: bool) -> Config {
+    Config {
+      lenient_length,
+      ..self
+    }
+  }
+
+  /// Sets whether to skip ASCII whitespace when decoding; see
+  /// [`Engine::ignore_whitespace()`].
+  pub const fn ignore_whitespace(self, ignore_whitespace: bool) -> Config {
+    Config {
+      ignore_whitespace,
+      ..self
+    }
+  }
+
+  /// Sets the line length to wrap encoded output at, or zero to not wrap; see
+  /// [`Engine::wrap()`].
+  ///
+  /// Wrapping implies ignoring whitespace when decoding.
+  pub const fn wrap(self, line_len: usize) -> Config {
+    Config {
+      wrap: line_len,
+      ..self
+    }
+  }
+
+  /// Sets what to end each line with when wrapping; see
+  /// [`Engine::line_ending()`].
+  pub const fn line_ending(self, line_ending: LineEnding) -> Config {
+    Config {
+      line_ending,
+      ..self
+    }
+  }
+
+  /// Builds an engine with these settings.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the line length is not a multiple of four.
+  pub const fn build(self) -> Engine {
+    let engine = match self.alphabet {
+      Alphabet::Standard => Engine::STANDARD,
+      Alphabet::UrlSafe => Engine::URL_SAFE,
+      Alphabet::Crypt => Engine::CRYPT,
+      Alphabet::Bcrypt => Engine::BCRYPT,
+    };
+    let mut engine = Engine {
+      pad: !matches!(self.padding, Padding::Forbidden),
+      padding: self.padding,
+      ..engine
+    };
+
+    if self.strict {
+      engine = engine.strict();
+    }
+    if self.lenient_length {
+      engine = engine.lenient_length();
+    }
+    if self.ignore_whitespace {
+      engine = engine.ignore_whitespace();
+    }
+    if self.wrap > 0 {
+      engine = engine.wrap(self.wrap);
+    }
+    engine.line_ending(self.line_ending)
+  }
+}
+
+impl Default for Config {
+  fn default() -> Config {
+    Config::new()
+  }
+}
+
+impl From<Config> for Engine {
+  fn from(config: Config) -> Engine {
+    config.build()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ConstEngine;
+  use super::Engine;
+  use super::LineEnding;
+
+  const STANDARD: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+  #[test]
+  fn rejects_bad_alphabets() {
+    let mut dup = *STANDARD;
+    dup[1] = b'A';
+    assert!(Engine::new(&dup).is_none());
+
+    let mut pad = *STANDARD;
+    pad[63] = b'=';
+    assert!(Engine::new(&pad).is_none());
+
+    let mut ctrl = *STANDARD;
+    ctrl[63] = b'\n';
+    assert!(Engine::new(&ctrl).is_none());
+  }
+
+  /// Checks `alphabet` against the `base64` crate, by translating its output.
+  fn roundtrip(alphabet: &[u8; 64]) {
+    use base64::prelude::BASE64_STANDARD;
+    use base64::Engine as _;
+    let engine = Engine::new(alphabet).unwrap();
+
+    for len in 0..200 {
+      let data = (0..len as u8)
+        .map(|i| i.wrapping_mul(17))
+        .collect::<Vec<_>>();
+      let expected = BASE64_STANDARD
+        .encode(&data)
+        .bytes()
+        .map(|c| match STANDARD.iter().position(|&s| s == c) {
+          Some(i) => alphabet[i],
+          None => c,
+        })
+        .collect::<Vec<_>>();
+
+      assert_eq!(engine.encode(&data).as_bytes(), expected, "len {len}");
+      assert_eq!(engine.decode(&expected).unwrap(), data, "len {len}");
+    }
+
+    for b in 0..255u8 {
+      let res = engine.decode([b, b, b'=', b'=']);
+      assert_eq!(res.is_ok(), alphabet.contains(&b), "{b:#04x}");
+    }
+  }
+
+  #[test]
+  fn swapped_case() {
+    roundtrip(
+      b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789+/",
+    );
+  }
+
+  #[test]
+  fn unhashable_encode() {
+    roundtrip(
+      b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+    );
+  }
+
+  #[test]
+  fn const_engine() {
+    const URL_SAFE: &[u8; 64] =
+      b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    const ENGINE: ConstEngine<URL_SAFE> = ConstEngine::new();
+
+    for (i, _, dec) in crate::tests::random_tests() {
+      let enc = crate::encode_url(&dec);
+      assert_eq!(ENGINE.encode(&dec), enc, "case {i}");
+      assert_eq!(ENGINE.decode(enc.as_bytes()).unwrap(), dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn unhashable_decode() {
+    let mut reversed = *STANDARD;
+    reversed.reverse();
+    roundtrip(&reversed);
+
+    roundtrip(
+      b"0123456789abcdefghijklmnopqrstuvwxyzBCDEFGHIJKLMNOPQRSTUVWXYZ!#$",
+    );
+  }
+
+  #[test]
+  fn crypt() {
+    // The hash from `$1$saltsalt$qjXMvbEw8oaL.CzflDugX/`, taken as an opaque
+    // crypt-base64 string. Its last character has nonzero trailing bits.
+    let hash = b"qjXMvbEw8oaL.CzflDugX/";
+    let decoded = Engine::CRYPT.decode(hash).unwrap();
+    assert_eq!(decoded.len(), 16);
+    assert_eq!(Engine::CRYPT.encode(&decoded), "qjXMvbEw8oaL.CzflDugX.");
+
+    assert!(Engine::CRYPT.decode(b"qjXM=").is_err());
+    assert_eq!(Engine::CRYPT.encode([0, 0, 0]), "....");
+    assert_eq!(Engine::CRYPT.encode([0xff; 3]), "zzzz");
+  }
+
+  #[test]
+  fn bcrypt() {
+    // The salt from `$2a$10$N9qo8uLOickgx2ZMRZoMyeIjZAgcfl7p92ldGxad68LJZdL17lhWy`.
+    let salt = b"N9qo8uLOickgx2ZMRZoMye";
+    let decoded = Engine::BCRYPT.decode(salt).unwrap();
+    assert_eq!(decoded.len(), 16);
+    assert_eq!(Engine::BCRYPT.encode(&decoded).as_bytes(), salt);
+
+    assert!(Engine::BCRYPT.decode(b"N9qo8uLOickgx2ZMRZoMye==").is_err());
+    assert_eq!(Engine::BCRYPT.encode([0, 0, 0]), "....");
+    assert_eq!(Engine::BCRYPT.encode([0xff; 3]), "9999");
+    assert_eq!(Engine::BCRYPT.encode(b"\x00\x10\x83"), "./AB");
+  }
+
+  #[test]
+  fn strict() {
+    use core::mem::discriminant;
+
+    use crate::Error;
+
+    let padded = Engine::STANDARD.strict();
+    let unpadded = Engine::STANDARD_NOPAD.strict();
+    for (i, enc, dec) in crate::tests::random_tests() {
+      assert_eq!(padded.decode(enc).unwrap(), dec, "case {i}");
+
+      let nopad = crate::encode_nopad(&dec);
+      assert_eq!(unpadded.decode(nopad.as_bytes()).unwrap(), dec, "case {i}");
+      if nopad.len() != enc.len() {
+        assert!(padded.decode(nopad.as_bytes()).is_err(), "case {i}");
+        assert!(unpadded.decode(enc).is_err(), "case {i}");
+      }
+    }
+
+    let cases: [(&[u8], Error); 12] = [
+      (b"QQ=", Error::InvalidPadding),
+      (b"QQ===", Error::InvalidPadding),
+      (b"QUJD=", Error::InvalidPadding),
+      (b"Q=Q=", Error::InvalidPadding),
+      (b"QUJD=extra", Error::InvalidPadding),
+      (b"QQ==QUJD", Error::InvalidPadding),
+      (b"QUI=QQ==", Error::InvalidPadding),
+      (b"QUI=\n", Error::InvalidPadding),
+      (b"QUI=\nQQ==", Error::InvalidPadding),
+      (b"QR==", Error::InvalidTrailingBits),
+      (b"QUJ=", Error::InvalidTrailingBits),
+      (b"QUJDR===", Error::InvalidLength),
+    ];
+    for (data, err) in cases {
+      let res = padded.decode(data).unwrap_err();
+      assert_eq!(discriminant(&res), discriminant(&err), "{data:?}");
+    }
+
+    let mut out = b"keep".to_vec();
+    assert!(padded.decode_to(b"QUJDQR==", &mut out).is_err());
+    assert_eq!(out, b"keep");
+  }
+
+  #[test]
+  fn whitespace() {
+    let engine = Engine::STANDARD.ignore_whitespace();
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let wrapped = enc
+        .chunks(64)
+        .flat_map(|line| line.iter().copied().chain(*b"\r\n"))
+        .collect::<Vec<_>>();
+      assert_eq!(engine.decode(&wrapped).unwrap(), dec, "case {i}");
+
+      let spaced = enc
+        .iter()
+        .flat_map(|&b| [b, b" \t\n\x0c\r"[b as usize % 5]])
+        .collect::<Vec<_>>();
+      assert_eq!(engine.decode(&spaced).unwrap(), dec, "case {i}");
+    }
+
+    let err = engine.decode(b" QU\nJD\r\n R!==").unwrap_err();
+    assert_eq!(err.offset(), Some(10));
+    assert!(engine.decode(b"QUJD\x0bRA==").is_err());
+  }
+
+  #[test]
+  fn to_slice() {
+    let mut buf = [0; 5];
+    for engine in [
+      Engine::STANDARD,
+      Engine::STANDARD.strict(),
+      Engine::STANDARD.ignore_whitespace(),
+    ] {
+      assert_eq!(engine.decode_to_slice(b"aGVsbG8=", &mut buf).unwrap(), 5);
+      assert_eq!(&buf, b"hello");
+      let err = engine.decode_to_slice(b"aGVsbG8h", &mut buf).unwrap_err();
+      assert!(matches!(err, crate::Error::BufferTooSmall));
+    }
+
+    let mut enc = [0; 8];
+    let n = Engine::URL_SAFE_NOPAD.encode_to_slice(b"\xfb\xff", &mut enc);
+    assert_eq!(&enc[..n.unwrap()], b"-_8");
+    assert!(Engine::STANDARD
+      .encode_to_slice(b"\xfb\xff", &mut enc[..3])
+      .is_err());
+
+    let engine = Engine::STANDARD.ignore_whitespace();
+    assert_eq!(
+      engine.decode_to_slice(b"aGVs\nbG8=\n", &mut buf).unwrap(),
+      5
+    );
+    assert!(Engine::STANDARD
+      .strict()
+      .decode_to_slice(b"aGVsbG9=", &mut buf)
+      .is_err());
+  }
+
+  #[test]
+  fn to_slice_spaced() {
+    // Long enough to be squeezed in several pieces.
+    let engine = Engine::STANDARD.ignore_whitespace();
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let text = crate::encode_mime(&dec);
+      let mut buf = vec![0; dec.len()];
+      let len = engine.decode_to_slice(&text, &mut buf);
+      assert_eq!(len, Ok(dec.len()), "case {i}");
+      assert_eq!(buf, dec, "case {i}");
+      assert_eq!(engine.decode_to_slice(enc, &mut buf), Ok(dec.len()));
+    }
+
+    let mut text = crate::encode_mime(&[0; 600]).into_bytes();
+    text[500] = b'!';
+    let mut buf = [0; 600];
+    let err = engine.decode_to_slice(&text, &mut buf).unwrap_err();
+    assert_eq!(err, engine.decode(&text).unwrap_err());
+    assert_eq!(err.offset(), Some(500));
+  }
+
+  #[test]
+  fn to_slice_padding_between_pieces() {
+    // The padding ends the first piece exactly.
+    let mut text = b"QUJD".repeat(63);
+    text.extend_from_slice(b"QQ==");
+    for engine in [
+      Engine::STANDARD.ignore_whitespace(),
+      Engine::STANDARD.ignore_whitespace().strict(),
+    ] {
+      let mut buf = [0; 300];
+      for rest in [&b" QUJD"[..], b"\n=", b" !"] {
+        let text = [&text[..], rest].concat();
+        let err = engine.decode_to_slice(&text, &mut buf);
+        assert_eq!(err, Err(crate::Error::InvalidPadding), "{rest:?}");
+        assert!(engine.decode(&text).is_err(), "{rest:?}");
+      }
+      for rest in [&b"\r\n"[..], b""] {
+        let text = [&text[..], rest].concat();
+        assert_eq!(engine.decode_to_slice(&text, &mut buf), Ok(190));
+      }
+    }
+  }
+
+  #[test]
+  fn padding() {
+    use super::Padding;
+
+    let policies = [Padding::Required, Padding::Optional, Padding::Forbidden];
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let nopad = crate::encode_nopad(&dec);
+      let padded = nopad.len() != enc.len();
+      for policy in policies {
+        let engine = Engine::STANDARD.decode_padding(policy);
+        let with = engine.decode(enc);
+        let without = engine.decode(nopad.as_bytes());
+        assert_eq!(with.is_ok(), policy != Padding::Forbidden || !padded);
+        assert_eq!(without.is_ok(), policy != Padding::Required || !padded);
+        assert_eq!(with.unwrap_or(dec.clone()), dec, "case {i}");
+        assert_eq!(without.unwrap_or(dec.clone()), dec, "case {i}");
+      }
+    }
+
+    // Unpadded engines can be made to accept padding, too.
+    let engine = Engine::URL_SAFE_NOPAD.decode_padding(Padding::Optional);
+    assert_eq!(engine.decode(b"_-8=").unwrap(), b"\xff\xef");
+    assert_eq!(engine.encode(b"\xff\xef"), "_-8");
+
+    let strict = Engine::STANDARD.strict().decode_padding(Padding::Optional);
+    assert!(strict.decode(b"QUI").is_ok());
+    assert!(strict.decode(b"QUI==").is_err());
+  }
+
+  #[test]
+  fn alphabet() {
+    let mut alphabet = *STANDARD;
+    alphabet[62] = b'.';
+    alphabet[63] = b'~';
+    let engine = Engine::new(&alphabet).unwrap();
+
+    for b in 0..255u8 {
+      let res = engine.decode([b, b, b'=', b'=']);
+      if b.is_ascii_alphanumeric() || b == b'.' || b == b'~' {
+        assert!(res.is_ok(), "{b:#04x} is valid data");
+      } else {
+        assert!(res.is_err(), "{b:#04x} is not valid data");
+      }
+    }
+  }
+
+  #[test]
+  fn wrap() {
+    let engine = Engine::STANDARD.wrap(76);
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let wrapped = engine.encode(&dec);
+      let expected = enc
+        .chunks(76)
+        .flat_map(|line| line.iter().copied().chain(*b"\n"))
+        .collect::<Vec<_>>();
+      assert_eq!(wrapped.as_bytes(), expected, "case {i}");
+      assert_eq!(&*engine.encode_boxed(&dec), wrapped, "case {i}");
+      assert_eq!(engine.decode(&wrapped).unwrap(), dec, "case {i}");
+    }
+
+    let mut buf = [0; 13];
+    let engine = Engine::STANDARD.wrap(4);
+    assert_eq!(engine.encode_to_slice(b"hello", &mut buf).unwrap(), 10);
+    assert_eq!(&buf[..10], b"aGVs\nbG8=\n");
+    let err = engine.encode_to_slice(b"hello", &mut buf[..9]);
+    assert!(matches!(err, Err(crate::Error::BufferTooSmall)));
+
+    let engine = Engine::STANDARD.wrap(64).line_ending(LineEnding::CrLf);
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let wrapped = engine.encode(&dec);
+      let expected = enc
+        .chunks(64)
+        .flat_map(|line| line.iter().copied().chain(*b"\r\n"))
+        .collect::<Vec<_>>();
+      assert_eq!(wrapped.as_bytes(), expected, "case {i}");
+      assert_eq!(engine.decode(&wrapped).unwrap(), dec, "case {i}");
+    }
+    assert_eq!(engine.encode_to_slice(b"hello", &mut buf).unwrap(), 10);
+    assert_eq!(&buf[..10], b"aGVsbG8=\r\n");
+  }
+
+  #[test]
+  fn wrap_lengths() {
+    // Lines shorter than a vector, ones that end partway through one, and ones
+    // that end right on the boundary between two.
+    let data = (0..300).map(|i| (i * 31) as u8).collect::<Vec<_>>();
+    for line_len in [4, 8, 12, 16, 20, 32, 60, 64, 76] {
+      for eol in [LineEnding::Lf, LineEnding::CrLf] {
+        for base in [Engine::STANDARD, Engine::URL_SAFE_NOPAD] {
+          let engine = base.wrap(line_len).line_ending(eol);
+          for len in 0..data.len() {
+            let expected = base
+              .encode(&data[..len])
+              .as_bytes()
+              .chunks(line_len)
+              .flat_map(|line| [line, eol.as_str().as_bytes()].concat())
+              .collect::<Vec<_>>();
+
+            let mut buf = vec![0xaa; expected.len() + 40];
+            let n = engine.encode_to_slice(&data[..len], &mut buf).unwrap();
+            assert_eq!(buf[..n], expected, "{line_len} {eol:?} len {len}");
+            assert!(buf[n..].iter().all(|&b| b == 0xaa));
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn writer() {
+    let data = (0..3000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engines = [
+      Engine::STANDARD,
+      Engine::URL_SAFE_NOPAD,
+      Engine::STANDARD.wrap(4).line_ending(LineEnding::CrLf),
+      Engine::STANDARD.wrap(76),
+    ];
+    for engine in &engines {
+      for len in [0, 1, 2, 3, 767, 768, 769, 3000] {
+        let mut out = Vec::new();
+        let n = engine.encode_to_writer(&data[..len], &mut out).unwrap();
+        assert_eq!(n, out.len());
+        assert_eq!(out, engine.encode(&data[..len]).as_bytes(), "len {len}");
+      }
+    }
+
+    let mut buf = [0; 5];
+    let err = crate::encode_to_writer(b"hello", &mut &mut buf[..]);
+    assert_eq!(err.unwrap_err().kind(), std::io::ErrorKind::WriteZero);
+  }
+
+  #[test]
+  #[should_panic]
+  fn wrap_multiple_of_four() {
+    let _ = Engine::STANDARD.wrap(3);
+  }
+
+  #[test]
+  fn config() {
+    use super::Alphabet;
+    use super::Config;
+    use super::Padding;
+
+    let engine = Config::new().build();
+    assert_eq!(engine.encode(b"\xfb\xff"), "+/8=");
+    assert_eq!(engine.decode(b"+/8").unwrap(), b"\xfb\xff");
+
+    let engine = Config::new()
+      .alphabet(Alphabet::UrlSafe)
+      .padding(Padding::Forbidden)
+      .build();
+    assert_eq!(engine.encode(b"\xfb\xff"), "-_8");
+    assert!(engine.decode(b"-_8=").is_err());
+
+    let engine = Config::new()
+      .padding(Padding::Required)
+      .strict(true)
+      .build();
+    assert!(engine.decode(b"QUI").is_err());
+    assert!(engine.decode(b"QUJ=").is_err());
+    assert!(engine.decode(b"QUI=").is_ok());
+
+    let engine = Config::new()
+      .alphabet(Alphabet::Bcrypt)
+      .lenient_length(true)
+      .ignore_whitespace(true)
+      .build();
+    assert_eq!(engine.encode(b"\x00\x10\x83"), "./AB");
+    assert_eq!(engine.decode(b"./\nAB.").unwrap(), b"\x00\x10\x83\x00");
+
+    let engine = Engine::from(Config::default().wrap(8));
+    assert_eq!(engine.encode(b"hello world"), "aGVsbG8g\nd29ybGQ=\n");
+  }
+}
@@ -0,0 +1,94 @@
+//! Extension traits for encoding and decoding in method position.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Engine;
+use crate::Error;
+
+/// Encodes bytes as base64 with `.to_base64()`.
+///
+/// This is implemented for everything that can be viewed as bytes, such as
+/// `[u8]`, `Vec<u8>`, and `str`.
+///
+/// ```
+/// use vb64::Engine;
+/// use vb64::ToBase64;
+///
+/// assert_eq!(b"hello".to_base64(), "aGVsbG8=");
+/// assert_eq!("hello".to_base64_with(&Engine::URL_SAFE_NOPAD), "aGVsbG8");
+/// ```
+pub trait ToBase64 {
+  /// Encodes `self` as standard, padded base64.
+  fn to_base64(&self) -> String;
+
+  /// Encodes `self` with the given engine's alphabet and padding.
+  fn to_base64_with(&self, engine: &Engine) -> String;
+}
+
+impl<T: AsRef<[u8]> + ?Sized> ToBase64 for T {
+  fn to_base64(&self) -> String {
+    crate::encode(self)
+  }
+
+  fn to_base64_with(&self, engine: &Engine) -> String {
+    engine.encode(self)
+  }
+}
+
+/// Decodes base64 with `.from_base64()`.
+///
+/// Like [`ToBase64`], this is implemented for everything that can be viewed
+/// as bytes.
+///
+/// ```
+/// use vb64::Engine;
+/// use vb64::FromBase64;
+///
+/// assert_eq!("aGVsbG8=".from_base64().unwrap(), b"hello");
+/// let engine = Engine::URL_SAFE_NOPAD;
+/// assert_eq!("aGVsbG8".from_base64_with(&engine).unwrap(), b"hello");
+/// ```
+// `from_*` would normally be a constructor, but this name is what other
+// encoding crates have taught people to reach for.
+#[allow(clippy::wrong_self_convention)]
+pub trait FromBase64 {
+  /// Decodes `self` as standard base64, with the same rules as
+  /// [`crate::decode()`].
+  fn from_base64(&self) -> Result<Vec<u8>, Error>;
+
+  /// Decodes `self` with the given engine.
+  fn from_base64_with(&self, engine: &Engine) -> Result<Vec<u8>, Error>;
+}
+
+impl<T: AsRef<[u8]> + ?Sized> FromBase64 for T {
+  fn from_base64(&self) -> Result<Vec<u8>, Error> {
+    crate::decode(self)
+  }
+
+  fn from_base64_with(&self, engine: &Engine) -> Result<Vec<u8>, Error> {
+    engine.decode(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn roundtrip() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      assert_eq!(dec.to_base64().as_bytes(), enc, "case {i}");
+      assert_eq!(enc.from_base64().unwrap(), dec, "case {i}");
+
+      let engine = Engine::URL_SAFE_NOPAD;
+      let url = dec.to_base64_with(&engine);
+      assert_eq!(url.from_base64_with(&engine).unwrap(), dec, "case {i}");
+    }
+
+    assert!("aGVsbG8!".from_base64().is_err());
+    assert!("aGVsbG8="
+      .from_base64_with(&Engine::URL_SAFE_NOPAD)
+      .is_err());
+  }
+}
@@ -0,0 +1,159 @@
+//! Encoding and decoding whole files.
+
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+use crate::DecoderReader;
+use crate::EncoderReader;
+use crate::Engine;
+
+/// Decodes standard base64, skipping line breaks and other whitespace, since
+/// base64 files are usually wrapped.
+const FILE: Engine = Engine::STANDARD.ignore_whitespace();
+
+/// Reads the file at `path` and encodes it as standard, padded base64.
+///
+/// The file is read a chunk at a time, so only the text is held in memory all
+/// at once.
+///
+/// ```no_run
+/// let text = vb64::encode_file("firmware.bin").unwrap();
+/// std::fs::write("firmware.b64", text).unwrap();
+/// ```
+pub fn encode_file(path: impl AsRef<Path>) -> io::Result<String> {
+  let file = File::open(path)?;
+  let len = file.metadata()?.len().try_into().unwrap_or(0);
+
+  // The length is only a hint, in case the file changes as we read it.
+  let mut out = Vec::with_capacity(crate::encoded_len(len, true).unwrap_or(0));
+  EncoderReader::new(file).read_to_end(&mut out)?;
+  // SAFETY: Encoders only produce ASCII.
+  Ok(unsafe { String::from_utf8_unchecked(out) })
+}
+
+/// Reads the file at `path` and decodes it as standard base64, ignoring
+/// whitespace, such as line breaks.
+///
+/// Invalid input is reported as an [`io::ErrorKind::InvalidData`] error
+/// wrapping an [`Error`][crate::Error]. Like [`encode_file()`], this reads the
+/// file a chunk at a time.
+///
+/// ```no_run
+/// let data = vb64::decode_file("firmware.b64").unwrap();
+/// std::fs::write("firmware.bin", data).unwrap();
+/// ```
+pub fn decode_file(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+  let file = File::open(path)?;
+  let len = file.metadata()?.len().try_into().unwrap_or(0);
+
+  let mut out = Vec::with_capacity(crate::max_decoded_len(len));
+  DecoderReader::with_engine(file, &FILE).read_to_end(&mut out)?;
+  Ok(out)
+}
+
+/// Like [`encode_file()`], but maps the file into memory instead of reading
+/// it, which saves copying it. Requires the `memmap2` feature.
+///
+/// This is only faster for big files, upwards of a megabyte or so; below that,
+/// setting up the mapping costs more than copying does.
+///
+/// # Panics
+///
+/// Panics if the encoded text wouldn't fit in memory, like [`crate::encode()`].
+///
+/// # Safety
+///
+/// The file must not be modified, by this process or any other, until this
+/// returns. Otherwise, its contents can change while they're being encoded,
+/// which is undefined behavior.
+#[cfg(feature = "memmap2")]
+pub unsafe fn encode_file_mmap(path: impl AsRef<Path>) -> io::Result<String> {
+  // SAFETY: Our caller promises not to modify the file.
+  let map = unsafe { memmap2::Mmap::map(&File::open(path)?)? };
+  Ok(crate::encode(&map))
+}
+
+/// Like [`decode_file()`], but maps the file into memory instead of reading
+/// it, as [`encode_file_mmap()`] does. Requires the `memmap2` feature.
+///
+/// # Safety
+///
+/// The file must not be modified, by this process or any other, until this
+/// returns.
+#[cfg(feature = "memmap2")]
+pub unsafe fn decode_file_mmap(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+  // SAFETY: Our caller promises not to modify the file.
+  let map = unsafe { memmap2::Mmap::map(&File::open(path)?)? };
+  Ok(FILE.decode(&map)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use super::*;
+
+  /// A file in the temporary directory that is deleted when dropped.
+  struct TempFile(PathBuf);
+
+  impl TempFile {
+    fn new(name: &str, contents: &[u8]) -> Self {
+      let name = format!("vb64-{}-{name}", std::process::id());
+      let path = std::env::temp_dir().join(name);
+      std::fs::write(&path, contents).unwrap();
+      Self(path)
+    }
+  }
+
+  impl Drop for TempFile {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_file(&self.0);
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    let data = (0..3 << 20).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    for len in [0, 1, 2, 3, 1000, data.len()] {
+      let file = TempFile::new("round-trip.bin", &data[..len]);
+      let text = encode_file(&file.0).unwrap();
+      assert_eq!(text, crate::encode(&data[..len]), "len {len}");
+
+      let file = TempFile::new("round-trip.b64", text.as_bytes());
+      assert_eq!(decode_file(&file.0).unwrap(), data[..len], "len {len}");
+
+      let text = crate::encode_mime(&data[..len]);
+      let file = TempFile::new("round-trip.txt", text.as_bytes());
+      assert_eq!(decode_file(&file.0).unwrap(), data[..len], "len {len}");
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "memmap2")]
+  fn round_trip_mmap() {
+    let data = (0..3 << 20).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    for len in [0, 1, 1000, data.len()] {
+      let file = TempFile::new("mmap.bin", &data[..len]);
+      let text = unsafe { encode_file_mmap(&file.0) }.unwrap();
+      assert_eq!(text, crate::encode(&data[..len]), "len {len}");
+
+      let text = crate::encode_mime(&data[..len]);
+      let file = TempFile::new("mmap.txt", text.as_bytes());
+      let got = unsafe { decode_file_mmap(&file.0) }.unwrap();
+      assert_eq!(got, data[..len], "len {len}");
+    }
+  }
+
+  #[test]
+  fn errors() {
+    let file = TempFile::new("errors.b64", b"aGVs!G8=\n");
+    let err = decode_file(&file.0).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let missing = file.0.with_extension("missing");
+    let err = encode_file(missing).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+  }
+}
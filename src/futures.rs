@@ -0,0 +1,499 @@
+//! Adapters between base64 and `futures-io` readers and writers.
+//!
+//! Requires the `futures-io` feature. These mirror the [`std::io`] adapters,
+//! such as [`crate::DecoderReader`], but implement [`AsyncRead`] and
+//! [`AsyncWrite`], which work with any runtime that speaks those traits, such
+//! as smol or async-std.
+//!
+//! The inner reader or writer must be [`Unpin`]; wrap it in [`Box::pin()`] if
+//! it isn't.
+
+use std::io;
+use std::pin::Pin;
+use std::task::ready;
+use std::task::Context;
+use std::task::Poll;
+
+use futures_io::AsyncRead;
+use futures_io::AsyncWrite;
+
+use crate::adapter::Codec;
+use crate::adapter::Pull;
+use crate::adapter::Push;
+use crate::io::BUF_LEN;
+use crate::Decoder;
+use crate::Encoder;
+use crate::Engine;
+
+/// An async reader that decodes the base64 text read from another reader.
+///
+/// Invalid input is reported as an [`io::ErrorKind::InvalidData`] error
+/// wrapping an [`Error`][crate::Error].
+pub struct DecoderReader<'a, R> {
+  inner: R,
+  state: Box<Pull<Decoder<'a>, BUF_LEN>>,
+}
+
+impl<R: AsyncRead + Unpin> DecoderReader<'static, R> {
+  /// Creates a reader that decodes standard base64.
+  pub fn new(inner: R) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, R: AsyncRead + Unpin> DecoderReader<'a, R> {
+  /// Creates a reader that decodes with the given engine's rules.
+  pub fn with_engine(inner: R, engine: &'a Engine) -> Self {
+    Self::with_decoder(inner, Decoder::with_engine(engine))
+  }
+
+  /// Creates a reader that feeds its input through `decoder`, such as one
+  /// with a [limit][Decoder::limit()].
+  pub fn with_decoder(inner: R, decoder: Decoder<'a>) -> Self {
+    Self {
+      inner,
+      state: Box::new(Pull::new(decoder)),
+    }
+  }
+
+  /// Returns a reference to the inner reader.
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  ///
+  /// Reading from it directly will skip over some of the text.
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  /// Returns the inner reader, discarding any text that was read from it but
+  /// not yet decoded.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for DecoderReader<'_, R> {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &mut [u8],
+  ) -> Poll<io::Result<usize>> {
+    let this = self.get_mut();
+    poll_pull(&mut this.state, Pin::new(&mut this.inner), cx, buf)
+  }
+}
+
+/// An async reader that encodes the data read from another reader as base64.
+pub struct EncoderReader<'a, R> {
+  inner: R,
+  state: Box<Pull<Encoder<'a>, BUF_LEN>>,
+}
+
+impl<R: AsyncRead + Unpin> EncoderReader<'static, R> {
+  /// Creates a reader that encodes standard, padded base64.
+  pub fn new(inner: R) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, R: AsyncRead + Unpin> EncoderReader<'a, R> {
+  /// Creates a reader that encodes with the given engine's alphabet, padding,
+  /// and line wrapping.
+  pub fn with_engine(inner: R, engine: &'a Engine) -> Self {
+    Self {
+      inner,
+      state: Box::new(Pull::new(Encoder::with_engine(engine))),
+    }
+  }
+
+  /// Returns a reference to the inner reader.
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  ///
+  /// Reading from it directly will skip over some of the data.
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  /// Returns the inner reader, discarding any data that was read from it but
+  /// not yet encoded.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncoderReader<'_, R> {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    buf: &mut [u8],
+  ) -> Poll<io::Result<usize>> {
+    let this = self.get_mut();
+    poll_pull(&mut this.state, Pin::new(&mut this.inner), cx, buf)
+  }
+}
+
+/// An async writer that encodes everything written to it as base64 and writes
+/// the text to another writer.
+///
+/// The last partial group is only encoded when the writer is closed, which
+/// also closes the inner writer. Unlike [`crate::EncoderWriter`], dropping
+/// the writer without closing it loses that group, since there's nowhere to
+/// wait for it to be written.
+pub struct EncoderWriter<'a, W> {
+  inner: W,
+  state: Box<Push<Encoder<'a>, BUF_LEN>>,
+}
+
+impl<W: AsyncWrite + Unpin> EncoderWriter<'static, W> {
+  /// Creates a writer that encodes standard, padded base64.
+  pub fn new(inner: W) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, W: AsyncWrite + Unpin> EncoderWriter<'a, W> {
+  /// Creates a writer that encodes with the given engine's alphabet, padding,
+  /// and line wrapping.
+  pub fn with_engine(inner: W, engine: &'a Engine) -> Self {
+    Self {
+      inner,
+      state: Box::new(Push::new(Encoder::with_engine(engine))),
+    }
+  }
+
+  /// Returns a reference to the inner writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  ///
+  /// Writing to it directly will corrupt the text.
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  /// Returns the inner writer, discarding any text that hasn't been written
+  /// to it yet; close this writer first to avoid that.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncoderWriter<'_, W> {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    data: &[u8],
+  ) -> Poll<io::Result<usize>> {
+    let this = self.get_mut();
+    poll_push(&mut this.state, Pin::new(&mut this.inner), cx, data)
+  }
+
+  fn poll_flush(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+  ) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    poll_flush(&mut this.state, Pin::new(&mut this.inner), cx)
+  }
+
+  fn poll_close(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+  ) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    poll_close(&mut this.state, Pin::new(&mut this.inner), cx)
+  }
+}
+
+/// An async writer that decodes the base64 text written to it and writes the
+/// data to another writer.
+///
+/// Invalid input is reported as an [`io::ErrorKind::InvalidData`] error
+/// wrapping an [`Error`][crate::Error], either by the write that contains it
+/// or, for problems with the end of the input, by closing the writer, which
+/// also closes the inner writer. Dropping the writer without closing it skips
+/// those checks, and loses whatever was left over.
+pub struct DecoderWriter<'a, W> {
+  inner: W,
+  state: Box<Push<Decoder<'a>, BUF_LEN>>,
+}
+
+impl<W: AsyncWrite + Unpin> DecoderWriter<'static, W> {
+  /// Creates a writer that decodes standard base64.
+  pub fn new(inner: W) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, W: AsyncWrite + Unpin> DecoderWriter<'a, W> {
+  /// Creates a writer that decodes with the given engine's rules.
+  pub fn with_engine(inner: W, engine: &'a Engine) -> Self {
+    Self::with_decoder(inner, Decoder::with_engine(engine))
+  }
+
+  /// Creates a writer that feeds its input through `decoder`, such as one
+  /// with a [limit][Decoder::limit()].
+  pub fn with_decoder(inner: W, decoder: Decoder<'a>) -> Self {
+    Self {
+      inner,
+      state: Box::new(Push::new(decoder)),
+    }
+  }
+
+  /// Returns a reference to the inner writer.
+  pub fn get_ref(&self) -> &W {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  ///
+  /// Writing to it directly will interleave with the decoded data.
+  pub fn get_mut(&mut self) -> &mut W {
+    &mut self.inner
+  }
+
+  /// Returns the inner writer, discarding any data that hasn't been written
+  /// to it yet; close this writer first to avoid that.
+  pub fn into_inner(self) -> W {
+    self.inner
+  }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for DecoderWriter<'_, W> {
+  fn poll_write(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+    text: &[u8],
+  ) -> Poll<io::Result<usize>> {
+    let this = self.get_mut();
+    poll_push(&mut this.state, Pin::new(&mut this.inner), cx, text)
+  }
+
+  fn poll_flush(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+  ) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    poll_flush(&mut this.state, Pin::new(&mut this.inner), cx)
+  }
+
+  fn poll_close(
+    self: Pin<&mut Self>,
+    cx: &mut Context,
+  ) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    poll_close(&mut this.state, Pin::new(&mut this.inner), cx)
+  }
+}
+
+/// Implements [`AsyncRead::poll_read()`], reading input from `inner` as
+/// needed.
+fn poll_pull<C: Codec>(
+  state: &mut Pull<C, BUF_LEN>,
+  mut inner: Pin<&mut impl AsyncRead>,
+  cx: &mut Context,
+  buf: &mut [u8],
+) -> Poll<io::Result<usize>> {
+  while let Some(input) = state.wants_input() {
+    let n = match ready!(inner.as_mut().poll_read(cx, input)) {
+      Ok(n) => n,
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+      Err(e) => return Poll::Ready(Err(e)),
+    };
+    state.fill(n)?;
+  }
+  Poll::Ready(Ok(state.read(buf)))
+}
+
+/// Implements [`AsyncWrite::poll_write()`], first writing out pending output
+/// if there's a lot of it.
+fn poll_push<C: Codec>(
+  state: &mut Push<C, BUF_LEN>,
+  inner: Pin<&mut impl AsyncWrite>,
+  cx: &mut Context,
+  input: &[u8],
+) -> Poll<io::Result<usize>> {
+  if state.is_full() {
+    ready!(poll_drain(state, inner, cx))?;
+  }
+  Poll::Ready(Ok(state.push(input)?))
+}
+
+/// Implements [`AsyncWrite::poll_flush()`].
+fn poll_flush<C: Codec>(
+  state: &mut Push<C, BUF_LEN>,
+  mut inner: Pin<&mut impl AsyncWrite>,
+  cx: &mut Context,
+) -> Poll<io::Result<()>> {
+  ready!(poll_drain(state, inner.as_mut(), cx))?;
+  inner.poll_flush(cx)
+}
+
+/// Implements [`AsyncWrite::poll_close()`], finishing the codec first.
+fn poll_close<C: Codec>(
+  state: &mut Push<C, BUF_LEN>,
+  mut inner: Pin<&mut impl AsyncWrite>,
+  cx: &mut Context,
+) -> Poll<io::Result<()>> {
+  state.finish()?;
+  ready!(poll_drain(state, inner.as_mut(), cx))?;
+  inner.poll_close(cx)
+}
+
+/// Writes out all the unwritten output.
+fn poll_drain<C: Codec>(
+  state: &mut Push<C, BUF_LEN>,
+  mut inner: Pin<&mut impl AsyncWrite>,
+  cx: &mut Context,
+) -> Poll<io::Result<()>> {
+  while !state.unwritten().is_empty() {
+    match ready!(inner.as_mut().poll_write(cx, state.unwritten())) {
+      Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+      Ok(n) => state.advance(n),
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+      Err(e) => return Poll::Ready(Err(e)),
+    }
+  }
+  Poll::Ready(Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::task::Waker;
+
+  use super::*;
+
+  /// Polls `f` until it's ready; everything here is in memory, so it only
+  /// ever returns `Pending` to simulate a slow peer.
+  fn block_on<T>(mut f: impl FnMut(&mut Context) -> Poll<T>) -> T {
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+      if let Poll::Ready(x) = f(&mut cx) {
+        return x;
+      }
+    }
+  }
+
+  /// A reader that hands out at most `.1` bytes at a time, and is pending
+  /// every other poll.
+  struct Trickle<'a>(&'a [u8], usize, bool);
+
+  impl AsyncRead for Trickle<'_> {
+    fn poll_read(
+      mut self: Pin<&mut Self>,
+      _: &mut Context,
+      buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+      self.2 = !self.2;
+      if self.2 {
+        return Poll::Pending;
+      }
+      let n = buf.len().min(self.1).min(self.0.len());
+      buf[..n].copy_from_slice(&self.0[..n]);
+      self.0 = &self.0[n..];
+      Poll::Ready(Ok(n))
+    }
+  }
+
+  /// A writer that accepts at most `.1` bytes at a time, and is pending every
+  /// other poll.
+  struct Sip(Vec<u8>, usize, bool, bool);
+
+  impl AsyncWrite for Sip {
+    fn poll_write(
+      mut self: Pin<&mut Self>,
+      _: &mut Context,
+      data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+      self.2 = !self.2;
+      if self.2 {
+        return Poll::Pending;
+      }
+      let n = data.len().min(self.1);
+      self.0.extend_from_slice(&data[..n]);
+      Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(
+      self: Pin<&mut Self>,
+      _: &mut Context,
+    ) -> Poll<io::Result<()>> {
+      Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+      mut self: Pin<&mut Self>,
+      _: &mut Context,
+    ) -> Poll<io::Result<()>> {
+      self.3 = true;
+      Poll::Ready(Ok(()))
+    }
+  }
+
+  fn read_all(mut r: Pin<&mut impl AsyncRead>) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut buf = [0; 100];
+    loop {
+      match block_on(|cx| r.as_mut().poll_read(cx, &mut buf))? {
+        0 => return Ok(out),
+        n => out.extend_from_slice(&buf[..n]),
+      }
+    }
+  }
+
+  fn write_all(
+    mut w: Pin<&mut impl AsyncWrite>,
+    mut data: &[u8],
+    chunk: usize,
+  ) -> io::Result<()> {
+    while !data.is_empty() {
+      let len = data.len().min(chunk);
+      let n = block_on(|cx| w.as_mut().poll_write(cx, &data[..len]))?;
+      data = &data[n..];
+    }
+    block_on(|cx| w.as_mut().poll_close(cx))
+  }
+
+  #[test]
+  fn readers() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let mut r = DecoderReader::new(Trickle(enc, 7, false));
+      assert_eq!(read_all(Pin::new(&mut r)).unwrap(), dec, "case {i}");
+
+      let mut r = EncoderReader::new(Trickle(&dec, 7, false));
+      assert_eq!(read_all(Pin::new(&mut r)).unwrap(), enc, "case {i}");
+    }
+
+    let mut r = DecoderReader::new(Trickle(b"aGVs!G8=", 3, false));
+    let err = read_all(Pin::new(&mut r)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  }
+
+  #[test]
+  fn writers() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let mut w = EncoderWriter::new(Sip(Vec::new(), 5, false, false));
+      write_all(Pin::new(&mut w), &dec, 11).unwrap();
+      let sip = w.into_inner();
+      assert!(sip.3);
+      assert_eq!(sip.0, enc, "case {i}");
+
+      let mut w = DecoderWriter::new(Sip(Vec::new(), 5, false, false));
+      write_all(Pin::new(&mut w), enc, 11).unwrap();
+      assert_eq!(w.into_inner().0, dec, "case {i}");
+    }
+
+    let mut w = DecoderWriter::new(Sip(Vec::new(), 5, false, false));
+    let err = write_all(Pin::new(&mut w), b"aGVsb", 3).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  }
+}
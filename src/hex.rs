@@ -0,0 +1,216 @@
+//! Hex, also known as base16.
+//!
+//! ```
+//! assert_eq!(vb64::hex::encode(b"\xca\xfe"), "cafe");
+//! assert_eq!(vb64::hex::encode_upper(b"\xca\xfe"), "CAFE");
+//! assert_eq!(vb64::hex::decode(b"CaFe").unwrap(), b"\xca\xfe");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::LaneCount;
+use core::simd::Simd;
+use core::simd::SupportedLaneCount;
+
+use crate::read_slice_padded;
+use crate::simd;
+use crate::Error;
+
+/// Decodes some hex `data` to a fresh vector.
+///
+/// Both uppercase and lowercase digits are accepted, even when mixed together.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as lowercase hex.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Encodes arbitrary data as uppercase hex.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_upper(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_upper_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some hex data and appends it to `out`.
+pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  if data.len() % 2 != 0 {
+    return Err(Error::InvalidLength);
+  }
+
+  if cfg!(target_feature = "avx2") {
+    decode_tunable::<32>(data, out)
+  } else {
+    decode_tunable::<16>(data, out)
+  }
+}
+
+/// Encodes arbitrary data as lowercase hex and appends it to `out`.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_to(data: &[u8], out: &mut Vec<u8>) {
+  encode_tunable::<16>(data, out, &simd::HEX)
+}
+
+/// Encodes arbitrary data as uppercase hex and appends it to `out`.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_upper_to(data: &[u8], out: &mut Vec<u8>) {
+  encode_tunable::<16>(data, out, &simd::HEX_UPPER)
+}
+
+/// Decodes `data`, which must have even length.
+fn decode_tunable<const N: usize>(
+  data: &[u8],
+  out: &mut Vec<u8>,
+) -> Result<(), Error>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  if data.is_empty() {
+    return Ok(());
+  }
+
+  // NOTE: Always a full N bytes of slop so we can do full SIMD stores.
+  crate::reserve(out, (data.len() / 2).checked_add(N))?;
+  let out_start = out.spare_capacity_mut().as_mut_ptr().cast::<u8>();
+  let mut raw_out = out_start;
+
+  let mut chunks = data.chunks_exact(N);
+  let mut failed = false;
+  for chunk in &mut chunks {
+    let (decoded, ok) = simd::decode16(Simd::from_slice(chunk), &simd::HEX);
+    failed |= !ok;
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(decoded);
+      raw_out = raw_out.add(N / 2);
+    }
+  }
+
+  let rest = chunks.remainder();
+  if !rest.is_empty() {
+    let ascii = unsafe { read_slice_padded::<N>(rest, b'0') };
+    let (decoded, ok) = simd::decode16(ascii, &simd::HEX);
+    failed |= !ok;
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(decoded);
+      raw_out = raw_out.add(rest.len() / 2);
+    }
+  }
+
+  if failed {
+    return Err(crate::invalid_byte(data, &simd::HEX));
+  }
+
+  unsafe {
+    let written = raw_out.offset_from(out_start);
+    out.set_len(out.len() + written as usize);
+  }
+
+  Ok(())
+}
+
+fn encode_tunable<const N: usize>(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+) where
+  LaneCount<N>: SupportedLaneCount,
+{
+  if data.is_empty() {
+    return;
+  }
+
+  // NOTE: Always a full N bytes of slop so we can do full SIMD stores.
+  let len = data.len().checked_mul(2).and_then(|n| n.checked_add(N));
+  out.reserve(len.expect("hex output too large"));
+  let out_start = out.spare_capacity_mut().as_mut_ptr().cast::<u8>();
+  let mut raw_out = out_start;
+
+  // Each step consumes half a vector, but we do full loads for as long as
+  // there's a full vector's worth of input left.
+  let mut rest = data;
+  while rest.len() >= N {
+    let encoded = simd::encode16(Simd::from_slice(&rest[..N]), luts);
+    rest = &rest[N / 2..];
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(encoded);
+      raw_out = raw_out.add(N);
+    }
+  }
+
+  while !rest.is_empty() {
+    let chunk = &rest[..rest.len().min(N / 2)];
+    let encoded =
+      simd::encode16(unsafe { read_slice_padded::<N>(chunk, 0) }, luts);
+    rest = &rest[chunk.len()..];
+
+    unsafe {
+      raw_out.cast::<Simd<u8, N>>().write_unaligned(encoded);
+      raw_out = raw_out.add(chunk.len() * 2);
+    }
+  }
+
+  unsafe {
+    let written = raw_out.offset_from(out_start);
+    out.set_len(out.len() + written as usize);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn random() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      let lower = dec.iter().map(|b| format!("{b:02x}")).collect::<String>();
+      let upper = lower.to_uppercase();
+
+      assert_eq!(encode(&dec), lower, "case {i}");
+      assert_eq!(encode_upper(&dec), upper, "case {i}");
+      assert_eq!(decode(lower.as_bytes()).unwrap(), dec, "case {i}");
+      assert_eq!(decode(upper.as_bytes()).unwrap(), dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn alphabet() {
+    for b in 0..255u8 {
+      let res = decode(&[b'0', b]);
+      if b.is_ascii_hexdigit() {
+        assert_eq!(res.unwrap(), [(b as char).to_digit(16).unwrap() as u8]);
+      } else {
+        assert!(res.is_err(), "{b:#04x}");
+      }
+    }
+  }
+
+  #[test]
+  fn odd_length() {
+    assert!(decode(b"0").is_err());
+    assert!(decode(b"00112233445566778").is_err());
+  }
+}
@@ -0,0 +1,234 @@
+//! Decoding and encoding into fixed-capacity and inline buffers, for small
+//! payloads like keys and UUIDs that aren't worth a heap allocation.
+//!
+//! Each of these is behind a feature named after the crate it works with.
+
+use core::mem::MaybeUninit;
+
+use crate::Error;
+
+/// Views the `cap` bytes at `ptr` as a buffer for the kernels to write into.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `cap` bytes for as long as the returned
+/// slice is alive.
+unsafe fn uninit<'a>(ptr: *mut u8, cap: usize) -> &'a mut [MaybeUninit<u8>] {
+  unsafe { core::slice::from_raw_parts_mut(ptr.cast(), cap) }
+}
+
+#[cfg(feature = "smallvec")]
+mod smallvec_impl {
+  use smallvec::Array;
+  use smallvec::SmallVec;
+
+  use super::*;
+
+  /// Decodes some base64 `data` into a [`SmallVec`], which only spills onto
+  /// the heap if the decoded data doesn't fit inline.
+  ///
+  /// Requires the `smallvec` feature.
+  ///
+  /// ```
+  /// let key = vb64::decode_smallvec::<[u8; 16]>(b"aGVsbG8=").unwrap();
+  /// assert_eq!(&key[..], b"hello");
+  /// assert!(!key.spilled());
+  /// ```
+  pub fn decode_smallvec<A: Array<Item = u8>>(
+    data: impl AsRef<[u8]>,
+  ) -> Result<SmallVec<A>, Error> {
+    let data = data.as_ref();
+    let mut out = SmallVec::new();
+    out
+      .try_reserve(crate::decoded_len(data))
+      .map_err(|_| Error::CapacityOverflow)?;
+    // SAFETY: This is all of `out`'s capacity, which is free.
+    let buf = unsafe { uninit(out.as_mut_ptr(), out.capacity()) };
+    let len = crate::decode_to_uninit(data, buf)?;
+    unsafe { out.set_len(len) };
+    Ok(out)
+  }
+
+  /// Encodes arbitrary data as base64 into a [`SmallVec`], which only spills
+  /// onto the heap if the encoded text doesn't fit inline.
+  ///
+  /// Requires the `smallvec` feature. The encoded text is all ASCII.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the encoded data wouldn't fit in memory.
+  pub fn encode_smallvec<A: Array<Item = u8>>(
+    data: impl AsRef<[u8]>,
+  ) -> SmallVec<A> {
+    let data = data.as_ref();
+    let len =
+      crate::encoded_len(data.len(), true).expect("base64 output too large");
+    let mut out = SmallVec::new();
+    out.reserve(len);
+    // SAFETY: This is all of `out`'s capacity, which is free.
+    let buf = unsafe { uninit(out.as_mut_ptr(), out.capacity()) };
+    let len = crate::encode_to_uninit(data, buf).unwrap();
+    unsafe { out.set_len(len) };
+    out
+  }
+}
+#[cfg(feature = "smallvec")]
+pub use smallvec_impl::*;
+
+#[cfg(feature = "arrayvec")]
+mod arrayvec_impl {
+  use arrayvec::ArrayString;
+  use arrayvec::ArrayVec;
+
+  use super::*;
+
+  /// Decodes some base64 `data` into an [`ArrayVec`].
+  ///
+  /// Requires the `arrayvec` feature. Returns [`Error::BufferTooSmall`] if the
+  /// decoded data is longer than `N`.
+  ///
+  /// ```
+  /// let key = vb64::decode_arrayvec::<16>(b"aGVsbG8=").unwrap();
+  /// assert_eq!(&key[..], b"hello");
+  /// assert!(vb64::decode_arrayvec::<4>(b"aGVsbG8=").is_err());
+  /// ```
+  pub fn decode_arrayvec<const N: usize>(
+    data: impl AsRef<[u8]>,
+  ) -> Result<ArrayVec<u8, N>, Error> {
+    let mut out = ArrayVec::new();
+    let len = crate::decode_to_uninit(data, out.spare_capacity_mut())?;
+    unsafe { out.set_len(len) };
+    Ok(out)
+  }
+
+  /// Encodes arbitrary data as base64 into an [`ArrayString`].
+  ///
+  /// Requires the `arrayvec` feature. Returns [`Error::BufferTooSmall`] if the
+  /// encoded text is longer than `N`.
+  ///
+  /// ```
+  /// let text = vb64::encode_arraystring::<16>(b"hello").unwrap();
+  /// assert_eq!(text.as_str(), "aGVsbG8=");
+  /// ```
+  pub fn encode_arraystring<const N: usize>(
+    data: impl AsRef<[u8]>,
+  ) -> Result<ArrayString<N>, Error> {
+    let mut out = ArrayString::new();
+    // SAFETY: This is all of `out`'s capacity, which is free.
+    let buf = unsafe { uninit(out.as_mut_ptr(), N) };
+    let len = crate::encode_to_uninit(data, buf)?;
+    // SAFETY: Encoders only produce ASCII.
+    unsafe { out.set_len(len) };
+    Ok(out)
+  }
+}
+#[cfg(feature = "arrayvec")]
+pub use arrayvec_impl::*;
+
+#[cfg(feature = "heapless")]
+mod heapless_impl {
+  use heapless::String;
+  use heapless::Vec;
+
+  use super::*;
+
+  /// Decodes some base64 `data` into a [`heapless::Vec`].
+  ///
+  /// Requires the `heapless` feature. Returns [`Error::BufferTooSmall`] if the
+  /// decoded data is longer than `N`.
+  ///
+  /// ```
+  /// let key = vb64::decode_heapless::<16>(b"aGVsbG8=").unwrap();
+  /// assert_eq!(&key[..], b"hello");
+  /// ```
+  pub fn decode_heapless<const N: usize>(
+    data: impl AsRef<[u8]>,
+  ) -> Result<Vec<u8, N>, Error> {
+    let mut out = Vec::new();
+    let len = crate::decode_to_uninit(data, spare(&mut out))?;
+    unsafe { out.set_len(len) };
+    Ok(out)
+  }
+
+  /// Encodes arbitrary data as base64 into a [`heapless::String`].
+  ///
+  /// Requires the `heapless` feature. Returns [`Error::BufferTooSmall`] if the
+  /// encoded text is longer than `N`.
+  ///
+  /// ```
+  /// let text = vb64::encode_heapless::<16>(b"hello").unwrap();
+  /// assert_eq!(text, "aGVsbG8=");
+  /// ```
+  pub fn encode_heapless<const N: usize>(
+    data: impl AsRef<[u8]>,
+  ) -> Result<String<N>, Error> {
+    let mut out = Vec::new();
+    let len = crate::encode_to_uninit(data, spare(&mut out))?;
+    unsafe { out.set_len(len) };
+    // SAFETY: Encoders only produce ASCII.
+    Ok(unsafe { String::from_utf8_unchecked(out) })
+  }
+
+  /// Returns all of `vec`'s capacity, which must be empty.
+  fn spare<const N: usize>(vec: &mut Vec<u8, N>) -> &mut [MaybeUninit<u8>] {
+    debug_assert!(vec.is_empty());
+    // SAFETY: An empty `Vec` has `N` free bytes of capacity.
+    unsafe { uninit(vec.as_mut_ptr(), N) }
+  }
+}
+#[cfg(feature = "heapless")]
+pub use heapless_impl::*;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  #[cfg(feature = "smallvec")]
+  fn smallvec() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let decoded = decode_smallvec::<[u8; 32]>(enc).unwrap();
+      assert_eq!(&decoded[..], dec, "case {i}");
+      assert_eq!(decoded.spilled(), dec.len() > 32, "case {i}");
+      let encoded = encode_smallvec::<[u8; 32]>(&dec);
+      assert_eq!(&encoded[..], enc, "case {i}");
+    }
+    assert!(decode_smallvec::<[u8; 4]>(b"QUJD!").is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "arrayvec")]
+  fn arrayvec() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      match decode_arrayvec::<64>(enc) {
+        Ok(decoded) => assert_eq!(&decoded[..], dec, "case {i}"),
+        Err(e) => assert!(dec.len() > 64, "case {i}: {e}"),
+      }
+      match encode_arraystring::<64>(&dec) {
+        Ok(encoded) => assert_eq!(encoded.as_bytes(), enc, "case {i}"),
+        Err(e) => assert!(enc.len() > 64, "case {i}: {e}"),
+      }
+    }
+
+    // Exactly full, so the last store has nowhere to spill.
+    assert_eq!(&decode_arrayvec::<3>(b"QUJD").unwrap()[..], b"ABC");
+    assert_eq!(&encode_arraystring::<4>(b"ABC").unwrap()[..], "QUJD");
+    assert!(encode_arraystring::<3>(b"ABC").is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "heapless")]
+  fn heapless() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      match decode_heapless::<64>(enc) {
+        Ok(decoded) => assert_eq!(&decoded[..], dec, "case {i}"),
+        Err(e) => assert!(dec.len() > 64, "case {i}: {e}"),
+      }
+      match encode_heapless::<64>(&dec) {
+        Ok(encoded) => assert_eq!(encoded.as_bytes(), enc, "case {i}"),
+        Err(e) => assert!(enc.len() > 64, "case {i}: {e}"),
+      }
+    }
+    assert!(decode_heapless::<2>(b"QUJD").is_err());
+  }
+}
@@ -0,0 +1,511 @@
+//! Adapters between base64 and `std::io` readers and writers.
+
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use crate::adapter::Codec;
+use crate::adapter::Pull;
+use crate::adapter::Push;
+use crate::Decoder;
+use crate::Encoder;
+use crate::Engine;
+
+/// How much the adapters read from or write to their inner reader or writer
+/// at a time: big enough that nearly all of it goes through the SIMD kernels
+/// in one call.
+pub(crate) const BUF_LEN: usize = 8 * 1024;
+
+/// A reader that decodes the base64 text read from another reader.
+///
+/// Invalid input is reported as an [`io::ErrorKind::InvalidData`] error
+/// wrapping an [`Error`][crate::Error].
+///
+/// ```
+/// use std::io::Read;
+///
+/// let mut reader = vb64::DecoderReader::new(&b"aGVsbG8gd29ybGQ="[..]);
+/// let mut data = String::new();
+/// reader.read_to_string(&mut data).unwrap();
+/// assert_eq!(data, "hello world");
+/// ```
+pub struct DecoderReader<'a, R> {
+  inner: R,
+  state: Box<Pull<Decoder<'a>, BUF_LEN>>,
+}
+
+impl<R: Read> DecoderReader<'static, R> {
+  /// Creates a reader that decodes standard base64.
+  pub fn new(inner: R) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, R: Read> DecoderReader<'a, R> {
+  /// Creates a reader that decodes with the given engine's rules.
+  pub fn with_engine(inner: R, engine: &'a Engine) -> Self {
+    Self::with_decoder(inner, Decoder::with_engine(engine))
+  }
+
+  /// Creates a reader that feeds its input through `decoder`, such as one
+  /// with a [limit][Decoder::limit()].
+  pub fn with_decoder(inner: R, decoder: Decoder<'a>) -> Self {
+    Self {
+      inner,
+      state: Box::new(Pull::new(decoder)),
+    }
+  }
+
+  /// Returns a reference to the inner reader.
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  ///
+  /// Reading from it directly will skip over some of the text.
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  /// Returns the inner reader, discarding any text that was read from it but
+  /// not yet decoded.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: Read> Read for DecoderReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.state.read_from(&mut self.inner, buf)
+  }
+}
+
+/// A reader that encodes the data read from another reader as base64, such as
+/// for streaming an encoded request body without building it in memory.
+///
+/// ```
+/// use std::io::Read;
+///
+/// let mut reader = vb64::EncoderReader::new(&b"hello world"[..]);
+/// let mut text = String::new();
+/// reader.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "aGVsbG8gd29ybGQ=");
+/// ```
+pub struct EncoderReader<'a, R> {
+  inner: R,
+  state: Box<Pull<Encoder<'a>, BUF_LEN>>,
+}
+
+impl<R: Read> EncoderReader<'static, R> {
+  /// Creates a reader that encodes standard, padded base64.
+  pub fn new(inner: R) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, R: Read> EncoderReader<'a, R> {
+  /// Creates a reader that encodes with the given engine's alphabet, padding,
+  /// and line wrapping.
+  pub fn with_engine(inner: R, engine: &'a Engine) -> Self {
+    Self {
+      inner,
+      state: Box::new(Pull::new(Encoder::with_engine(engine))),
+    }
+  }
+
+  /// Returns a reference to the inner reader.
+  pub fn get_ref(&self) -> &R {
+    &self.inner
+  }
+
+  /// Returns a mutable reference to the inner reader.
+  ///
+  /// Reading from it directly will skip over some of the data.
+  pub fn get_mut(&mut self) -> &mut R {
+    &mut self.inner
+  }
+
+  /// Returns the inner reader, discarding any data that was read from it but
+  /// not yet encoded.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+}
+
+impl<R: Read> Read for EncoderReader<'_, R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.state.read_from(&mut self.inner, buf)
+  }
+}
+
+/// A writer that encodes everything written to it as base64 and writes the
+/// text to another writer.
+///
+/// Since the last partial group can't be encoded until all the data has been
+/// written, call [`EncoderWriter::finish()`] when done. Dropping the writer
+/// also finishes it, but ignores any errors.
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut writer = vb64::EncoderWriter::new(Vec::new());
+/// writer.write_all(b"hello ").unwrap();
+/// writer.write_all(b"world").unwrap();
+/// assert_eq!(writer.finish().unwrap(), b"aGVsbG8gd29ybGQ=");
+/// ```
+pub struct EncoderWriter<'a, W: Write> {
+  /// The inner writer, until `finish()` takes it.
+  inner: Option<W>,
+  state: Box<Push<Encoder<'a>, BUF_LEN>>,
+}
+
+impl<W: Write> EncoderWriter<'static, W> {
+  /// Creates a writer that encodes standard, padded base64.
+  pub fn new(inner: W) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, W: Write> EncoderWriter<'a, W> {
+  /// Creates a writer that encodes with the given engine's alphabet, padding,
+  /// and line wrapping.
+  pub fn with_engine(inner: W, engine: &'a Engine) -> Self {
+    Self {
+      inner: Some(inner),
+      state: Box::new(Push::new(Encoder::with_engine(engine))),
+    }
+  }
+
+  /// Returns a reference to the inner writer.
+  pub fn get_ref(&self) -> &W {
+    self.inner.as_ref().unwrap()
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  ///
+  /// Writing to it directly will corrupt the text.
+  pub fn get_mut(&mut self) -> &mut W {
+    self.inner.as_mut().unwrap()
+  }
+
+  /// Encodes the last partial group, with its padding, writes out all the
+  /// remaining text, and returns the inner writer.
+  pub fn finish(mut self) -> io::Result<W> {
+    // Take the writer first, so `drop()` doesn't finish a second time.
+    let mut inner = self.inner.take().unwrap();
+    self.state.finish()?;
+    self.state.write_to(&mut inner)?;
+    Ok(inner)
+  }
+}
+
+impl<W: Write> Write for EncoderWriter<'_, W> {
+  fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+    if self.state.is_full() {
+      self.state.write_to(self.inner.as_mut().unwrap())?;
+    }
+    Ok(self.state.push(data)?)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    let inner = self.inner.as_mut().unwrap();
+    self.state.write_to(inner)?;
+    inner.flush()
+  }
+}
+
+impl<W: Write> Drop for EncoderWriter<'_, W> {
+  fn drop(&mut self) {
+    if let Some(inner) = &mut self.inner {
+      if !std::thread::panicking() && self.state.finish().is_ok() {
+        let _ = self.state.write_to(inner);
+      }
+    }
+  }
+}
+
+/// A writer that decodes the base64 text written to it and writes the data to
+/// another writer, such as for decoding a MIME part as it streams in.
+///
+/// Invalid input is reported as an [`io::ErrorKind::InvalidData`] error
+/// wrapping an [`Error`][crate::Error], either by the write that contains it
+/// or, for problems with the end of the input, by
+/// [`DecoderWriter::finish()`]. Dropping the writer also finishes it, but
+/// ignores any errors.
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut writer = vb64::DecoderWriter::new(Vec::new());
+/// writer.write_all(b"aGVsbG8gd2").unwrap();
+/// writer.write_all(b"9ybGQ=").unwrap();
+/// assert_eq!(writer.finish().unwrap(), b"hello world");
+/// ```
+pub struct DecoderWriter<'a, W: Write> {
+  /// The inner writer, until `finish()` takes it.
+  inner: Option<W>,
+  state: Box<Push<Decoder<'a>, BUF_LEN>>,
+}
+
+impl<W: Write> DecoderWriter<'static, W> {
+  /// Creates a writer that decodes standard base64.
+  pub fn new(inner: W) -> Self {
+    Self::with_engine(inner, &Engine::STANDARD)
+  }
+}
+
+impl<'a, W: Write> DecoderWriter<'a, W> {
+  /// Creates a writer that decodes with the given engine's rules.
+  pub fn with_engine(inner: W, engine: &'a Engine) -> Self {
+    Self::with_decoder(inner, Decoder::with_engine(engine))
+  }
+
+  /// Creates a writer that feeds its input through `decoder`, such as one
+  /// with a [limit][Decoder::limit()].
+  pub fn with_decoder(inner: W, decoder: Decoder<'a>) -> Self {
+    Self {
+      inner: Some(inner),
+      state: Box::new(Push::new(decoder)),
+    }
+  }
+
+  /// Returns a reference to the inner writer.
+  pub fn get_ref(&self) -> &W {
+    self.inner.as_ref().unwrap()
+  }
+
+  /// Returns a mutable reference to the inner writer.
+  ///
+  /// Writing to it directly will interleave with the decoded data.
+  pub fn get_mut(&mut self) -> &mut W {
+    self.inner.as_mut().unwrap()
+  }
+
+  /// Decodes whatever is left over, writes out all the remaining data, and
+  /// returns the inner writer.
+  pub fn finish(mut self) -> io::Result<W> {
+    // Take the writer first, so `drop()` doesn't finish a second time.
+    let mut inner = self.inner.take().unwrap();
+    self.state.finish()?;
+    self.state.write_to(&mut inner)?;
+    Ok(inner)
+  }
+}
+
+impl<W: Write> Write for DecoderWriter<'_, W> {
+  fn write(&mut self, text: &[u8]) -> io::Result<usize> {
+    if self.state.is_full() {
+      self.state.write_to(self.inner.as_mut().unwrap())?;
+    }
+    Ok(self.state.push(text)?)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    let inner = self.inner.as_mut().unwrap();
+    self.state.write_to(inner)?;
+    inner.flush()
+  }
+}
+
+impl<W: Write> Drop for DecoderWriter<'_, W> {
+  fn drop(&mut self) {
+    if let Some(inner) = &mut self.inner {
+      if !std::thread::panicking() && self.state.finish().is_ok() {
+        let _ = self.state.write_to(inner);
+      }
+    }
+  }
+}
+
+impl<C: Codec, const N: usize> Pull<C, N> {
+  /// Implements [`Read::read()`], reading input from `inner` as needed.
+  fn read_from(
+    &mut self,
+    inner: &mut impl Read,
+    buf: &mut [u8],
+  ) -> io::Result<usize> {
+    while let Some(input) = self.wants_input() {
+      let n = match inner.read(input) {
+        Ok(n) => n,
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+        Err(e) => return Err(e),
+      };
+      self.fill(n)?;
+    }
+    Ok(self.read(buf))
+  }
+}
+
+impl<C: Codec, const N: usize> Push<C, N> {
+  /// Writes out all the unwritten output, keeping track of how far we got if
+  /// that fails partway through.
+  fn write_to(&mut self, w: &mut impl Write) -> io::Result<()> {
+    while !self.unwritten().is_empty() {
+      match w.write(self.unwritten()) {
+        Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+        Ok(n) => self.advance(n),
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+        Err(e) => return Err(e),
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A reader that hands out at most `n` bytes at a time.
+  struct Trickle<'a>(&'a [u8], usize);
+
+  impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      let n = buf.len().min(self.0.len()).min(self.1);
+      buf[..n].copy_from_slice(&self.0[..n]);
+      self.0 = &self.0[n..];
+      Ok(n)
+    }
+  }
+
+  #[test]
+  fn decoder_reader() {
+    let data = (0..20000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engine = Engine::STANDARD.wrap(76);
+    let text = engine.encode(&data);
+    for trickle in [1, 3, 100, BUF_LEN, usize::MAX] {
+      let mut reader =
+        DecoderReader::with_engine(Trickle(text.as_bytes(), trickle), &engine);
+      let mut out = Vec::new();
+      reader.read_to_end(&mut out).unwrap();
+      assert_eq!(out, data, "trickle {trickle}");
+    }
+
+    let mut reader = DecoderReader::new(&b"QUJDQU!D"[..]);
+    let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = err
+      .into_inner()
+      .unwrap()
+      .downcast::<crate::Error>()
+      .unwrap();
+    assert_eq!(
+      *err,
+      crate::Error::InvalidByte {
+        offset: 6,
+        byte: b'!'
+      }
+    );
+
+    let decoder = Decoder::new().limit(2);
+    let mut reader = DecoderReader::with_decoder(&b"QUJD"[..], decoder);
+    assert!(reader.read_to_end(&mut Vec::new()).is_err());
+  }
+
+  /// A writer that takes at most `n` bytes at a time.
+  struct Sip(Vec<u8>, usize);
+
+  impl Write for Sip {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+      let n = data.len().min(self.1);
+      self.0.extend_from_slice(&data[..n]);
+      Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn encoder_writer() {
+    let data = (0..20000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engine = Engine::STANDARD.wrap(76);
+    for sip in [1, 3, 100, BUF_LEN, usize::MAX] {
+      for piece in [1, 2, 57, BUF_LEN, 20000] {
+        let mut writer =
+          EncoderWriter::with_engine(Sip(Vec::new(), sip), &engine);
+        for chunk in data.chunks(piece) {
+          writer.write_all(chunk).unwrap();
+        }
+        let text = writer.finish().unwrap().0;
+        assert_eq!(text, engine.encode(&data).as_bytes(), "{sip}, {piece}");
+      }
+    }
+
+    let mut text = Vec::new();
+    let mut writer = EncoderWriter::new(&mut text);
+    writer.write_all(b"hello").unwrap();
+    drop(writer);
+    assert_eq!(text, b"aGVsbG8=");
+  }
+
+  #[test]
+  fn long_lines() {
+    // Lines at least as long as the buffer, so no whole line ever fits in it.
+    let data = (0..20000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engines = [BUF_LEN, BUF_LEN + 8, 20000].map(|len| {
+      Engine::STANDARD
+        .wrap(len)
+        .line_ending(crate::LineEnding::CrLf)
+    });
+    for engine in &engines {
+      let text = engine.encode(&data);
+      let mut reader =
+        EncoderReader::with_engine(Trickle(&data, usize::MAX), engine);
+      let mut out = String::new();
+      reader.read_to_string(&mut out).unwrap();
+      assert_eq!(out, text, "{}", engine.line_len());
+
+      let mut writer =
+        EncoderWriter::with_engine(Sip(Vec::new(), usize::MAX), engine);
+      writer.write_all(&data).unwrap();
+      let out = writer.finish().unwrap().0;
+      assert_eq!(out, text.as_bytes(), "{}", engine.line_len());
+    }
+  }
+
+  #[test]
+  fn decoder_writer() {
+    let data = (0..20000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engine = Engine::STANDARD.wrap(76);
+    let text = engine.encode(&data);
+    for sip in [1, 3, 100, BUF_LEN, usize::MAX] {
+      for piece in [1, 2, 77, BUF_LEN, 30000] {
+        let mut writer =
+          DecoderWriter::with_engine(Sip(Vec::new(), sip), &engine);
+        for chunk in text.as_bytes().chunks(piece) {
+          writer.write_all(chunk).unwrap();
+        }
+        let out = writer.finish().unwrap().0;
+        assert_eq!(out, data, "{sip}, {piece}");
+      }
+    }
+
+    let mut writer = DecoderWriter::new(Vec::new());
+    let err = writer.write_all(b"QUJDQU!D").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let mut writer = DecoderWriter::new(Vec::new());
+    writer.write_all(b"QUJDR").unwrap();
+    assert!(writer.finish().is_err());
+  }
+
+  #[test]
+  fn encoder_reader() {
+    let data = (0..20000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    for engine in [Engine::STANDARD, Engine::URL_SAFE_NOPAD.wrap(76)] {
+      for trickle in [1, 2, 100, BUF_LEN, usize::MAX] {
+        let mut reader =
+          EncoderReader::with_engine(Trickle(&data, trickle), &engine);
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, engine.encode(&data), "trickle {trickle}");
+      }
+    }
+
+    let mut text = Vec::new();
+    let n = io::copy(&mut EncoderReader::new(&b""[..]), &mut text).unwrap();
+    assert_eq!(n, 0);
+  }
+}
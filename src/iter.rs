@@ -0,0 +1,265 @@
+//! Iterator adaptors over the codecs.
+
+use core::iter::FusedIterator;
+
+use crate::Error;
+
+/// How much input the iterators process at once. A multiple of both three and
+/// four, so that whole chunks never need padding.
+const CHUNK: usize = 384;
+
+/// Encodes `data` as standard, padded base64, one byte at a time.
+///
+/// The encoding is done a chunk at a time into a buffer inside the iterator,
+/// so nothing is allocated.
+///
+/// ```
+/// let text = vb64::encode_iter(b"hello").map(char::from).collect::<String>();
+/// assert_eq!(text, "aGVsbG8=");
+/// ```
+pub fn encode_iter(data: &[u8]) -> EncodeIter<'_> {
+  EncodeIter {
+    data,
+    buf: [0; CHUNK / 3 * 4],
+    pos: 0,
+    len: 0,
+  }
+}
+
+/// The iterator returned by [`encode_iter()`].
+#[derive(Clone)]
+pub struct EncodeIter<'a> {
+  data: &'a [u8],
+  buf: [u8; CHUNK / 3 * 4],
+  pos: usize,
+  len: usize,
+}
+
+impl Iterator for EncodeIter<'_> {
+  type Item = u8;
+
+  fn next(&mut self) -> Option<u8> {
+    if self.pos == self.len {
+      if self.data.is_empty() {
+        return None;
+      }
+
+      let (chunk, rest) = self.data.split_at(self.data.len().min(CHUNK));
+      self.len = crate::encode_to_slice(chunk, &mut self.buf).unwrap();
+      self.pos = 0;
+      self.data = rest;
+    }
+
+    self.pos += 1;
+    Some(self.buf[self.pos - 1])
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = crate::encoded_len(self.data.len(), true)
+      .and_then(|len| len.checked_add(self.len - self.pos));
+    (len.unwrap_or(usize::MAX), len)
+  }
+}
+
+impl ExactSizeIterator for EncodeIter<'_> {}
+impl FusedIterator for EncodeIter<'_> {}
+
+/// Decodes standard base64 that arrives as a sequence of chunks, one byte at
+/// a time.
+///
+/// The chunks are treated as if they had been concatenated, and can be split
+/// anywhere. Nothing is allocated; each chunk is decoded a piece at a time
+/// into a buffer inside the iterator.
+///
+/// Once an error is found, it is yielded, and the iterator stops. Because
+/// chunks are decoded as they come in, an input with several problems may
+/// report a different one than [`crate::decode()`] would.
+///
+/// ```
+/// let chunks = ["aGV", "sbG8", "="];
+/// let data = vb64::decode_iter(chunks).collect::<Result<Vec<u8>, _>>();
+/// assert_eq!(data.unwrap(), b"hello");
+///
+/// let chunks = ["aGV", "s!G8="];
+/// assert!(vb64::decode_iter(chunks).collect::<Result<Vec<u8>, _>>().is_err());
+/// ```
+pub fn decode_iter<I>(chunks: I) -> DecodeIter<I::IntoIter>
+where
+  I: IntoIterator,
+  I::Item: AsRef<[u8]>,
+{
+  DecodeIter {
+    chunks: chunks.into_iter(),
+    chunk: None,
+    chunk_pos: 0,
+    group: [0; 4],
+    group_len: 0,
+    consumed: 0,
+    buf: [0; CHUNK / 4 * 3],
+    pos: 0,
+    len: 0,
+    done: false,
+  }
+}
+
+/// The iterator returned by [`decode_iter()`].
+pub struct DecodeIter<I: Iterator> {
+  chunks: I,
+  chunk: Option<I::Item>,
+  chunk_pos: usize,
+
+  /// The last few characters we've seen, which we hold on to until we know
+  /// whether they're the end of the input, where padding is allowed.
+  group: [u8; 4],
+  group_len: usize,
+  /// How many characters we've taken out of `chunks` so far.
+  consumed: usize,
+
+  buf: [u8; CHUNK / 4 * 3],
+  pos: usize,
+  len: usize,
+  done: bool,
+}
+
+impl<I> DecodeIter<I>
+where
+  I: Iterator,
+  I::Item: AsRef<[u8]>,
+{
+  /// Refills `buf` with the next few decoded bytes, if there are any.
+  fn fill(&mut self) -> Result<(), Error> {
+    self.pos = 0;
+    self.len = 0;
+    while self.len == 0 && !self.done {
+      let rest = match &self.chunk {
+        Some(chunk) if chunk.as_ref().len() > self.chunk_pos => {
+          &chunk.as_ref()[self.chunk_pos..]
+        }
+        _ => match self.chunks.next() {
+          Some(chunk) => {
+            self.chunk = Some(chunk);
+            self.chunk_pos = 0;
+            continue;
+          }
+          None => {
+            // That was the last group, so decode it as such.
+            self.done = true;
+            let group = &self.group[..self.group_len];
+            let start = self.consumed - self.group_len;
+            self.len = crate::decode_to_slice(group, &mut self.buf)
+              .map_err(|e| e.shift(start))?;
+            break;
+          }
+        },
+      };
+
+      if self.group_len == 4 {
+        // There's more input, so the group we held back wasn't the last.
+        let start = self.consumed - 4;
+        self.len = decode_body(&self.group, &mut self.buf)
+          .map_err(|e| e.shift(start))?;
+        self.group_len = 0;
+      } else if self.group_len > 0 || rest.len() <= 4 {
+        let n = rest.len().min(4 - self.group_len);
+        self.group[self.group_len..self.group_len + n]
+          .copy_from_slice(&rest[..n]);
+        self.group_len += n;
+        self.chunk_pos += n;
+        self.consumed += n;
+      } else {
+        // Leave at least one character behind, in case it's the end.
+        let n = ((rest.len() - 1) / 4 * 4).min(CHUNK);
+        self.len = decode_body(&rest[..n], &mut self.buf)
+          .map_err(|e| e.shift(self.consumed))?;
+        self.chunk_pos += n;
+        self.consumed += n;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Decodes whole groups that aren't at the end of the input, where padding
+/// isn't allowed.
+fn decode_body(data: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+  if data.contains(&b'=') {
+    return Err(crate::invalid_byte(data, &crate::simd::STANDARD));
+  }
+  crate::decode_to_slice(data, out)
+}
+
+impl<I> Iterator for DecodeIter<I>
+where
+  I: Iterator,
+  I::Item: AsRef<[u8]>,
+{
+  type Item = Result<u8, Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.pos == self.len {
+      if self.done {
+        return None;
+      }
+      if let Err(e) = self.fill() {
+        self.done = true;
+        return Some(Err(e));
+      }
+      if self.len == 0 {
+        return None;
+      }
+    }
+
+    self.pos += 1;
+    Some(Ok(self.buf[self.pos - 1]))
+  }
+}
+
+impl<I> FusedIterator for DecodeIter<I>
+where
+  I: Iterator,
+  I::Item: AsRef<[u8]>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encode() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let iter = encode_iter(&dec);
+      assert_eq!(iter.len(), enc.len(), "case {i}");
+      assert_eq!(iter.collect::<Vec<_>>(), enc, "case {i}");
+    }
+
+    let data = vec![0xfb; 1000];
+    let mut iter = encode_iter(&data);
+    iter.nth(600);
+    assert_eq!(iter.len(), crate::encode(&data).len() - 601);
+  }
+
+  #[test]
+  fn decode() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      for size in [1, 3, 4, 5, 500, usize::MAX] {
+        let chunks = enc.chunks(size.min(enc.len()).max(1));
+        let got = decode_iter(chunks).collect::<Result<Vec<_>, _>>();
+        assert_eq!(got.unwrap(), dec, "case {i} size {size}");
+      }
+    }
+
+    assert_eq!(decode_iter([""; 0]).count(), 0);
+    assert_eq!(decode_iter(["", "", ""]).count(), 0);
+
+    for data in ["QUJD!UJD", "QUJDQ", "QQ==QUJD", "QUJDQ===", "Q=", "QU!D"] {
+      let want = crate::decode(data).unwrap_err();
+      for size in [1, 2, 3, 4, 8] {
+        let mut iter = decode_iter(data.as_bytes().chunks(size));
+        let got = iter.find_map(Result::err).unwrap();
+        assert_eq!(got.to_string(), want.to_string(), "{data} size {size}");
+        assert!(iter.next().is_none());
+      }
+    }
+  }
+}
@@ -21,6 +21,25 @@
 //!
 //! Also this crate uses `std::simd` so it requires nightly.
 //!
+//! # `no_std`
+//!
+//! Turning off the default `std` feature makes this crate `no_std`. The
+//! `alloc` feature brings back everything that returns a `Vec` or `String`;
+//! without it, what's left are the functions that write into a slice, such as
+//! [`decode_to_slice()`], [`encode_to_slice()`], and
+//! [`Decoder::update_to_slice()`].
+//!
+//! # Panics
+//!
+//! Malformed input never causes a panic: decoders report input of any shape
+//! as an [`Error`]. The only panics are for output that can't be allocated, in
+//! the functions that have no way to return an error, such as [`encode()`] or
+//! [`decode_until_invalid()`]; each one says so under `# Panics`, and most have
+//! a `try_` version that returns [`Error::CapacityOverflow`] instead.
+//!
+//! The decoders are checked for panics with proptest, and the decoding
+//! entry points with [kani](https://github.com/model-checking/kani).
+//!
 //! # Constant time?? 👀
 //!
 //! For decoding valid base64 (and for encoding any message), the
@@ -33,289 +52,1920 @@
 // The comedy of using base64 to encode an image of benchmark results from my
 // base64 library is not lost on me.
 #![doc = concat!("[graph-png]: data:image/png;base64,", include_str!("../images/graph.png.base64"))]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![feature(portable_simd)]
+#![feature(adt_const_params)]
+#![cfg_attr(feature = "alloc", feature(allocator_api))]
+#![allow(incomplete_features)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-use std::simd::LaneCount;
-use std::simd::Simd;
-use std::simd::SupportedLaneCount;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::alloc::Allocator;
+use core::array;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::simd::LaneCount;
+use core::simd::Mask;
+use core::simd::Simd;
+use core::simd::SupportedLaneCount;
 
 #[macro_use]
 mod util;
+#[cfg(any(feature = "std", feature = "embedded-io"))]
+mod adapter;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "alloc")]
+pub mod armor;
+#[cfg(feature = "alloc")]
+pub mod ascii85;
+#[cfg(feature = "alloc")]
+pub mod base32;
+#[cfg(feature = "alloc")]
+pub mod base58;
+#[cfg(feature = "alloc")]
+pub mod base91;
+#[cfg(feature = "alloc")]
+mod batch;
+#[cfg(feature = "bumpalo")]
+mod bump;
+#[cfg(feature = "bytes")]
+mod bytes;
+#[cfg(feature = "stream")]
+mod bytes_stream;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "alloc")]
+mod checked;
+#[cfg(feature = "base64")]
+pub mod compat;
+mod consts;
+#[cfg(feature = "alloc")]
+mod data_uri;
+mod display;
+#[cfg(feature = "embedded-io")]
+pub mod embedded;
+#[cfg(feature = "alloc")]
+pub mod emoji;
+mod engine;
+#[cfg(feature = "alloc")]
+mod ext;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "futures-io")]
+pub mod futures;
+#[cfg(feature = "alloc")]
+pub mod hex;
+#[cfg(any(feature = "arrayvec", feature = "heapless", feature = "smallvec"))]
+mod inline;
+#[cfg(feature = "std")]
+mod io;
+mod iter;
+#[cfg(feature = "alloc")]
+mod mime;
+#[cfg(feature = "alloc")]
+pub mod multibase;
+#[cfg(feature = "alloc")]
+pub mod pem;
+#[cfg(feature = "alloc")]
+pub mod percent;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "alloc")]
+pub mod qp;
+mod scan;
+#[cfg(feature = "alloc")]
+mod selftest;
+#[cfg(feature = "serde")]
+pub mod serde;
+// Most of the kernels are only used by the other encodings, which need `alloc`.
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
 mod simd;
+mod stream;
+mod translate;
+#[cfg(feature = "alloc")]
+mod utf16;
+#[cfg(feature = "alloc")]
+pub mod uu;
+#[cfg(feature = "std")]
+mod vectored;
+mod verify;
+#[cfg(feature = "alloc")]
+pub mod vlq;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+#[cfg(feature = "alloc")]
+mod wrapper;
+
+#[cfg(feature = "alloc")]
+pub use batch::decode_many;
+#[cfg(feature = "alloc")]
+pub use batch::encode_many;
+#[cfg(feature = "alloc")]
+pub use batch::DecodedBatch;
+#[cfg(feature = "alloc")]
+pub use batch::EncodedBatch;
+#[cfg(feature = "bumpalo")]
+pub use bump::decode_in_bump;
+#[cfg(feature = "bumpalo")]
+pub use bump::encode_in_bump;
+#[cfg(feature = "bytes")]
+pub use bytes::decode_bytes;
+#[cfg(feature = "bytes")]
+pub use bytes::decode_to_bytes;
+#[cfg(feature = "bytes")]
+pub use bytes::encode_bytes;
+#[cfg(feature = "bytes")]
+pub use bytes::encode_to_bytes;
+#[cfg(feature = "stream")]
+pub use bytes_stream::DecodeStream;
+#[cfg(feature = "stream")]
+pub use bytes_stream::EncodeStream;
+#[cfg(feature = "alloc")]
+pub use checked::Base64Str;
+#[cfg(feature = "alloc")]
+pub use checked::Base64String;
+pub use consts::const_decode;
+pub use consts::const_decoded_len;
+pub use consts::const_encode;
+#[cfg(feature = "alloc")]
+pub use data_uri::decode_data_uri;
+#[cfg(feature = "alloc")]
+pub use data_uri::encode_data_uri;
+pub use display::Base64Display;
+pub use display::FmtEncoder;
+pub use engine::Alphabet;
+pub use engine::Config;
+pub use engine::ConstEngine;
+pub use engine::Engine;
+pub use engine::LineEnding;
+pub use engine::Padding;
+#[cfg(feature = "alloc")]
+pub use ext::FromBase64;
+#[cfg(feature = "alloc")]
+pub use ext::ToBase64;
+#[cfg(feature = "std")]
+pub use file::decode_file;
+#[cfg(feature = "memmap2")]
+pub use file::decode_file_mmap;
+#[cfg(feature = "std")]
+pub use file::encode_file;
+#[cfg(feature = "memmap2")]
+pub use file::encode_file_mmap;
+#[cfg(any(
+  feature = "arrayvec",
+  feature = "heapless",
+  feature = "smallvec"
+))]
+pub use inline::*;
+#[cfg(feature = "std")]
+pub use io::DecoderReader;
+#[cfg(feature = "std")]
+pub use io::DecoderWriter;
+#[cfg(feature = "std")]
+pub use io::EncoderReader;
+#[cfg(feature = "std")]
+pub use io::EncoderWriter;
+pub use iter::decode_iter;
+pub use iter::encode_iter;
+pub use iter::DecodeIter;
+pub use iter::EncodeIter;
+#[cfg(feature = "alloc")]
+pub use mime::decode_mime;
+#[cfg(feature = "alloc")]
+pub use mime::decode_mime_to;
+#[cfg(feature = "alloc")]
+pub use mime::encode_mime;
+#[cfg(feature = "alloc")]
+pub use mime::encode_mime_to;
+pub use scan::find_base64;
+pub use scan::FindBase64;
+#[cfg(feature = "alloc")]
+pub use selftest::self_test;
+#[cfg(feature = "alloc")]
+pub use selftest::SelfTestError;
+pub use stream::Decoder;
+pub use stream::Encoder;
+#[cfg(feature = "alloc")]
+pub use translate::set_padding;
+pub use translate::translate;
+pub use translate::Translation;
+#[cfg(feature = "alloc")]
+pub use utf16::decode_utf16;
+#[cfg(feature = "alloc")]
+pub use utf16::encode_utf16;
+#[cfg(feature = "std")]
+pub use vectored::decode_vectored;
+#[cfg(feature = "std")]
+pub use vectored::encode_vectored;
+pub use verify::verify;
+#[cfg(feature = "alloc")]
+pub use wrapper::Base64;
 
 /// The error returned by all decode functions.
-#[derive(Copy, Clone, Debug)]
-pub struct Error;
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error {
+  /// A byte that isn't part of the encoding's alphabet.
+  InvalidByte {
+    /// The offset of the byte in the input.
+    offset: usize,
+    /// The byte itself.
+    byte: u8,
+  },
+  /// The input is a length that no valid encoding could be.
+  InvalidLength,
+  /// Padding is missing, misplaced, or the wrong length.
+  InvalidPadding,
+  /// The last character has bits set that don't make it into the output, so
+  /// the input isn't the canonical encoding of anything.
+  InvalidTrailingBits,
+  /// A checksum in the input doesn't match the data.
+  InvalidChecksum,
+  /// The input isn't structured correctly, such as a PEM block that's missing
+  /// its `END` line.
+  InvalidFormat,
+  /// The decoded data would be longer than the caller allowed.
+  LimitExceeded,
+  /// The output buffer couldn't grow to fit the decoded data.
+  CapacityOverflow,
+  /// The caller's output buffer is too short to hold the result.
+  BufferTooSmall,
+}
+
+impl Error {
+  /// Returns the offset of the first invalid byte in the input, if that's what
+  /// caused this error.
+  ///
+  /// Decoders only go looking for the offending byte once they've failed, so
+  /// tracking it doesn't slow down decoding valid input.
+  pub fn offset(&self) -> Option<usize> {
+    match *self {
+      Self::InvalidByte { offset, .. } => Some(offset),
+      _ => None,
+    }
+  }
+
+  /// Returns this error with its offset moved by `by`, for when the input we
+  /// decoded was a suffix of what the caller gave us.
+  pub(crate) fn shift(self, by: usize) -> Self {
+    match self {
+      Self::InvalidByte { offset, byte } => Self::InvalidByte {
+        offset: offset + by,
+        byte,
+      },
+      e => e,
+    }
+  }
+
+  /// Returns this error without its offset, for when the input we decoded was
+  /// pieced together from the caller's and offsets into it mean nothing to
+  /// them.
+  #[cfg(feature = "alloc")]
+  pub(crate) fn unlocated(self) -> Self {
+    match self {
+      Self::InvalidByte { .. } => Self::InvalidFormat,
+      e => e,
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::InvalidByte { offset, byte } => {
+        write!(f, "invalid byte {byte:#04x} at offset {offset}")
+      }
+      Self::InvalidLength => f.write_str("invalid input length"),
+      Self::InvalidPadding => f.write_str("invalid padding"),
+      Self::InvalidTrailingBits => f.write_str("non-zero trailing bits"),
+      Self::InvalidChecksum => f.write_str("checksum mismatch"),
+      Self::InvalidFormat => f.write_str("malformed input"),
+      Self::LimitExceeded => f.write_str("decoded data exceeds limit"),
+      Self::CapacityOverflow => {
+        f.write_str("decoded data too large to allocate")
+      }
+      Self::BufferTooSmall => f.write_str("output buffer too small"),
+    }
+  }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+  fn from(e: Error) -> Self {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+  }
+}
 
 /// Decodes some base64 `data` to a fresh vector.
-pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+#[cfg(feature = "alloc")]
+pub fn decode(data: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+  let data = data.as_ref();
   let mut out = Vec::new();
   decode_to(data, &mut out)?;
   Ok(out)
 }
 
+/// Decodes some base64 text to a fresh vector.
+///
+/// This is the same as [`decode()`], which also accepts strings, but with a
+/// concrete signature, for passing to things like [`Iterator::map()`].
+///
+/// ```
+/// let decoded = ["aGk=", "eW8="].map(vb64::decode_str);
+/// assert_eq!(decoded.map(Result::unwrap), [b"hi", b"yo"]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_str(data: &str) -> Result<Vec<u8>, Error> {
+  decode(data)
+}
+
 /// Encodes arbitrary data as base64.
-pub fn encode(data: &[u8]) -> String {
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory; [`try_encode()`] reports
+/// that as an error instead.
+#[cfg(feature = "alloc")]
+pub fn encode(data: impl AsRef<[u8]>) -> String {
+  try_encode(data).expect("base64 output too large")
+}
+
+/// Encodes arbitrary data as base64, returning [`Error::CapacityOverflow`]
+/// rather than panicking if the encoded data wouldn't fit in memory.
+///
+/// ```
+/// assert_eq!(vb64::try_encode(b"hello").unwrap(), "aGVsbG8=");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn try_encode(data: impl AsRef<[u8]>) -> Result<String, Error> {
+  let data = data.as_ref();
   let mut out = Vec::new();
-  encode_to(data, &mut out);
-  unsafe { String::from_utf8_unchecked(out) }
+  try_encode_to(data, &mut out)?;
+  Ok(unsafe { String::from_utf8_unchecked(out) })
 }
 
 /// Decodes some base64 data as base64 and appends it to `out`.
-pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
-  if cfg!(target_feature = "avx2") {
-    decode_tunable::<32>(data, out)
-  } else {
-    decode_tunable::<16>(data, out)
-  }
+///
+/// Returns how many bytes were appended. If decoding fails, the contents of
+/// `out` are left as they were, although its capacity may have grown. This
+/// goes for all of the `decode_*_to()` functions.
+#[cfg(feature = "alloc")]
+pub fn decode_to(
+  data: impl AsRef<[u8]>,
+  out: &mut Vec<u8>,
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  decode_with(data, out, &simd::STANDARD, true)
 }
 
-/// Encodes arbitrary data as base64 and appends it to `out`.
-pub fn encode_to(data: &[u8], out: &mut Vec<u8>) {
-  encode_tunable::<16>(data, out)
+/// Decodes some base64 `data` to a boxed slice with no spare capacity.
+///
+/// [`decode()`] leaves some room at the end of its vector for the decoder's
+/// vector stores; this doesn't, which adds up when storing lots of values.
+#[cfg(feature = "alloc")]
+pub fn decode_boxed(data: impl AsRef<[u8]>) -> Result<Box<[u8]>, Error> {
+  let data = data.as_ref();
+  boxed(decoded_len(data), |out| decode_to_uninit(data, out))
 }
 
-fn decode_tunable<const N: usize>(
-  data: &[u8],
-  out: &mut Vec<u8>,
-) -> Result<(), Error>
-where
-  LaneCount<N>: SupportedLaneCount,
-{
-  assert!(N % 4 == 0);
-
-  let data = match data {
-    [p @ .., b'=', b'='] | [p @ .., b'='] | p => p,
-  };
+/// Encodes arbitrary data as base64 to a boxed string with no spare capacity.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory; [`try_encode_boxed()`]
+/// reports that as an error instead.
+#[cfg(feature = "alloc")]
+pub fn encode_boxed(data: impl AsRef<[u8]>) -> Box<str> {
+  try_encode_boxed(data).expect("base64 output too large")
+}
 
-  if data.is_empty() {
-    return Ok(());
-  }
+/// Like [`encode_boxed()`], but returns [`Error::CapacityOverflow`] if the
+/// encoded data wouldn't fit in memory.
+#[cfg(feature = "alloc")]
+pub fn try_encode_boxed(data: impl AsRef<[u8]>) -> Result<Box<str>, Error> {
+  let data = data.as_ref();
+  let len = encoded_len(data.len(), true).ok_or(Error::CapacityOverflow)?;
+  let out = boxed(len, |out| encode_to_uninit(data, out))?;
+  // SAFETY: Encoders only produce ASCII.
+  Ok(unsafe { alloc::str::from_boxed_utf8_unchecked(out) })
+}
 
-  // NOTE: Always a full N bytes of slop so we can do full SIMD stores.
-  out.reserve(decoded_len(data.len()) + N);
-  let mut raw_out = out.as_mut_ptr_range().end;
+/// Decodes some base64 `data` to a fresh vector allocated in `alloc`.
+///
+/// ```
+/// #![cfg_attr(feature = "alloc", feature(allocator_api))]
+/// use std::alloc::System;
+///
+/// let data = vb64::decode_in(b"aGVsbG8=", System).unwrap();
+/// assert_eq!(data, b"hello");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_in<A: Allocator>(
+  data: impl AsRef<[u8]>,
+  alloc: A,
+) -> Result<Vec<u8, A>, Error> {
+  let data = data.as_ref();
+  let mut out = Vec::new_in(alloc);
+  append_with(&mut out, max_decoded_len(data.len()), |out| {
+    decode_to_uninit(data, out)
+  })?;
+  Ok(out)
+}
 
-  let mut chunks = data.chunks_exact(N);
-  let mut failed = false;
-  for chunk in &mut chunks {
-    let (decoded, ok) = simd::decode(Simd::from_slice(chunk));
-    failed |= !ok;
+/// Encodes arbitrary data as base64 to a fresh vector allocated in `alloc`.
+///
+/// `String` can't use other allocators yet, so this returns the encoded text
+/// as bytes, all of which are ASCII.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory; [`try_encode_in()`]
+/// reports that as an error instead.
+#[cfg(feature = "alloc")]
+pub fn encode_in<A: Allocator>(data: impl AsRef<[u8]>, alloc: A) -> Vec<u8, A> {
+  try_encode_in(data, alloc).expect("base64 output too large")
+}
 
-    unsafe {
-      raw_out.cast::<Simd<u8, N>>().write_unaligned(decoded);
-      raw_out = raw_out.add(decoded_len(N));
-    }
-  }
+/// Like [`encode_in()`], but returns [`Error::CapacityOverflow`] if the
+/// encoded data wouldn't fit in memory.
+#[cfg(feature = "alloc")]
+pub fn try_encode_in<A: Allocator>(
+  data: impl AsRef<[u8]>,
+  alloc: A,
+) -> Result<Vec<u8, A>, Error> {
+  let data = data.as_ref();
+  let len = encoded_len(data.len(), true).ok_or(Error::CapacityOverflow)?;
+  let mut out = Vec::new_in(alloc);
+  append_with(&mut out, len, |out| encode_to_uninit(data, out))?;
+  Ok(out)
+}
 
-  let rest = chunks.remainder();
-  if !rest.is_empty() {
-    let (decoded, ok) =
-      simd::decode(unsafe { read_slice_padded::<N, b'A'>(rest) });
-    failed |= !ok;
+/// Runs `write` on a fresh buffer of `len` bytes, and returns what it wrote.
+#[cfg(feature = "alloc")]
+pub(crate) fn boxed(
+  len: usize,
+  write: impl FnOnce(&mut [MaybeUninit<u8>]) -> Result<usize, Error>,
+) -> Result<Box<[u8]>, Error> {
+  let mut out = Vec::new();
+  out
+    .try_reserve_exact(len)
+    .map_err(|_| Error::CapacityOverflow)?;
+  let written = write(&mut out.spare_capacity_mut()[..len])?;
+  unsafe { out.set_len(written) };
+  Ok(out.into_boxed_slice())
+}
 
-    unsafe {
-      raw_out.cast::<Simd<u8, N>>().write_unaligned(decoded);
-      raw_out = raw_out.add(decoded_len(rest.len()));
-    }
+/// Decodes some base64 `data` into an array, which it must exactly fill.
+///
+/// This is handy for keys, hashes, and nonces, whose size is known up front,
+/// and never allocates. If `data` decodes to any other number of bytes, this
+/// returns [`Error::InvalidLength`].
+///
+/// ```
+/// let nonce = vb64::decode_exact::<4>("3q2+7w==").unwrap();
+/// assert_eq!(nonce, [0xde, 0xad, 0xbe, 0xef]);
+/// assert!(vb64::decode_exact::<5>("3q2+7w==").is_err());
+/// ```
+pub fn decode_exact<const N: usize>(
+  data: impl AsRef<[u8]>,
+) -> Result<[u8; N], Error> {
+  let data = unpad(data.as_ref(), true)?;
+  if max_decoded_len(data.len()) != N {
+    return Err(Error::InvalidLength);
   }
 
-  if failed {
-    return Err(Error);
-  }
+  let mut out = [0; N];
+  decode_uninit(data, as_uninit(&mut out), &simd::STANDARD)?;
+  Ok(out)
+}
 
-  unsafe {
-    let new_len = raw_out.offset_from(out.as_ptr());
-    out.set_len(new_len as usize);
-  }
+/// Decodes some base64 data into `out`, returning how many bytes were written.
+///
+/// This follows the same rules as [`decode()`], but never allocates. If `out`
+/// is too short for the decoded data, this returns [`Error::BufferTooSmall`]
+/// before decoding anything. Bytes of `out` past the decoded data are never
+/// touched, but if decoding fails, the rest of its contents are unspecified.
+///
+/// ```
+/// let mut buf = [0; 8];
+/// let len = vb64::decode_to_slice(b"aGVsbG8=", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"hello");
+///
+/// let err = vb64::decode_to_slice(b"aGVsbG8=", &mut buf[..4]).unwrap_err();
+/// assert!(matches!(err, vb64::Error::BufferTooSmall));
+/// ```
+pub fn decode_to_slice(
+  data: impl AsRef<[u8]>,
+  out: &mut [u8],
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  decode_to_uninit(data, as_uninit(out))
+}
 
-  Ok(())
+/// Decodes some base64 data into possibly-uninitialized memory, returning how
+/// many bytes were written.
+///
+/// This is [`decode_to_slice()`] for buffers that haven't been initialized
+/// yet, such as a `Vec`'s spare capacity, which saves zeroing them first. On
+/// success, the first that-many bytes of `out` are initialized.
+///
+/// ```
+/// let mut buf = Vec::with_capacity(64);
+/// let len = vb64::decode_to_uninit(b"aGVsbG8=", buf.spare_capacity_mut())
+///   .unwrap();
+/// unsafe { buf.set_len(len) };
+/// assert_eq!(buf, b"hello");
+/// ```
+pub fn decode_to_uninit(
+  data: impl AsRef<[u8]>,
+  out: &mut [MaybeUninit<u8>],
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  let data = unpad(data, true)?;
+  let out = prefix(out, max_decoded_len(data.len()));
+  decode_uninit(data, out, &simd::STANDARD)
 }
 
-fn encode_tunable<const N: usize>(data: &[u8], out: &mut Vec<u8>)
-where
-  LaneCount<N>: SupportedLaneCount,
-{
-  assert!(N % 4 == 0);
-  let n3q = N / 4 * 3;
+/// Decodes the base64 in `buf` in place, returning the decoded prefix of it.
+///
+/// Decoded data is always shorter than its encoding, so this overwrites `buf`
+/// from the front as it goes. This follows the same rules as [`decode()`];
+/// whatever is left in `buf` after the returned prefix, or all of it if
+/// decoding fails, is unspecified.
+///
+/// ```
+/// let mut buf = *b"aGVsbG8=";
+/// assert_eq!(vb64::decode_in_place(&mut buf).unwrap(), b"hello");
+/// ```
+pub fn decode_in_place(buf: &mut [u8]) -> Result<&mut [u8], Error> {
+  // Big enough to amortize the copy, and a multiple of four, so that only the
+  // last chunk can end in a partial group.
+  const CHUNK: usize = 512;
 
-  if data.is_empty() {
-    return;
-  }
+  let len = unpad(buf, true)?.len();
+  let (mut read, mut written) = (0, 0);
+  while read < len {
+    // The chunk gets copied out first, since we overwrite it as we decode.
+    // Everything up to the end of it is fair game for the output, which gives
+    // the kernel room to do full-vector stores.
+    let n = CHUNK.min(len - read);
+    let mut chunk = [0; CHUNK];
+    chunk[..n].copy_from_slice(&buf[read..read + n]);
 
-  // NOTE: Always a full N bytes of slop so we can do full SIMD stores.
-  out.reserve(encoded_len(data.len()) + N);
-  let mut raw_out = out.as_mut_ptr_range().end;
+    let out = as_uninit(&mut buf[written..read + n]);
+    written += decode_uninit(&chunk[..n], out, &simd::STANDARD)
+      .map_err(|e| e.shift(read))?;
+    read += n;
+  }
 
-  // Can't use `[u8]::chunks` here, because we want 32-byte windows so we can
-  // do full 32-byte loads, but we want them to overlap by 8 bytes; we also
-  // want eight bytes of slop on the last chunk.
-  //
-  // There are two cases: either data.len() % 24 >= 8, or not; in the former
-  // case, we can load every full chunk with a full load, but in the latter we
-  // need an extra case to load less than 24 bytes.
-  //
-  // There is also a third, extra case where data.len() < 32, in which case
-  // we need to not do pointer arithmetic below.
-  let mut start = data.as_ptr();
-  let end = unsafe {
-    if data.len() % n3q >= (N - n3q) {
-      start.add(data.len() - data.len() % n3q)
-    } else if data.len() < N {
-      start
-    } else {
-      start.add(data.len() - data.len() % n3q - n3q)
-    }
-  };
+  Ok(&mut buf[..written])
+}
 
-  while start != end {
-    let chunk = unsafe { std::slice::from_raw_parts(start, N) };
-    let encoded = simd::encode(Simd::from_slice(chunk));
+/// Encodes arbitrary data as base64 into `out`, returning how many bytes were
+/// written.
+///
+/// If `out` is too short for the encoded data, padding included, this returns
+/// [`Error::BufferTooSmall`] without writing anything. Bytes of `out` past the
+/// encoded data are never touched.
+///
+/// ```
+/// let mut buf = [0; 16];
+/// let len = vb64::encode_to_slice(b"hello", &mut buf).unwrap();
+/// assert_eq!(&buf[..len], b"aGVsbG8=");
+/// assert!(vb64::encode_to_slice(b"hello", &mut buf[..7]).is_err());
+/// ```
+pub fn encode_to_slice(
+  data: impl AsRef<[u8]>,
+  out: &mut [u8],
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  encode_to_uninit(data, as_uninit(out))
+}
 
-    unsafe {
-      start = start.add(n3q);
+/// Encodes arbitrary data as base64 into possibly-uninitialized memory,
+/// returning how many bytes were written.
+///
+/// This is [`encode_to_slice()`] for buffers that haven't been initialized
+/// yet. On success, the first that-many bytes of `out` are initialized.
+pub fn encode_to_uninit(
+  data: impl AsRef<[u8]>,
+  out: &mut [MaybeUninit<u8>],
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  encode_slice_with(data, out, &simd::STANDARD, true)
+}
 
-      raw_out.cast::<Simd<u8, N>>().write_unaligned(encoded);
-      raw_out = raw_out.add(N);
-    }
-  }
+/// Encodes the first `data_len` bytes of `buf` as base64 in place, returning
+/// the encoded prefix of it.
+///
+/// The encoding is longer than the data, so `buf` needs room for all of it,
+/// padding included; otherwise, this returns [`Error::BufferTooSmall`] and
+/// leaves `buf` alone. The data is encoded back to front, so that no byte is
+/// overwritten before it's been read.
+///
+/// ```
+/// let mut buf = [0; 8];
+/// buf[..5].copy_from_slice(b"hello");
+/// assert_eq!(vb64::encode_in_place(&mut buf, 5).unwrap(), b"aGVsbG8=");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `data_len` is greater than `buf.len()`.
+pub fn encode_in_place(
+  buf: &mut [u8],
+  data_len: usize,
+) -> Result<&mut [u8], Error> {
+  // A multiple of three, so that only the last chunk can end in a partial
+  // group.
+  const CHUNK: usize = 384;
 
-  let end = data.as_ptr_range().end;
-  while start < end {
-    let chunk = unsafe {
-      let rest = end.offset_from(start) as usize;
-      std::slice::from_raw_parts(start, rest.min(n3q))
-    };
-    let encoded = simd::encode(unsafe { read_slice_padded::<N, 0>(chunk) });
+  assert!(data_len <= buf.len(), "data_len out of bounds");
+  let len = match encoded_len(data_len, true) {
+    Some(len) if len <= buf.len() => len,
+    _ => return Err(Error::BufferTooSmall),
+  };
 
-    unsafe {
-      start = start.add(chunk.len());
+  for start in (0..data_len).step_by(CHUNK).rev() {
+    let n = CHUNK.min(data_len - start);
+    let mut chunk = [0; CHUNK];
+    chunk[..n].copy_from_slice(&buf[start..start + n]);
 
-      raw_out.cast::<Simd<u8, N>>().write_unaligned(encoded);
-      raw_out = raw_out.add(encoded_len(chunk.len()));
-    }
+    // Each chunk's output starts at or after the chunk itself, and ends right
+    // where the next chunk's output, which is already there, begins.
+    let last = start + n == data_len;
+    let out_start = start / 3 * 4;
+    let out_len = encoded_len(n, last).unwrap();
+    let out = as_uninit(&mut buf[out_start..out_start + out_len]);
+    encode_slice_with(&chunk[..n], out, &simd::STANDARD, last)?;
   }
 
-  unsafe {
-    let new_len = raw_out.offset_from(out.as_ptr());
-    out.set_len(new_len as usize);
-  }
+  Ok(&mut buf[..len])
+}
 
-  match out.len() % 4 {
-    2 => out.extend_from_slice(b"=="),
-    3 => out.extend_from_slice(b"="),
-    _ => {}
-  }
+/// Encodes arbitrary data as base64 and appends it to `out`, returning how
+/// many bytes were appended.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory, which can only happen on
+/// 32-bit targets; [`try_encode_to()`] reports that as an error instead. The
+/// same goes for all of the other `encode_*()` functions that allocate.
+#[cfg(feature = "alloc")]
+pub fn encode_to(data: impl AsRef<[u8]>, out: &mut Vec<u8>) -> usize {
+  try_encode_to(data, out).expect("base64 output too large")
 }
 
-fn decoded_len(input: usize) -> usize {
-  let mod4 = input % 4;
-  input / 4 * 3 + (mod4 - mod4 / 2)
+/// Like [`encode_to()`], but returns [`Error::CapacityOverflow`] if the
+/// encoded data wouldn't fit in memory. In that case, `out` is left as it was.
+///
+/// ```
+/// let mut out = b"data: ".to_vec();
+/// assert_eq!(vb64::try_encode_to(b"hi", &mut out), Ok(4));
+/// assert_eq!(out, b"data: aGk=");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn try_encode_to(
+  data: impl AsRef<[u8]>,
+  out: &mut Vec<u8>,
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  encode_with(data, out, &simd::STANDARD, true)
 }
 
-fn encoded_len(input: usize) -> usize {
-  let mod3 = input % 3;
-  input / 3 * 4 + (mod3 + (mod3 + 1) / 2)
+/// Encodes arbitrary data as base64 and appends it to `out`.
+///
+/// Like [`encode_to()`], this reuses `out`'s capacity, which makes it the
+/// cheaper option when building up a larger document.
+///
+/// ```
+/// let mut json = String::from("{\"data\":\"");
+/// vb64::encode_to_string(b"hello", &mut json);
+/// json.push_str("\"}");
+/// assert_eq!(json, r#"{"data":"aGVsbG8="}"#);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+#[cfg(feature = "alloc")]
+pub fn encode_to_string(data: impl AsRef<[u8]>, out: &mut String) -> usize {
+  let data = data.as_ref();
+  // SAFETY: We only ever append ASCII.
+  encode_to(data, unsafe { out.as_mut_vec() })
 }
 
-/// Gathers elements, in order, from `slice`, replacing them with `Z`
-/// if `slice` is too short.
+/// Encodes arbitrary data as base64 and writes it to `out`, returning how many
+/// bytes were written.
 ///
-/// This is approximately 2-3x faster than `Simd::gather_or` on AVX2.
+/// The text goes through a buffer on the stack, about a kilobyte at a time, so
+/// nothing is allocated, however large `data` is.
 ///
-/// # Safety
+/// ```
+/// let mut out = Vec::new();
+/// assert_eq!(vb64::encode_to_writer(b"hello", &mut out).unwrap(), 8);
+/// assert_eq!(out, b"aGVsbG8=");
+/// ```
+#[cfg(feature = "std")]
+pub fn encode_to_writer(
+  data: impl AsRef<[u8]>,
+  out: &mut impl std::io::Write,
+) -> std::io::Result<usize> {
+  Engine::STANDARD.encode_to_writer(data, out)
+}
+
+/// Decodes some unpadded base64 `data` to a fresh vector.
 ///
-/// `slice.len()` must be within `1..N`.
-#[inline(always)]
-unsafe fn read_slice_padded<const N: usize, const Z: u8>(
-  slice: &[u8],
-) -> Simd<u8, N>
-where
-  LaneCount<N>: SupportedLaneCount,
-{
-  let mut buf = [Z; N];
+/// Unlike [`decode()`], any `=` padding is rejected.
+#[cfg(feature = "alloc")]
+pub fn decode_nopad(data: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  decode_nopad_to(data, &mut out)?;
+  Ok(out)
+}
 
-  // Load a bunch of big 16-byte chunks. This should select "load vector"
-  // instructions.
-  let ascii_ptr = buf.as_mut_ptr();
-  let mut write_at = ascii_ptr;
-  if slice.len() >= 16 {
-    for i in 0..slice.len() / 16 {
-      unsafe {
-        let word = slice.as_ptr().cast::<u128>().add(i).read_unaligned();
-        write_at.cast::<u128>().write_unaligned(word);
-        write_at = write_at.add(16);
-      }
-    }
-  }
+/// Encodes arbitrary data as base64, without `=` padding.
+#[cfg(feature = "alloc")]
+pub fn encode_nopad(data: impl AsRef<[u8]>) -> String {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  encode_nopad_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
 
-  // This block loads the "remainder of the remainder" by essentially doing
-  // something like a binary search on rest.len() % 16.
-  //
-  // Each of the "load" functions below loads a very small variable-length
-  // buffer into a scalar without branching on the length, which means that
-  // the total number of branches necessary is logarithmic, rather than
-  // linear, in rest.len() % 16, and the number of loads is ~constant.
-  //
-  // For the >= 8/4 cases, we perform two 8/4-byte loads respectively that cover
-  // the low and high 8/4 bytes of the buffer, and then we shift the high chunk
-  // so it overlaps the low one and or them together.
-  //
-  // For the 1..=3 case, we perform three byte loads and carefully align them;
-  // see the case below for how this is done.
-  //
-  // I don't know precisely who this trick is due to, but I learned it while
-  // contributing to Swisstable, since Abseil uses a version of it in its
-  // low-level string hash implementation.
-  unsafe {
-    let ptr = slice.as_ptr().offset(write_at.offset_from(ascii_ptr));
-    let len = slice.len() % 16;
-    if len >= 8 {
-      let lo = ptr.cast::<u64>().read_unaligned() as u128;
-      let hi = ptr.add(len - 8).cast::<u64>().read_unaligned() as u128;
-      let data = lo | (hi << ((len - 8) * 8));
+/// Decodes some unpadded base64 data and appends it to `out`.
+#[cfg(feature = "alloc")]
+pub fn decode_nopad_to(
+  data: impl AsRef<[u8]>,
+  out: &mut Vec<u8>,
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  decode_with(data, out, &simd::STANDARD, false)
+}
 
-      let z = u128::from_ne_bytes([Z; 16]) << (len * 8);
-      write_at.cast::<u128>().write_unaligned(data | z);
-    } else if len >= 4 {
-      let lo = ptr.cast::<u32>().read_unaligned() as u64;
-      let hi = ptr.add(len - 4).cast::<u32>().read_unaligned() as u64;
-      let data = lo | (hi << ((len - 4) * 8));
+/// Encodes arbitrary data as base64, without `=` padding, and appends it to
+/// `out`.
+#[cfg(feature = "alloc")]
+pub fn encode_nopad_to(data: impl AsRef<[u8]>, out: &mut Vec<u8>) -> usize {
+  let data = data.as_ref();
+  encode_with(data, out, &simd::STANDARD, false)
+    .expect("base64 output too large")
+}
 
-      let z = u64::from_ne_bytes([Z; 8]) << (len * 8);
-      write_at.cast::<u64>().write_unaligned(data | z);
-    } else if len >= 1 {
-      // For len       1       2       3     ...
-      // ... this is  ptr[0]  ptr[0]  ptr[0]
-      let lo = ptr.read() as u32;
-      // ... this is  ptr[0]  ptr[1]  ptr[1]
-      let mid = ptr.add(len / 2).read() as u32;
-      // ... this is  ptr[0]  ptr[1]  ptr[2]
-      let hi = ptr.add(len - 1).read() as u32;
+/// Decodes some URL-safe base64 `data` to a fresh vector.
+///
+/// This uses the alphabet from [RFC 4648 §5], which replaces `+` and `/` with
+/// `-` and `_`.
+///
+/// [RFC 4648 §5]: https://datatracker.ietf.org/doc/html/rfc4648#section-5
+#[cfg(feature = "alloc")]
+pub fn decode_url(data: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  decode_url_to(data, &mut out)?;
+  Ok(out)
+}
 
-      let data = lo | (mid << ((len / 2) * 8)) | hi << ((len - 1) * 8);
+/// Encodes arbitrary data as URL-safe base64.
+#[cfg(feature = "alloc")]
+pub fn encode_url(data: impl AsRef<[u8]>) -> String {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  encode_url_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
 
-      let z = u32::from_ne_bytes([Z; 4]) << (len * 8);
-      write_at.cast::<u32>().write_unaligned(data | z);
-    }
-  }
+/// Decodes some URL-safe base64 data and appends it to `out`.
+#[cfg(feature = "alloc")]
+pub fn decode_url_to(
+  data: impl AsRef<[u8]>,
+  out: &mut Vec<u8>,
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  decode_with(data, out, &simd::URL_SAFE, true)
+}
 
-  buf.into()
+/// Encodes arbitrary data as URL-safe base64 and appends it to `out`.
+#[cfg(feature = "alloc")]
+pub fn encode_url_to(data: impl AsRef<[u8]>, out: &mut Vec<u8>) -> usize {
+  let data = data.as_ref();
+  encode_with(data, out, &simd::URL_SAFE, true)
+    .expect("base64 output too large")
 }
 
-#[cfg(test)]
-mod tests {
-  fn random_tests() -> Vec<(usize, &'static [u8], Vec<u8>)> {
-    use base64::prelude::*;
-    include_bytes!("test_vectors.txt")
-      .split(|&b| b == b'\n')
-      .enumerate()
-      .map(|(i, b64)| (i, b64, BASE64_STANDARD.decode(b64).unwrap()))
-      .collect()
-  }
+/// Decodes some unpadded URL-safe base64 `data` to a fresh vector.
+///
+/// This is the encoding used by JWTs and friends. Unlike [`decode_url()`], any
+/// `=` padding is rejected.
+#[cfg(feature = "alloc")]
+pub fn decode_url_nopad(data: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  decode_url_nopad_to(data, &mut out)?;
+  Ok(out)
+}
 
-  fn all_ones_tests() -> Vec<(usize, Vec<u8>, Vec<u8>)> {
-    use base64::prelude::*;
+/// Encodes arbitrary data as URL-safe base64, without `=` padding.
+#[cfg(feature = "alloc")]
+pub fn encode_url_nopad(data: impl AsRef<[u8]>) -> String {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  encode_url_nopad_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some unpadded URL-safe base64 data and appends it to `out`.
+#[cfg(feature = "alloc")]
+pub fn decode_url_nopad_to(
+  data: impl AsRef<[u8]>,
+  out: &mut Vec<u8>,
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  decode_with(data, out, &simd::URL_SAFE, false)
+}
+
+/// Encodes arbitrary data as URL-safe base64, without `=` padding, and appends
+/// it to `out`.
+#[cfg(feature = "alloc")]
+pub fn encode_url_nopad_to(data: impl AsRef<[u8]>, out: &mut Vec<u8>) -> usize {
+  let data = data.as_ref();
+  encode_with(data, out, &simd::URL_SAFE, false)
+    .expect("base64 output too large")
+}
+
+/// Decodes some base64 `data` to a fresh vector, accepting both the standard
+/// and URL-safe alphabets.
+///
+/// The two alphabets may be mixed freely within the same input; this is
+/// useful for inputs from the web, where either one may show up.
+#[cfg(feature = "alloc")]
+pub fn decode_mixed(data: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  decode_mixed_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Decodes some base64 data in either the standard or URL-safe alphabet, and
+/// appends it to `out`.
+#[cfg(feature = "alloc")]
+pub fn decode_mixed_to(
+  data: impl AsRef<[u8]>,
+  out: &mut Vec<u8>,
+) -> Result<usize, Error> {
+  let data = data.as_ref();
+  decode_with(data, out, &simd::MIXED, true)
+}
+
+/// Decodes some base64 `data` to a fresh vector, failing if the result would
+/// be longer than `max_len` bytes.
+///
+/// The length is checked before anything is allocated or decoded, which makes
+/// this suitable for untrusted input.
+///
+/// ```
+/// assert_eq!(vb64::decode_with_limit(b"aGVsbG8=", 5).unwrap(), b"hello");
+/// assert!(vb64::decode_with_limit(b"aGVsbG8=", 4).is_err());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_with_limit(
+  data: impl AsRef<[u8]>,
+  max_len: usize,
+) -> Result<Vec<u8>, Error> {
+  let data = data.as_ref();
+  if decoded_len(data) > max_len {
+    return Err(Error::LimitExceeded);
+  }
+  decode(data)
+}
+
+/// Returns whether `data` is valid base64, that is, whether [`decode()`] would
+/// succeed on it.
+///
+/// This only runs the validation half of the decoder, so it's faster than
+/// decoding and throwing the result away.
+///
+/// ```
+/// assert!(vb64::is_base64(b"SGVsbG8="));
+/// assert!(!vb64::is_base64(b"SGVsbG8-"));
+/// ```
+pub fn is_base64(data: impl AsRef<[u8]>) -> bool {
+  let data = data.as_ref();
+  validate_with(data, &simd::STANDARD, true)
+}
+
+/// Returns whether `data` is valid URL-safe base64, that is, whether
+/// [`decode_url()`] would succeed on it.
+pub fn is_base64_url(data: impl AsRef<[u8]>) -> bool {
+  let data = data.as_ref();
+  validate_with(data, &simd::URL_SAFE, true)
+}
+
+/// Returns the offset of the first byte of `data` that isn't standard base64,
+/// or `None` if there isn't one.
+///
+/// Up to two `=` at the very end are allowed, as padding. Unlike
+/// [`is_base64()`], this doesn't care about the length of `data`.
+///
+/// ```
+/// assert_eq!(vb64::find_invalid(b"SGVs bG8="), Some(4));
+/// assert_eq!(vb64::find_invalid(b"SGVsbG8="), None);
+/// ```
+pub fn find_invalid(data: impl AsRef<[u8]>) -> Option<usize> {
+  let data = data.as_ref();
+  find_invalid_with(strip_padding(data), &simd::STANDARD)
+}
+
+/// Returns the offset of the first byte of `data` that isn't URL-safe base64,
+/// or `None` if there isn't one.
+///
+/// Up to two `=` at the very end are allowed, as padding.
+pub fn find_invalid_url(data: impl AsRef<[u8]>) -> Option<usize> {
+  let data = data.as_ref();
+  find_invalid_with(strip_padding(data), &simd::URL_SAFE)
+}
+
+/// Decodes the longest prefix of `data` that is valid base64, returning the
+/// decoded bytes along with how many bytes of `data` were consumed.
+///
+/// This is useful for parsers, where base64 is followed by other text. The
+/// prefix stops before the first byte that isn't base64, and includes any
+/// padding that properly ends it.
+///
+/// ```
+/// let (data, len) = vb64::decode_until_invalid(b"aGk=; path=/");
+/// assert_eq!(data, b"hi");
+/// assert_eq!(len, 4);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the decoded data wouldn't fit in memory.
+#[cfg(feature = "alloc")]
+pub fn decode_until_invalid(data: impl AsRef<[u8]>) -> (Vec<u8>, usize) {
+  let data = data.as_ref();
+  let mut out = Vec::new();
+  let len = decode_until_invalid_to(data, &mut out);
+  (out, len)
+}
+
+/// Decodes the longest prefix of `data` that is valid base64 and appends it to
+/// `out`, returning how many bytes of `data` were consumed.
+///
+/// # Panics
+///
+/// Panics if the decoded data wouldn't fit in memory. The prefix is always
+/// valid, so that's the only thing that can go wrong.
+#[cfg(feature = "alloc")]
+pub fn decode_until_invalid_to(
+  data: impl AsRef<[u8]>,
+  out: &mut Vec<u8>,
+) -> usize {
+  let data = data.as_ref();
+  let end = find_invalid_with(data, &simd::STANDARD).unwrap_or(data.len());
+
+  // A lone character at the end can't be decoded, so we leave it for the
+  // caller.
+  let body = &data[..end - (end % 4 == 1) as usize];
+  let pads = match (body.len() == end, body.len() % 4, &data[end..]) {
+    (true, 2, [b'=', b'=', ..]) => 2,
+    (true, 3, [b'=', ..]) => 1,
+    _ => 0,
+  };
+
+  decode_any(body, out, &simd::STANDARD).expect("base64 output too large");
+  body.len() + pads
+}
+
+/// If `pad` is false, `=` is treated like any other invalid character.
+#[inline(always)]
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_with(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+  pad: bool,
+) -> Result<usize, Error> {
+  decode_any(unpad(data, pad)?, out, luts)
+}
+
+/// Checks whether [`decode_with()`] would succeed, without decoding anything.
+#[inline(always)]
+pub(crate) fn validate_with(data: &[u8], luts: &simd::Luts, pad: bool) -> bool {
+  let Ok(data) = unpad(data, pad) else {
+    return false;
+  };
+
+  if cfg!(target_feature = "avx2") {
+    validate_tunable::<32>(data, luts)
+  } else {
+    validate_tunable::<16>(data, luts)
+  }
+}
+
+/// Checks that `data` is valid standard base64, like [`validate_with()`], but
+/// says what's wrong with it if it isn't.
+#[cfg(feature = "alloc")]
+pub(crate) fn check(data: &[u8]) -> Result<(), Error> {
+  let body = unpad(data, true)?;
+  if !validate_with(body, &simd::STANDARD, false) {
+    return Err(invalid_byte(body, &simd::STANDARD));
+  }
+  Ok(())
+}
+
+/// Decodes `data`, which [`check()`] has already accepted, and appends it to
+/// `out`.
+///
+/// # Panics
+///
+/// Panics if the decoded data wouldn't fit in memory.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_valid(data: &[u8], out: &mut Vec<u8>) -> usize {
+  let data = strip_padding(data);
+  append_with(out, max_decoded_len(data.len()), |out| {
+    // No need to validate anything a second time.
+    if cfg!(target_feature = "avx2") {
+      decode_tunable::<32, false>(data, out, &simd::STANDARD)
+    } else {
+      decode_tunable::<16, false>(data, out, &simd::STANDARD)
+    }
+  })
+  .expect("base64 output too large")
+}
+
+/// Strips padding from `data`, if `pad` is set, and checks that what's left is
+/// a length that an encoder could have produced.
+#[inline(always)]
+pub(crate) const fn unpad(data: &[u8], pad: bool) -> Result<&[u8], Error> {
+  let padded_len = data.len();
+  let data = if pad { strip_padding(data) } else { data };
+
+  // A lone character at the end can't hold a whole byte, and padding only
+  // ever rounds up to a multiple of four.
+  if data.len() % 4 == 1 {
+    return Err(Error::InvalidLength);
+  }
+  if data.len() != padded_len && padded_len % 4 != 0 {
+    return Err(Error::InvalidPadding);
+  }
+  Ok(data)
+}
+
+/// Decodes `data`, which must not contain any padding, without checking its
+/// length.
+#[inline(always)]
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_any(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+) -> Result<usize, Error> {
+  append_with(out, max_decoded_len(data.len()), |out| {
+    decode_uninit(data, out, luts)
+  })
+}
+
+/// Like [`decode_any()`], but writes to the front of `out` and returns how
+/// many bytes it wrote.
+#[inline(always)]
+fn decode_uninit(
+  data: &[u8],
+  out: &mut [MaybeUninit<u8>],
+  luts: &simd::Luts,
+) -> Result<usize, Error> {
+  if cfg!(target_feature = "avx2") {
+    decode_tunable::<32, true>(data, out, luts)
+  } else {
+    decode_tunable::<16, true>(data, out, luts)
+  }
+}
+
+/// Runs `write` on the spare capacity of `out`, which gets room for `len`
+/// bytes, and then appends however many bytes it says it wrote, returning
+/// that.
+///
+/// If `write` fails, `out` is left as it was. The extra vector's worth of room
+/// means that the kernels never need to bounce their last store.
+#[inline(always)]
+#[cfg(feature = "alloc")]
+pub(crate) fn append_with<A: Allocator>(
+  out: &mut Vec<u8, A>,
+  len: usize,
+  write: impl FnOnce(&mut [MaybeUninit<u8>]) -> Result<usize, Error>,
+) -> Result<usize, Error> {
+  if len > 0 {
+    reserve(out, len.checked_add(32))?;
+  }
+
+  let written = write(out.spare_capacity_mut())?;
+  unsafe { out.set_len(out.len() + written) };
+  Ok(written)
+}
+
+/// Views `out` as a buffer that the kernels can write into.
+pub(crate) fn as_uninit(out: &mut [u8]) -> &mut [MaybeUninit<u8>] {
+  // SAFETY: The kernels only ever write initialized bytes.
+  unsafe { &mut *(out as *mut [u8] as *mut [MaybeUninit<u8>]) }
+}
+
+/// Returns up to the first `len` bytes of `out`, so that the kernels'
+/// full-vector stores don't clobber anything after that.
+pub(crate) fn prefix(
+  out: &mut [MaybeUninit<u8>],
+  len: usize,
+) -> &mut [MaybeUninit<u8>] {
+  let len = len.min(out.len());
+  &mut out[..len]
+}
+
+/// Like [`decode_with()`], but with a configurable padding policy.
+///
+/// If `check_length`, this rejects lengths that no encoder would produce, like
+/// [`decode_with()`] does. If `strict`, this only accepts the canonical
+/// encoding: on top of the length checks, any bits of the last character that
+/// don't make it into the output must be zero.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_checked(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+  padding: Padding,
+  check_length: bool,
+  strict: bool,
+) -> Result<usize, Error> {
+  append_with(out, max_decoded_len(data.len()), |out| {
+    decode_checked_uninit(data, out, luts, padding, check_length, strict)
+  })
+}
+
+/// Like [`decode_checked()`], but writes to the front of `out` and returns how
+/// many bytes it wrote.
+pub(crate) fn decode_checked_uninit(
+  data: &[u8],
+  out: &mut [MaybeUninit<u8>],
+  luts: &simd::Luts,
+  padding: Padding,
+  check_length: bool,
+  strict: bool,
+) -> Result<usize, Error> {
+  let pads = data.iter().rev().take_while(|&&c| c == b'=').count();
+  let (data, padded_len) = (&data[..data.len() - pads], data.len());
+
+  // Once padding starts, nothing but more padding may follow it. This gets
+  // caught below anyway, but possibly as some other error.
+  if strict && data.contains(&b'=') {
+    return Err(Error::InvalidPadding);
+  }
+
+  let check_length = check_length || strict;
+  if check_length && data.len() % 4 == 1 {
+    return Err(Error::InvalidLength);
+  }
+
+  let bad_padding = match padding {
+    Padding::Required => padded_len % 4 != 0,
+    Padding::Optional => false,
+    Padding::Forbidden => pads != 0,
+  };
+  let bad_count = check_length && pads != 0 && padded_len % 4 != 0;
+  if bad_padding || bad_count || pads > 2 {
+    return Err(Error::InvalidPadding);
+  }
+
+  let written = decode_uninit(data, out, luts).map_err(|e| match e {
+    Error::InvalidByte { byte: b'=', .. } => Error::InvalidPadding,
+    e => e,
+  })?;
+
+  // Encoders always leave these bits zero.
+  let unused = data.len() * 6 % 8;
+  if let (true, Some(&last)) = (strict, data.last()) {
+    let (sextet, _) = simd::to_sextets(Simd::<u8, 16>::splat(last), luts);
+    if sextet[0] & ((1 << unused) - 1) != 0 {
+      return Err(Error::InvalidTrailingBits);
+    }
+  }
+
+  Ok(written)
+}
+
+/// Decodes `data` that is wrapped at a fixed column, such as the body of a PEM
+/// or MIME message, by decoding each line where it sits.
+///
+/// This only handles lines of the same length, a multiple of four, ending in
+/// either `\n` or `\r\n`; the last line may be shorter, and its line ending is
+/// optional. Returns `false` without touching `out` if `data` isn't shaped like
+/// that or doesn't decode, in which case the caller should fall back to a
+/// general whitespace-skipping decoder, which will also find any errors.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_wrapped(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+  padding: Padding,
+  strict: bool,
+) -> bool {
+  let Some(nl) = data.iter().position(|&b| b == b'\n') else {
+    return false;
+  };
+  let eol: &[u8] = match data[..nl] {
+    [.., b'\r'] => b"\r\n",
+    _ => b"\n",
+  };
+  let width = nl + 1 - eol.len();
+  if width == 0 || width % 4 != 0 {
+    return false;
+  }
+
+  let start = out.len();
+  let mut lines = data.chunks(width + eol.len()).peekable();
+  while let Some(line) = lines.next() {
+    let is_last = lines.peek().is_none();
+    let ok = match line.split_at_checked(width) {
+      Some((line, end)) if end == eol && !is_last => {
+        decode_any(line, out, luts).is_ok()
+      }
+      _ if is_last => {
+        let line = line.strip_suffix(eol).unwrap_or(line);
+        decode_checked(line, out, luts, padding, true, strict).is_ok()
+      }
+      _ => false,
+    };
+
+    if !ok {
+      out.truncate(start);
+      return false;
+    }
+  }
+  true
+}
+
+#[inline(always)]
+#[cfg(feature = "alloc")]
+pub(crate) fn encode_with(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+  pad: bool,
+) -> Result<usize, Error> {
+  let len = encoded_len(data.len(), pad).ok_or(Error::CapacityOverflow)?;
+  append_with(out, len, |out| encode_uninit(data, out, luts, pad))
+}
+
+/// Like [`encode_with()`], but writes into `out`, never touching anything past
+/// the encoded data.
+#[inline(always)]
+pub(crate) fn encode_slice_with(
+  data: &[u8],
+  out: &mut [MaybeUninit<u8>],
+  luts: &simd::Luts,
+  pad: bool,
+) -> Result<usize, Error> {
+  let len = encoded_len(data.len(), pad).unwrap_or(usize::MAX);
+  encode_uninit(data, prefix(out, len), luts, pad)
+}
+
+/// Like [`encode_with()`], but writes to the front of `out` and returns how
+/// many bytes it wrote.
+#[inline(always)]
+fn encode_uninit(
+  data: &[u8],
+  out: &mut [MaybeUninit<u8>],
+  luts: &simd::Luts,
+  pad: bool,
+) -> Result<usize, Error> {
+  // Check up front, so that we don't write anything if the padding won't fit.
+  match encoded_len(data.len(), pad) {
+    Some(len) if len <= out.len() => {}
+    _ => return Err(Error::BufferTooSmall),
+  }
+
+  let written = encode_tunable::<16>(data, out, luts, usize::MAX, &[])?;
+  let pads = if pad { (4 - written % 4) % 4 } else { 0 };
+  for out in &mut out[written..written + pads] {
+    out.write(b'=');
+  }
+  Ok(written + pads)
+}
+
+/// Like [`encode_uninit()`], but ends every `wrap` characters, and the last
+/// line, with `eol`.
+///
+/// The caller must make sure that `out` is long enough for all of it.
+#[inline(always)]
+pub(crate) fn encode_wrapped_uninit(
+  data: &[u8],
+  out: &mut [MaybeUninit<u8>],
+  luts: &simd::Luts,
+  pad: bool,
+  wrap: usize,
+  eol: &[u8],
+) -> Result<usize, Error> {
+  let mut written = encode_tunable::<16>(data, out, luts, wrap, eol)?;
+
+  // The kernel breaks every full line, so we only need to finish off a
+  // partial one, which is also the only place padding can go.
+  let chars = encoded_len_unchecked(data.len());
+  let pads = if pad { (4 - chars % 4) % 4 } else { 0 };
+  if chars % wrap != 0 {
+    for b in core::iter::repeat_n(&b'=', pads).chain(eol) {
+      out[written].write(*b);
+      written += 1;
+    }
+  }
+  Ok(written)
+}
+
+/// Decodes `data`, which must not contain any padding, into the front of
+/// `out`, returning how many bytes it wrote.
+///
+/// Without `CHECK`, `data` had better be valid already: bytes outside the
+/// alphabet decode to garbage instead of an error.
+fn decode_tunable<const N: usize, const CHECK: bool>(
+  data: &[u8],
+  out: &mut [MaybeUninit<u8>],
+  luts: &simd::Luts,
+) -> Result<usize, Error>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  const { assert!(N % 4 == 0) };
+
+  if out.len() < max_decoded_len(data.len()) {
+    return Err(Error::BufferTooSmall);
+  }
+
+  let mut chunks = data.chunks_exact(N);
+  let mut failed = false;
+  let mut written = 0;
+  for chunk in &mut chunks {
+    let (decoded, ok) = simd::decode(Simd::from_slice(chunk), luts);
+    failed |= !ok;
+
+    store::<N>(&mut out[written..], decoded, max_decoded_len(N));
+    written += max_decoded_len(N);
+  }
+
+  let rest = chunks.remainder();
+  if !rest.is_empty() {
+    let (decoded, ok) =
+      simd::decode(unsafe { read_slice_padded::<N>(rest, luts.zero) }, luts);
+    failed |= !ok;
+
+    store(&mut out[written..], decoded, max_decoded_len(rest.len()));
+    written += max_decoded_len(rest.len());
+  }
+
+  if CHECK && failed {
+    return Err(invalid_byte(data, luts));
+  }
+  Ok(written)
+}
+
+/// Writes the first `len` lanes of `v` to the front of `out`, which must have
+/// room for at least that many.
+///
+/// If there's room for all of `v`, this is a single store, and whatever lands
+/// past `len` gets overwritten by the next one or ignored. Otherwise, we're at
+/// the end of a tightly-sized buffer, and copy out of a bounce buffer instead.
+#[inline(always)]
+fn store<const N: usize>(
+  out: &mut [MaybeUninit<u8>],
+  v: Simd<u8, N>,
+  len: usize,
+) where
+  LaneCount<N>: SupportedLaneCount,
+{
+  if out.len() >= N {
+    unsafe { out.as_mut_ptr().cast::<Simd<u8, N>>().write_unaligned(v) }
+  } else {
+    let v = v.to_array();
+    for (out, &b) in out[..len].iter_mut().zip(&v) {
+      out.write(b);
+    }
+  }
+}
+
+/// Strips up to two `=` from the end of `data`.
+pub(crate) const fn strip_padding(data: &[u8]) -> &[u8] {
+  match data {
+    [p @ .., b'=', b'='] | [p @ .., b'='] => p,
+    p => p,
+  }
+}
+
+/// Checks that `data`, which must not contain any padding, is entirely made up
+/// of characters in the alphabet described by `luts`.
+fn validate_tunable<const N: usize>(data: &[u8], luts: &simd::Luts) -> bool
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // Like the decoder, don't bail early, so that the loop stays branchless.
+  let mut chunks = data.chunks_exact(N);
+  let mut valid = Mask::splat(true);
+  for chunk in &mut chunks {
+    valid &= simd::valid_lanes(Simd::from_slice(chunk), luts);
+  }
+
+  let rest = chunks.remainder();
+  if !rest.is_empty() {
+    let rest = unsafe { read_slice_padded::<N>(rest, luts.zero) };
+    valid &= simd::valid_lanes(rest, luts);
+  }
+
+  valid.all()
+}
+
+/// Finds the first byte of `data` that isn't in the alphabet described by
+/// `luts`, and returns an error pointing at it.
+#[cold]
+pub(crate) fn invalid_byte(data: &[u8], luts: &simd::Luts) -> Error {
+  match find_invalid_with(data, luts) {
+    Some(offset) => Error::InvalidByte {
+      offset,
+      byte: data[offset],
+    },
+    None => Error::InvalidFormat,
+  }
+}
+
+/// Finds the first byte of `data` that isn't in the alphabet described by
+/// `luts`.
+pub(crate) fn find_invalid_with(
+  data: &[u8],
+  luts: &simd::Luts,
+) -> Option<usize> {
+  const N: usize = 16;
+  for (i, chunk) in data.chunks(N).enumerate() {
+    let chunk = unsafe { read_slice_padded::<N>(chunk, luts.zero) };
+    let invalid = !simd::valid_lanes(chunk, luts);
+    if invalid.any() {
+      return Some(i * N + invalid.to_bitmask().trailing_zeros() as usize);
+    }
+  }
+
+  None
+}
+
+/// Encodes `data`, without padding, into the front of `out`, returning how
+/// many bytes it wrote.
+///
+/// Every `wrap` characters, which must be a multiple of four, the line is
+/// ended with `eol`; pass `usize::MAX` to never end it. The breaks are put in
+/// as each vector is stored, rather than in a second pass.
+fn encode_tunable<const N: usize>(
+  data: &[u8],
+  out: &mut [MaybeUninit<u8>],
+  luts: &simd::Luts,
+  wrap: usize,
+  eol: &[u8],
+) -> Result<usize, Error>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  const { assert!(N % 4 == 0) };
+  let n3q = N / 4 * 3;
+
+  let chars = encoded_len_unchecked(data.len());
+  match (chars / wrap).checked_mul(eol.len()) {
+    Some(eols) if chars + eols <= out.len() => {}
+    _ => return Err(Error::BufferTooSmall),
+  }
+
+  // Can't use `[u8]::chunks` here, because we want 32-byte windows so we can
+  // do full 32-byte loads, but we want them to overlap by 8 bytes; we also
+  // want eight bytes of slop on the last chunk.
+  //
+  // There are two cases: either data.len() % 24 >= 8, or not; in the former
+  // case, we can load every full chunk with a full load, but in the latter we
+  // need an extra case to load less than 24 bytes.
+  //
+  // There is also a third, extra case where data.len() < 32, in which case
+  // we need to not do pointer arithmetic below.
+  let mut start = data.as_ptr();
+  let end = unsafe {
+    if data.len() % n3q >= (N - n3q) {
+      start.add(data.len() - data.len() % n3q)
+    } else if data.len() < N {
+      start
+    } else {
+      start.add(data.len() - data.len() % n3q - n3q)
+    }
+  };
+
+  let mut written = 0;
+  let mut left = wrap;
+  while start != end {
+    let chunk = unsafe { core::slice::from_raw_parts(start, N) };
+    let encoded = simd::encode(Simd::from_slice(chunk), luts);
+    start = unsafe { start.add(n3q) };
+
+    if N < left {
+      store::<N>(&mut out[written..], encoded, N);
+      written += N;
+      left -= N;
+    } else {
+      store_lines(out, &mut written, &mut left, encoded, N, wrap, eol);
+    }
+  }
+
+  let end = data.as_ptr_range().end;
+  while start < end {
+    let chunk = unsafe {
+      let rest = end.offset_from(start) as usize;
+      core::slice::from_raw_parts(start, rest.min(n3q))
+    };
+    let encoded =
+      simd::encode(unsafe { read_slice_padded::<N>(chunk, 0) }, luts);
+    start = unsafe { start.add(chunk.len()) };
+
+    let len = encoded_len_unchecked(chunk.len());
+    store_lines(out, &mut written, &mut left, encoded, len, wrap, eol);
+  }
+
+  Ok(written)
+}
+
+/// Stores the first `len` bytes of `v` at `written`, like [`store()`], ending
+/// the line with `eol` whenever that uses up the `left` characters it has room
+/// for.
+///
+/// Whatever comes after a break is shifted down to the front of the vector and
+/// stored again just past the `eol`, so every piece is still a single store.
+#[inline(always)]
+fn store_lines<const N: usize>(
+  out: &mut [MaybeUninit<u8>],
+  written: &mut usize,
+  left: &mut usize,
+  mut v: Simd<u8, N>,
+  mut len: usize,
+  wrap: usize,
+  eol: &[u8],
+) where
+  LaneCount<N>: SupportedLaneCount,
+{
+  while len >= *left {
+    let head = *left;
+    store::<N>(&mut out[*written..], v, head);
+    *written += head;
+    for &b in eol {
+      out[*written].write(b);
+      *written += 1;
+    }
+
+    let lanes = Simd::from_array(array::from_fn(|i| i as u8));
+    v = v.swizzle_dyn(lanes + Simd::splat(head as u8));
+    len -= head;
+    *left = wrap;
+  }
+
+  if len > 0 {
+    store::<N>(&mut out[*written..], v, len);
+    *written += len;
+    *left -= len;
+  }
+}
+
+/// Returns how many bytes `data` decodes to, if it's valid base64.
+///
+/// This looks at the padding, if there is any, so it's exact. It doesn't check
+/// whether `data` is actually valid, though.
+///
+/// ```
+/// assert_eq!(vb64::decoded_len(b"aGVsbG8="), 5);
+/// assert_eq!(vb64::decoded_len(b"aGVsbG8"), 5);
+/// ```
+pub fn decoded_len(data: impl AsRef<[u8]>) -> usize {
+  max_decoded_len(strip_padding(data.as_ref()).len())
+}
+
+/// Returns the most bytes that any `encoded_len` bytes of base64 can decode
+/// to, which is how much room to leave for decoding without looking at the
+/// data.
+///
+/// This is exact for unpadded input.
+pub const fn max_decoded_len(encoded_len: usize) -> usize {
+  let mod4 = encoded_len % 4;
+  encoded_len / 4 * 3 + (mod4 - mod4 / 2)
+}
+
+/// Returns how long the encoding of `data_len` bytes is, with or without
+/// padding, or `None` if that would overflow a `usize`.
+///
+/// ```
+/// assert_eq!(vb64::encoded_len(5, true), Some(8));
+/// assert_eq!(vb64::encoded_len(5, false), Some(7));
+/// assert_eq!(vb64::encoded_len(usize::MAX, true), None);
+/// ```
+pub const fn encoded_len(data_len: usize, pad: bool) -> Option<usize> {
+  let mod3 = data_len % 3;
+  let Some(len) = (data_len / 3).checked_mul(4) else {
+    return None;
+  };
+  let Some(len) = len.checked_add(mod3 + (mod3 + 1) / 2) else {
+    return None;
+  };
+  if pad {
+    len.checked_next_multiple_of(4)
+  } else {
+    Some(len)
+  }
+}
+
+/// Like [`encoded_len()`], but for output that's wrapped every `line_len`
+/// characters, with a line ending of `eol_len` bytes after every line,
+/// including the last one.
+///
+/// ```
+/// // A PEM body, without the header and footer.
+/// assert_eq!(vb64::encoded_len_wrapped(48, true, 64, 1), Some(65));
+/// assert_eq!(vb64::encoded_len_wrapped(49, true, 64, 1), Some(65 + 5));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `line_len` is zero.
+pub const fn encoded_len_wrapped(
+  data_len: usize,
+  pad: bool,
+  line_len: usize,
+  eol_len: usize,
+) -> Option<usize> {
+  assert!(line_len > 0, "line_len must be nonzero");
+  let Some(len) = encoded_len(data_len, pad) else {
+    return None;
+  };
+  let Some(eols) = len.div_ceil(line_len).checked_mul(eol_len) else {
+    return None;
+  };
+  len.checked_add(eols)
+}
+
+/// Like [`encoded_len()`] without padding, but doesn't check for overflow, for
+/// lengths that are known to be small.
+fn encoded_len_unchecked(input: usize) -> usize {
+  let mod3 = input % 3;
+  input / 3 * 4 + (mod3 + (mod3 + 1) / 2)
+}
+
+/// Reserves room for `additional` more bytes in `out`, returning an error
+/// rather than panicking or aborting if that's more than we can allocate.
+///
+/// `None` means the caller's length computation overflowed.
+#[cfg(feature = "alloc")]
+pub(crate) fn reserve<A: Allocator>(
+  out: &mut Vec<u8, A>,
+  additional: Option<usize>,
+) -> Result<(), Error> {
+  additional
+    .and_then(|n| out.try_reserve(n).ok())
+    .ok_or(Error::CapacityOverflow)
+}
+
+/// Gathers elements, in order, from `slice`, replacing them with `z`
+/// if `slice` is too short.
+///
+/// This is approximately 2-3x faster than `Simd::gather_or` on AVX2.
+///
+/// # Safety
+///
+/// `slice.len()` must be at most `N`, and `N` must be a multiple of 16, so that
+/// the 16-byte stores below stay inside the buffer.
+#[inline(always)]
+pub(crate) unsafe fn read_slice_padded<const N: usize>(
+  slice: &[u8],
+  z: u8,
+) -> Simd<u8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  debug_assert!(slice.len() <= N && N % 16 == 0);
+  let mut buf = [z; N];
+
+  // Load a bunch of big 16-byte chunks. This should select "load vector"
+  // instructions.
+  let ascii_ptr = buf.as_mut_ptr();
+  let mut write_at = ascii_ptr;
+  if slice.len() >= 16 {
+    for i in 0..slice.len() / 16 {
+      unsafe {
+        let word = slice.as_ptr().cast::<u128>().add(i).read_unaligned();
+        write_at.cast::<u128>().write_unaligned(word);
+        write_at = write_at.add(16);
+      }
+    }
+  }
+
+  // This block loads the "remainder of the remainder" by essentially doing
+  // something like a binary search on rest.len() % 16.
+  //
+  // Each of the "load" functions below loads a very small variable-length
+  // buffer into a scalar without branching on the length, which means that
+  // the total number of branches necessary is logarithmic, rather than
+  // linear, in rest.len() % 16, and the number of loads is ~constant.
+  //
+  // For the >= 8/4 cases, we perform two 8/4-byte loads respectively that cover
+  // the low and high 8/4 bytes of the buffer, and then we shift the high chunk
+  // so it overlaps the low one and or them together.
+  //
+  // For the 1..=3 case, we perform three byte loads and carefully align them;
+  // see the case below for how this is done.
+  //
+  // I don't know precisely who this trick is due to, but I learned it while
+  // contributing to Swisstable, since Abseil uses a version of it in its
+  // low-level string hash implementation.
+  unsafe {
+    let ptr = slice.as_ptr().offset(write_at.offset_from(ascii_ptr));
+    let len = slice.len() % 16;
+    if len >= 8 {
+      let lo = ptr.cast::<u64>().read_unaligned() as u128;
+      let hi = ptr.add(len - 8).cast::<u64>().read_unaligned() as u128;
+      let data = lo | (hi << ((len - 8) * 8));
+
+      let fill = u128::from_ne_bytes([z; 16]) << (len * 8);
+      write_at.cast::<u128>().write_unaligned(data | fill);
+    } else if len >= 4 {
+      let lo = ptr.cast::<u32>().read_unaligned() as u64;
+      let hi = ptr.add(len - 4).cast::<u32>().read_unaligned() as u64;
+      let data = lo | (hi << ((len - 4) * 8));
+
+      let fill = u64::from_ne_bytes([z; 8]) << (len * 8);
+      write_at.cast::<u64>().write_unaligned(data | fill);
+    } else if len >= 1 {
+      // For len       1       2       3     ...
+      // ... this is  ptr[0]  ptr[0]  ptr[0]
+      let lo = ptr.read() as u32;
+      // ... this is  ptr[0]  ptr[1]  ptr[1]
+      let mid = ptr.add(len / 2).read() as u32;
+      // ... this is  ptr[0]  ptr[1]  ptr[2]
+      let hi = ptr.add(len - 1).read() as u32;
+
+      let data = lo | (mid << ((len / 2) * 8)) | hi << ((len - 1) * 8);
+
+      let fill = u32::from_ne_bytes([z; 4]) << (len * 8);
+      write_at.cast::<u32>().write_unaligned(data | fill);
+    }
+  }
+
+  buf.into()
+}
+
+/// Model-checking harnesses for the pointer arithmetic in the tail handling.
+/// Run them with `cargo kani`.
+#[cfg(kani)]
+mod proofs {
+  use super::*;
+
+  /// Every length up to a whole vector, both loading exactly the right bytes
+  /// and never touching memory outside of `slice` or the buffer.
+  fn check_read_slice_padded<const N: usize>()
+  where
+    LaneCount<N>: SupportedLaneCount,
+  {
+    let data: [u8; N] = kani::any();
+    let len: usize = kani::any();
+    kani::assume(len <= N);
+    let z: u8 = kani::any();
+
+    let v = unsafe { read_slice_padded::<N>(&data[..len], z) };
+    for i in 0..N {
+      assert_eq!(v[i], if i < len { data[i] } else { z });
+    }
+  }
+
+  #[kani::proof]
+  #[kani::unwind(17)]
+  fn read_slice_padded_16() {
+    check_read_slice_padded::<16>();
+  }
+
+  #[kani::proof]
+  #[kani::unwind(33)]
+  fn read_slice_padded_32() {
+    check_read_slice_padded::<32>();
+  }
+
+  /// Covers all three cases of the loop bounds in `encode_tunable()`: inputs
+  /// shorter than a vector, and longer ones whose tail is either more or less
+  /// than the overlap between loads.
+  #[kani::proof]
+  #[kani::unwind(9)]
+  fn encode_tunable_in_bounds() {
+    const MAX: usize = 40;
+    let data: [u8; MAX] = kani::any();
+    let len: usize = kani::any();
+    kani::assume(len <= MAX);
+
+    let mut out = [MaybeUninit::uninit(); MAX / 3 * 4 + 4];
+    let out = &mut out[..encoded_len_unchecked(len)];
+    let n =
+      encode_tunable::<16>(&data[..len], out, &simd::STANDARD, usize::MAX, &[]);
+    assert_eq!(n.ok(), Some(encoded_len_unchecked(len)));
+  }
+
+  #[kani::proof]
+  #[kani::unwind(5)]
+  fn decode_tunable_in_bounds() {
+    const MAX: usize = 40;
+    let data: [u8; MAX] = kani::any();
+    let len: usize = kani::any();
+    kani::assume(len <= MAX);
+
+    // Exactly as much room as needed, so the tail goes through `store()`'s
+    // bounce buffer.
+    let mut out = [MaybeUninit::uninit(); MAX];
+    let out = &mut out[..max_decoded_len(len)];
+    if let Ok(n) =
+      decode_tunable::<16, true>(&data[..len], out, &simd::STANDARD)
+    {
+      assert_eq!(n, max_decoded_len(len));
+    }
+  }
+
+  /// Up to three groups of any bytes, which covers every padding and length
+  /// case, and every byte in every position.
+  fn any_text() -> ([u8; 12], usize) {
+    let data: [u8; 12] = kani::any();
+    let len: usize = kani::any();
+    kani::assume(len <= data.len());
+    (data, len)
+  }
+
+  #[kani::proof]
+  #[kani::unwind(13)]
+  fn decode_to_slice_never_panics() {
+    let (data, len) = any_text();
+    let mut out = [0; 9];
+    if let Ok(n) = decode_to_slice(&data[..len], &mut out) {
+      assert!(n <= max_decoded_len(len));
+    }
+    let _ =
+      decode_to_slice(&data[..len], &mut out[..kani::any::<u8>() as usize % 9]);
+    let _ = decode_exact::<6>(&data[..len]);
+  }
+
+  #[kani::proof]
+  #[kani::unwind(13)]
+  fn decode_in_place_never_panics() {
+    let (mut data, len) = any_text();
+    if let Ok(out) = decode_in_place(&mut data[..len]) {
+      assert!(out.len() <= max_decoded_len(len));
+    }
+  }
+
+  #[kani::proof]
+  #[kani::unwind(13)]
+  fn engine_decode_never_panics() {
+    let (data, len) = any_text();
+    let engines = [
+      Engine::URL_SAFE_NOPAD,
+      Engine::STANDARD.strict(),
+      Engine::STANDARD.lenient_length(),
+    ];
+    let mut out = [0; 9];
+    for engine in engines {
+      let _ = engine.decode_to_slice(&data[..len], &mut out);
+    }
+  }
+
+  #[kani::proof]
+  #[kani::unwind(13)]
+  fn decode_until_invalid_never_panics() {
+    let (data, len) = any_text();
+    let mut out = Vec::new();
+    let used = decode_until_invalid_to(&data[..len], &mut out);
+    assert!(used <= len);
+    let _ = find_invalid(&data[..len]);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  pub(crate) fn random_tests() -> Vec<(usize, &'static [u8], Vec<u8>)> {
+    use base64::prelude::*;
+    include_bytes!("test_vectors.txt")
+      .split(|&b| b == b'\n')
+      .enumerate()
+      .map(|(i, b64)| (i, b64, BASE64_STANDARD.decode(b64).unwrap()))
+      .collect()
+  }
+
+  fn all_ones_tests() -> Vec<(usize, Vec<u8>, Vec<u8>)> {
+    use base64::prelude::*;
     (0..500)
       .map(|i| vec![0xff; i])
       .enumerate()
@@ -352,9 +2002,10 @@ mod tests {
   }
 
   #[test]
+  #[allow(clippy::needless_borrows_for_generic_args)]
   fn alphabet() {
     for b in 0..255u8 {
-      let res = crate::decode(&[b, b'=', b'=']);
+      let res = crate::decode(&[b, b, b'=', b'=']);
       if b.is_ascii_alphanumeric() || b == b'+' || b == b'/' {
         assert!(res.is_ok(), "{b:#04x} is valid data");
       } else {
@@ -363,9 +2014,831 @@ mod tests {
     }
   }
 
+  #[test]
+  fn random_url() {
+    use base64::prelude::*;
+    for (i, _, dec) in random_tests() {
+      let enc = BASE64_URL_SAFE.encode(&dec);
+      assert_eq!(crate::encode_url(&dec), enc, "case {i}");
+      assert_eq!(crate::decode_url(enc.as_bytes()).unwrap(), dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn random_url_nopad() {
+    use base64::prelude::*;
+    for (i, _, dec) in random_tests() {
+      let enc = BASE64_URL_SAFE_NO_PAD.encode(&dec);
+      assert_eq!(crate::encode_url_nopad(&dec), enc, "case {i}");
+      assert_eq!(
+        crate::decode_url_nopad(enc.as_bytes()).unwrap(),
+        dec,
+        "case {i}"
+      );
+
+      let padded = BASE64_URL_SAFE.encode(&dec);
+      if padded != enc {
+        assert!(
+          crate::decode_url_nopad(padded.as_bytes()).is_err(),
+          "case {i}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn random_nopad() {
+    use base64::prelude::*;
+    for (i, _, dec) in random_tests() {
+      let enc = BASE64_STANDARD_NO_PAD.encode(&dec);
+      assert_eq!(crate::encode_nopad(&dec), enc, "case {i}");
+      assert_eq!(
+        crate::decode_nopad(enc.as_bytes()).unwrap(),
+        dec,
+        "case {i}"
+      );
+      assert_eq!(
+        crate::Engine::STANDARD_NOPAD
+          .decode(enc.as_bytes())
+          .unwrap(),
+        dec,
+        "case {i}"
+      );
+
+      let padded = BASE64_STANDARD.encode(&dec);
+      if padded != enc {
+        assert!(crate::decode_nopad(padded.as_bytes()).is_err(), "case {i}");
+      }
+    }
+  }
+
+  #[test]
+  #[allow(clippy::needless_borrows_for_generic_args)]
+  fn url_alphabet() {
+    for b in 0..255u8 {
+      let res = crate::decode_url(&[b, b, b'=', b'=']);
+      if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+        assert!(res.is_ok(), "{b:#04x} is valid data");
+      } else {
+        assert!(res.is_err(), "{b:#04x} is not valid data");
+      }
+    }
+  }
+
+  #[test]
+  fn random_mixed() {
+    use base64::prelude::*;
+    for (i, enc, dec) in random_tests() {
+      let url = BASE64_URL_SAFE.encode(&dec);
+      assert_eq!(crate::decode_mixed(enc).unwrap(), dec, "case {i}");
+      assert_eq!(
+        crate::decode_mixed(url.as_bytes()).unwrap(),
+        dec,
+        "case {i}"
+      );
+
+      // Swap every other special character over to the other alphabet.
+      let mixed = enc
+        .iter()
+        .enumerate()
+        .map(|(j, &c)| match c {
+          b'+' if j % 2 == 0 => b'-',
+          b'/' if j % 2 == 0 => b'_',
+          c => c,
+        })
+        .collect::<Vec<_>>();
+      assert_eq!(crate::decode_mixed(&mixed).unwrap(), dec, "case {i}");
+    }
+  }
+
+  #[test]
+  #[allow(clippy::needless_borrows_for_generic_args)]
+  fn mixed_alphabet() {
+    for b in 0..255u8 {
+      let res = crate::decode_mixed(&[b, b, b'=', b'=']);
+      if b.is_ascii_alphanumeric() || b"+/-_".contains(&b) {
+        assert!(res.is_ok(), "{b:#04x} is valid data");
+      } else {
+        assert!(res.is_err(), "{b:#04x} is not valid data");
+      }
+    }
+  }
+
+  #[test]
+  fn error_offset() {
+    for (i, enc, _) in random_tests() {
+      let len = enc.iter().take_while(|&&c| c != b'=').count();
+      for j in [0, len / 2, len.saturating_sub(1)] {
+        if j >= len {
+          continue;
+        }
+
+        let mut bad = enc.to_vec();
+        bad[j] = b'!';
+        if j + 1 < len {
+          bad[j + 1] = b'?';
+        }
+
+        let err = crate::decode(&bad).unwrap_err();
+        assert_eq!(err.offset(), Some(j), "case {i}");
+      }
+    }
+
+    assert_eq!(crate::decode(b"AA=A").unwrap_err().offset(), Some(2));
+    assert_eq!(
+      crate::base32::decode(b"NBSW!3DP").unwrap_err().offset(),
+      Some(4)
+    );
+    assert_eq!(crate::hex::decode(b"cafg").unwrap_err().offset(), Some(3));
+  }
+
+  #[test]
+  fn validate() {
+    for (i, enc, dec) in random_tests() {
+      assert!(crate::is_base64(enc), "case {i}");
+
+      let url = crate::encode_url(&dec);
+      assert!(crate::is_base64_url(url.as_bytes()), "case {i}");
+      assert_eq!(
+        crate::is_base64(url.as_bytes()),
+        crate::decode(url.as_bytes()).is_ok(),
+        "case {i}"
+      );
+    }
+
+    // Not quite to the end, since a trailing `=` is fine.
+    for len in 0..60 {
+      for bad in *b"!=\n\xff" {
+        let mut data = b"QUJD".repeat(16);
+        data[len] = bad;
+        assert!(!crate::is_base64(&data), "{len} {bad:#04x}");
+        if bad != b'=' {
+          assert!(!crate::is_base64(&data[..len + 1]), "{len} {bad:#04x}");
+        }
+      }
+    }
+
+    assert!(crate::is_base64(b""));
+    assert!(crate::is_base64(b"QQ=="));
+    assert!(!crate::is_base64(b"QQ="));
+    assert!(!crate::is_base64(b"QUJDR"));
+  }
+
+  #[test]
+  fn find_invalid() {
+    for (i, enc, dec) in random_tests() {
+      assert_eq!(crate::find_invalid(enc), None, "case {i}");
+
+      let url = crate::encode_url(&dec);
+      assert_eq!(crate::find_invalid_url(url.as_bytes()), None, "case {i}");
+      assert_eq!(
+        crate::find_invalid(url.as_bytes()),
+        url.find(['-', '_']),
+        "case {i}"
+      );
+    }
+
+    for len in 0..64 {
+      let mut data = b"QUJD".repeat(16);
+      data[len] = b'!';
+      data[63] = b'?';
+      assert_eq!(crate::find_invalid(&data), Some(len));
+    }
+
+    assert_eq!(crate::find_invalid(b"QUJ"), None);
+    assert_eq!(crate::find_invalid(b"Q==="), Some(1));
+    assert_eq!(crate::find_invalid(b"Q=B="), Some(1));
+  }
+
+  #[test]
+  fn until_invalid() {
+    let cases: [(&[u8], &[u8], usize); 9] = [
+      (b"", b"", 0),
+      (b"QUJD", b"ABC", 4),
+      (b"QUJD=extra", b"ABC", 4),
+      (b"QUI=;x", b"AB", 4),
+      (b"QQ==\r\n", b"A", 4),
+      (b"QQ=", b"A", 2),
+      (b"QUJDR!", b"ABC", 4),
+      (b"QUJDRA-", b"ABCD", 6),
+      (b"!QUJD", b"", 0),
+    ];
+    for (enc, dec, len) in cases {
+      let mut out = b"x".to_vec();
+      assert_eq!(
+        crate::decode_until_invalid_to(enc, &mut out),
+        len,
+        "{enc:?}"
+      );
+      assert_eq!(out[1..], *dec, "{enc:?}");
+    }
+
+    for (i, enc, dec) in random_tests() {
+      let mut data = enc.to_vec();
+      data.extend_from_slice(b" trailer");
+      assert_eq!(
+        crate::decode_until_invalid(&data),
+        (dec, enc.len()),
+        "case {i}"
+      );
+    }
+  }
+
+  #[test]
+  fn untouched_on_error() {
+    let bad: [&[u8]; 5] = [
+      b"QUJDRA!=",
+      b"!QUJDRA=",
+      &[b'Q'; 101],
+      &[b'\xff'; 100],
+      b"QUJDRA===",
+    ];
+    for data in bad {
+      let mut big = b"QUJD".repeat(64);
+      big.extend_from_slice(data);
+      for data in [data, &big] {
+        let mut out = b"keep".to_vec();
+        assert!(crate::decode_to(data, &mut out).is_err());
+        assert!(crate::decode_mixed_to(data, &mut out).is_err());
+        assert!(crate::Engine::STANDARD
+          .strict()
+          .decode_to(data, &mut out)
+          .is_err());
+        assert_eq!(out, b"keep");
+      }
+    }
+
+    let mut out = b"keep".to_vec();
+    let strict = crate::Engine::STANDARD.strict();
+    assert!(strict.decode_to(b"QUJDRB==", &mut out).is_err());
+    assert_eq!(out, b"keep");
+  }
+
+  #[test]
+  fn wrapped() {
+    use crate::decode_wrapped;
+    use crate::Padding;
+
+    let std = &crate::simd::STANDARD;
+    for (i, enc, dec) in random_tests() {
+      if enc.is_empty() {
+        continue;
+      }
+
+      for (width, eol) in [(64, &b"\n"[..]), (76, b"\r\n"), (4, b"\n")] {
+        let wrapped = enc
+          .chunks(width)
+          .flat_map(|line| [line, eol])
+          .flatten()
+          .copied()
+          .collect::<Vec<_>>();
+
+        let mut out = Vec::new();
+        assert!(
+          decode_wrapped(&wrapped, &mut out, std, Padding::Optional, true),
+          "case {i}"
+        );
+        assert_eq!(out, dec, "case {i}");
+
+        let trimmed = &wrapped[..wrapped.len() - eol.len()];
+        assert_eq!(
+          decode_wrapped(trimmed, &mut out, std, Padding::Optional, true),
+          enc.len() > width,
+          "case {i}"
+        );
+      }
+    }
+
+    let bad: [&[u8]; 5] = [
+      b"QUJDQUJD\nQUJD\nQUJD\n",
+      b"QUJ\nQUJ\n",
+      b"QUJD\r\nQUJD\nQUJD",
+      b"QQ==\nQUJD\n",
+      b"QUJD\nQUJD\n\nQUJD",
+    ];
+    for data in bad {
+      let mut out = b"keep".to_vec();
+      assert!(!decode_wrapped(
+        data,
+        &mut out,
+        std,
+        Padding::Optional,
+        false
+      ));
+      assert_eq!(out, b"keep", "{data:?}");
+    }
+  }
+
+  #[test]
+  fn limit() {
+    for (i, enc, dec) in random_tests() {
+      let res = crate::decode_with_limit(enc, dec.len());
+      assert_eq!(res.unwrap(), dec, "case {i}");
+
+      if let Some(limit) = dec.len().checked_sub(1) {
+        let err = crate::decode_with_limit(enc, limit).unwrap_err();
+        assert!(matches!(err, crate::Error::LimitExceeded), "case {i}");
+      }
+    }
+
+    assert!(crate::decode_with_limit(b"QUJD!", 3).is_err());
+    assert!(crate::decode_with_limit([0; 1 << 20], 1024).is_err());
+  }
+
+  #[test]
+  fn to_slice() {
+    let data = (0..=255).collect::<Vec<u8>>();
+    for len in 0..100 {
+      let enc = crate::encode(&data[..len]);
+
+      // Exactly the right size, which exercises the bounced tail stores.
+      let mut buf = vec![0xaa; len];
+      let n = crate::decode_to_slice(enc.as_bytes(), &mut buf).unwrap();
+      assert_eq!(n, len);
+      assert_eq!(buf, &data[..len], "len {len}");
+
+      // Bytes past the output are left alone.
+      let mut buf = vec![0xaa; len + 40];
+      let n = crate::decode_to_slice(enc.as_bytes(), &mut buf).unwrap();
+      assert_eq!(&buf[..n], &data[..len], "len {len}");
+      assert!(buf[n..].iter().all(|&b| b == 0xaa), "len {len}");
+
+      if len > 0 {
+        let mut buf = vec![0; len - 1];
+        let err = crate::decode_to_slice(enc.as_bytes(), &mut buf);
+        assert!(matches!(err, Err(crate::Error::BufferTooSmall)));
+      }
+    }
+
+    let mut buf = [0; 16];
+    assert!(crate::decode_to_slice(b"QUJD!", &mut buf).is_err());
+    assert!(crate::decode_to_slice(b"QUJDR", &mut buf).is_err());
+    assert_eq!(crate::decode_to_slice(b"", &mut []).unwrap(), 0);
+  }
+
+  #[test]
+  fn encode_to_slice() {
+    let data = (0..=255).collect::<Vec<u8>>();
+    for len in 0..100 {
+      let enc = crate::encode(&data[..len]);
+
+      let mut buf = vec![0xaa; enc.len()];
+      let n = crate::encode_to_slice(&data[..len], &mut buf).unwrap();
+      assert_eq!(&buf[..n], enc.as_bytes(), "len {len}");
+
+      let mut buf = vec![0xaa; enc.len() + 40];
+      let n = crate::encode_to_slice(&data[..len], &mut buf).unwrap();
+      assert_eq!(&buf[..n], enc.as_bytes(), "len {len}");
+      assert!(buf[n..].iter().all(|&b| b == 0xaa), "len {len}");
+
+      if len > 0 {
+        let mut buf = vec![0xaa; enc.len() - 1];
+        let err = crate::encode_to_slice(&data[..len], &mut buf);
+        assert!(matches!(err, Err(crate::Error::BufferTooSmall)));
+        assert!(buf.iter().all(|&b| b == 0xaa), "len {len}");
+      }
+    }
+  }
+
+  #[test]
+  fn to_uninit() {
+    use core::mem::MaybeUninit;
+
+    let data = (0..=255).collect::<Vec<u8>>();
+    for len in 0..100 {
+      let mut enc = Vec::with_capacity(len / 3 * 4 + 4);
+      let n = crate::encode_to_uninit(&data[..len], enc.spare_capacity_mut())
+        .unwrap();
+      unsafe { enc.set_len(n) };
+      assert_eq!(enc, crate::encode(&data[..len]).as_bytes(), "len {len}");
+
+      let mut dec = [MaybeUninit::uninit(); 100];
+      let n = crate::decode_to_uninit(&enc, &mut dec).unwrap();
+      let dec = unsafe { &*(&dec[..n] as *const [_] as *const [u8]) };
+      assert_eq!(dec, &data[..len], "len {len}");
+    }
+  }
+
+  #[test]
+  fn in_place() {
+    for (i, enc, dec) in random_tests() {
+      let mut buf = enc.to_vec();
+      assert_eq!(crate::decode_in_place(&mut buf).unwrap(), dec, "case {i}");
+    }
+
+    let data = (0..2000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let enc = crate::encode(&data);
+    for len in [0, 1, 383, 384, 385, 767, 768, 769, enc.len()] {
+      let mut buf = enc.as_bytes()[..len].to_vec();
+      let res = crate::decode_in_place(&mut buf).ok().map(|b| b.to_vec());
+      assert_eq!(res, crate::decode(&enc.as_bytes()[..len]).ok(), "len {len}");
+    }
+
+    // Errors point into the original input, even past the first chunk.
+    for at in [0, 100, 511, 512, 1000, 2000] {
+      let mut buf = enc.clone().into_bytes();
+      buf[at] = b'!';
+      let err = crate::decode_in_place(&mut buf).unwrap_err();
+      assert_eq!(err.offset(), Some(at));
+    }
+  }
+
+  #[test]
+  fn encode_in_place() {
+    let data = (0..2000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    for len in [0, 1, 2, 3, 4, 100, 383, 384, 385, 767, 768, 769, 2000] {
+      let enc = crate::encode(&data[..len]);
+
+      let mut buf = data.clone();
+      buf.resize(enc.len().max(len), 0xaa);
+      let out = crate::encode_in_place(&mut buf, len).unwrap();
+      assert_eq!(out, enc.as_bytes(), "len {len}");
+
+      if enc.len() > len {
+        let mut buf = data[..len].to_vec();
+        buf.resize(enc.len() - 1, 0xaa);
+        let before = buf.clone();
+        let err = crate::encode_in_place(&mut buf, len).unwrap_err();
+        assert!(matches!(err, crate::Error::BufferTooSmall));
+        assert_eq!(buf, before);
+      }
+    }
+  }
+
+  #[test]
+  fn to_string() {
+    let mut out = String::from("data:");
+    for (i, enc, dec) in random_tests() {
+      out.truncate(5);
+      crate::encode_to_string(&dec, &mut out);
+      assert_eq!(&out.as_bytes()[5..], enc, "case {i}");
+    }
+  }
+
+  #[test]
+  fn as_ref() {
+    let enc = String::from("aGVsbG8=");
+    assert_eq!(crate::decode(&enc).unwrap(), b"hello");
+    assert_eq!(crate::decode("aGVsbG8=").unwrap(), b"hello");
+    assert_eq!(crate::decode(enc.clone().into_bytes()).unwrap(), b"hello");
+    assert_eq!(crate::decode_str(&enc).unwrap(), b"hello");
+    assert_eq!(crate::encode("hello"), enc);
+    assert_eq!(crate::encode(vec![b'h', b'i']), "aGk=");
+    assert_eq!(crate::Engine::URL_SAFE.encode([0xfb, 0xff]), "-_8=");
+  }
+
+  #[test]
+  fn len_helpers() {
+    let data = [0xaa; 200];
+    for len in 0..200 {
+      let enc = crate::encode(&data[..len]);
+      assert_eq!(crate::encoded_len(len, true), Some(enc.len()));
+      assert_eq!(crate::decoded_len(&enc), len);
+
+      let enc = crate::encode_nopad(&data[..len]);
+      assert_eq!(crate::encoded_len(len, false), Some(enc.len()));
+      assert_eq!(crate::decoded_len(&enc), len);
+      assert_eq!(crate::max_decoded_len(enc.len()), len);
+
+      let pem = crate::pem::encode("", &data[..len]);
+      let body = pem.len() - "-----BEGIN -----\n-----END -----\n".len();
+      let wrapped = crate::encoded_len_wrapped(len, true, 64, 1);
+      assert_eq!(wrapped, Some(body), "len {len}");
+    }
+  }
+
+  #[test]
+  fn counts() {
+    let mut out = b"prefix".to_vec();
+    assert_eq!(crate::encode_to(b"hello", &mut out), 8);
+    assert_eq!(crate::encode_url_nopad_to(b"hello", &mut out), 7);
+    assert_eq!(out, b"prefixaGVsbG8=aGVsbG8");
+
+    let mut dec = b"prefix".to_vec();
+    assert_eq!(crate::decode_to(&out[6..14], &mut dec).unwrap(), 5);
+    assert_eq!(crate::decode_url_nopad_to(&out[14..], &mut dec).unwrap(), 5);
+    assert_eq!(dec, b"prefixhellohello");
+
+    let engine = crate::Engine::STANDARD.ignore_whitespace();
+    assert_eq!(engine.decode_to(b"aGVs\nbG8=\n", &mut dec).unwrap(), 5);
+    assert_eq!(engine.decode_to(b"aGVs bG8=", &mut dec).unwrap(), 5);
+    assert_eq!(crate::decode_mime_to(b"aGVs\r\nbG8=", &mut dec).unwrap(), 5);
+  }
+
+  #[test]
+  fn exact() {
+    use crate::Error;
+
+    let key = (0..32).collect::<Vec<u8>>();
+    let enc = crate::encode(&key);
+    assert_eq!(crate::decode_exact::<32>(&enc).unwrap()[..], key);
+    for bad in [crate::decode_exact::<31>(&enc), crate::decode_exact(&enc)] {
+      assert!(matches!(bad, Err(Error::InvalidLength)));
+    }
+    let err = crate::decode_exact::<2>("QU!=").unwrap_err();
+    assert_eq!(err.offset(), Some(2));
+    assert_eq!(crate::decode_exact::<0>("").unwrap(), [0u8; 0]);
+
+    let engine = crate::Engine::STANDARD.ignore_whitespace();
+    assert_eq!(engine.decode_exact::<5>("aGVs\nbG8=").unwrap(), *b"hello");
+    let err = engine.decode_exact::<4>("aGVs\nbG8=").unwrap_err();
+    assert!(matches!(err, Error::InvalidLength));
+    let err = engine.decode_exact::<6>("aGVs\nbG8=").unwrap_err();
+    assert!(matches!(err, Error::InvalidLength));
+  }
+
+  #[test]
+  fn boxed() {
+    for (i, enc, dec) in random_tests() {
+      let boxed = crate::decode_boxed(enc).unwrap();
+      assert_eq!(boxed[..], dec, "case {i}");
+      let boxed = crate::encode_boxed(&dec);
+      assert_eq!(boxed.as_bytes(), enc, "case {i}");
+    }
+
+    assert!(crate::decode_boxed("QUJD!").is_err());
+    let engine = crate::Engine::URL_SAFE_NOPAD.ignore_whitespace();
+    assert_eq!(&*engine.encode_boxed(b"\xfb\xff"), "-_8");
+    assert_eq!(&*engine.decode_boxed("-_\n8\n").unwrap(), b"\xfb\xff");
+  }
+
+  #[test]
+  fn allocator() {
+    use core::alloc::AllocError;
+    use core::alloc::Allocator;
+    use core::alloc::Layout;
+    use core::cell::Cell;
+    use core::ptr::NonNull;
+    use std::alloc::Global;
+
+    /// Counts how many allocations it has been asked for.
+    struct Counting<'a>(&'a Cell<usize>);
+    unsafe impl Allocator for Counting<'_> {
+      fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0.set(self.0.get() + 1);
+        Global.allocate(layout)
+      }
+
+      unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { Global.deallocate(ptr, layout) }
+      }
+    }
+
+    let count = Cell::new(0);
+    for (i, enc, dec) in random_tests() {
+      let decoded = crate::decode_in(enc, Counting(&count)).unwrap();
+      assert_eq!(decoded, dec, "case {i}");
+      let encoded = crate::encode_in(&dec, Counting(&count));
+      assert_eq!(encoded, enc, "case {i}");
+    }
+    assert!(count.get() > 0);
+
+    assert!(crate::decode_in("QUJD!", Global).is_err());
+    let engine = crate::Engine::URL_SAFE_NOPAD.ignore_whitespace();
+    assert_eq!(engine.encode_in(b"\xfb\xff", Global), b"-_8");
+    assert_eq!(engine.decode_in("-_\n8\n", Global).unwrap(), b"\xfb\xff");
+  }
+
+  #[test]
+  fn overflow() {
+    assert_eq!(crate::encoded_len(3, false), Some(4));
+    assert_eq!(crate::encoded_len(usize::MAX / 4 * 3 + 3, false), None);
+    assert_eq!(crate::encoded_len(usize::MAX, false), None);
+    assert_eq!(crate::encoded_len(usize::MAX / 4 * 3 + 1, true), None);
+
+    let mut out = b"keep".to_vec();
+    for len in [Some(usize::MAX), Some(isize::MAX as usize), None] {
+      let err = crate::reserve(&mut out, len).unwrap_err();
+      assert!(matches!(err, crate::Error::CapacityOverflow));
+    }
+    assert_eq!(out, b"keep");
+  }
+
+  #[test]
+  fn try_encode() {
+    use core::alloc::AllocError;
+    use core::alloc::Allocator;
+    use core::alloc::Layout;
+    use core::ptr::NonNull;
+
+    /// Never has any memory to give.
+    struct Empty;
+    unsafe impl Allocator for Empty {
+      fn allocate(&self, _: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        Err(AllocError)
+      }
+
+      unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {}
+    }
+
+    for (i, enc, dec) in random_tests() {
+      assert_eq!(crate::try_encode(&dec).unwrap().as_bytes(), enc, "case {i}");
+      let boxed = crate::try_encode_boxed(&dec).unwrap();
+      assert_eq!(boxed.as_bytes(), enc, "case {i}");
+      let engine = crate::Engine::STANDARD.wrap(64);
+      assert_eq!(
+        engine.try_encode(&dec).unwrap(),
+        engine.encode(&dec),
+        "case {i}"
+      );
+    }
+
+    let err = crate::try_encode_in(b"hello", Empty).unwrap_err();
+    assert_eq!(err, crate::Error::CapacityOverflow);
+    let err = crate::Engine::STANDARD.try_encode_in(b"hello", Empty);
+    assert_eq!(err.unwrap_err(), crate::Error::CapacityOverflow);
+
+    let mut out = b"keep".to_vec();
+    assert_eq!(crate::try_encode_to(b"", &mut out), Ok(0));
+    assert_eq!(out, b"keep");
+  }
+
+  /// Feeds `data` to every decoder in the crate. Decoders must report bad
+  /// input as an error, never a panic.
+  fn decode_everything(data: &[u8]) {
+    use crate::*;
+
+    let engines = [
+      Engine::STANDARD,
+      Engine::URL_SAFE_NOPAD.lenient_length(),
+      Engine::STANDARD.strict(),
+      Engine::STANDARD.ignore_whitespace(),
+      Engine::CRYPT.strict().ignore_whitespace(),
+      Engine::STANDARD.wrap(4),
+    ];
+
+    let _ = decode(data);
+    let _ = decode_nopad(data);
+    let _ = decode_url(data);
+    let _ = decode_url_nopad(data);
+    let _ = decode_mixed(data);
+    let _ = decode_mime(data);
+    let _ = decode_with_limit(data, 16);
+    let _ = decode_boxed(data);
+    let _ = decode_in(data, std::alloc::Global);
+    let _ = decode_exact::<3>(data);
+    let _ = decode_to_slice(data, &mut [0; 160]);
+    let _ = decode_to_slice(data, &mut [0; 2]);
+    let _ = decode_in_place(&mut data.to_vec());
+    let _ = decode_until_invalid(data);
+    let _ = decode_many(&[data, data]);
+    let _ = decode_iter(data.chunks(3)).count();
+    let _ = decode_vectored(&[std::io::IoSlice::new(data)]);
+    let _ = decode_utf16(&data.iter().map(|&b| b as u16).collect::<Vec<_>>());
+    let _ = is_base64(data);
+    let _ = is_base64_url(data);
+    let _ = find_invalid(data);
+    let _ = find_invalid_url(data);
+    let _ = find_base64(data, 4).count();
+    let _ = verify(data, b"hello");
+    if let Ok(text) = core::str::from_utf8(data) {
+      let _ = decode_data_uri(text);
+      if let Ok(text) = Base64Str::new(text) {
+        let _ = text.decode();
+      }
+    }
+    for engine in engines {
+      let _ = engine.decode(data);
+      let _ = engine.decode_to_slice(data, &mut [0; 160]);
+      let _ = engine.decode_exact::<3>(data);
+
+      let mut dec = Decoder::with_engine(&engine);
+      let mut out = Vec::new();
+      let mut buf = [0; 16];
+      for chunk in data.chunks(5) {
+        if dec.update(chunk, &mut out).is_err()
+          || dec.update_to_slice(chunk, &mut buf).is_err()
+        {
+          break;
+        }
+      }
+      let _ = dec.finish(&mut out);
+    }
+
+    let _ = armor::decode(data);
+    let _ = ascii85::decode(data);
+    let _ = ascii85::decode_adobe(data);
+    let _ = base32::decode(data);
+    let _ = base32::decode_hex(data);
+    let _ = base32::decode_crockford(data);
+    let _ = base32::decode_crockford_check(data);
+    let _ = base58::decode(data);
+    let _ = base58::decode_check(data);
+    let _ = base91::decode(data);
+    let _ = emoji::decode(data);
+    let _ = hex::decode(data);
+    let _ = multibase::decode(data);
+    let _ = pem::parse(data);
+    let _ = pem::parse_all(data);
+    let _ = percent::decode(data);
+    let _ = qp::decode(data);
+    let _ = uu::decode(data);
+    let _ = uu::decode_body(data);
+    let _ = vlq::decode(data);
+  }
+
+  /// Throws junk at every decoder in the crate, shaped to get past the
+  /// headers of the formats that have them.
+  #[test]
+  fn no_panic() {
+    const BYTES: &[u8] =
+      b"AQZaz09+/-_=.~!$%<>&zu \t\r\n\0\x7f\x80\xff\xf0\x9f\x8c";
+    const PREFIXES: [&[u8]; 8] = [
+      b"",
+      b"-----BEGIN A-----\n",
+      b"-----BEGIN A-----\nK: V\n\n",
+      b"<~",
+      b"begin 644 f\n",
+      b"M",
+      b"z",
+      "🌀🌀🌀".as_bytes(),
+    ];
+
+    let mut state = 0x9e3779b97f4a7c15u64;
+    let mut next = move || {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      state as usize
+    };
+
+    for _ in 0..2000 {
+      let mut data = PREFIXES[next() % PREFIXES.len()].to_vec();
+      let len = next() % 200;
+      data.extend((0..len).map(|_| BYTES[next() % BYTES.len()]));
+      if next() % 4 == 0 {
+        data.extend_from_slice(b"\n-----END A-----\n=AAAA\nend\n~>");
+      }
+      decode_everything(&data);
+    }
+  }
+
+  proptest::proptest! {
+    #[test]
+    fn no_panic_prop(
+      data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..300),
+    ) {
+      decode_everything(&data);
+    }
+  }
+
+  #[test]
+  fn lengths() {
+    use crate::Error;
+
+    for len in 0..32 {
+      let data = "QUJD".repeat(8);
+      let res = crate::decode(&data.as_bytes()[..len]);
+      assert_eq!(res.is_ok(), len % 4 != 1, "len {len}");
+    }
+
+    assert_eq!(crate::decode(b"QUJDR"), Err(Error::InvalidLength));
+    assert_eq!(crate::decode(b"QQ="), Err(Error::InvalidPadding));
+    assert_eq!(crate::decode(b"QUJD="), Err(Error::InvalidPadding));
+    assert_eq!(crate::decode_url(b"QQ==").unwrap(), b"A");
+    assert_eq!(crate::decode_nopad(b"Q"), Err(Error::InvalidLength));
+  }
+
+  #[test]
+  fn error_kinds() {
+    use crate::Error;
+
+    let err = crate::decode(b"QU!D").unwrap_err();
+    assert_eq!(
+      err,
+      Error::InvalidByte {
+        offset: 2,
+        byte: b'!'
+      }
+    );
+    assert_eq!(err.to_string(), "invalid byte 0x21 at offset 2");
+
+    let err = crate::hex::decode(b"abc").unwrap_err();
+    assert_eq!(err, Error::InvalidLength);
+    assert_eq!(err.to_string(), "invalid input length");
+
+    let err =
+      crate::base58::decode_check(b"3vQB7B6MrGQZaxCuFg4oi").unwrap_err();
+    assert_eq!(err, Error::InvalidChecksum);
+
+    let err = crate::pem::parse(b"-----BEGIN X-----\nQUJD\n").unwrap_err();
+    assert_eq!(err, Error::InvalidFormat);
+
+    let err = crate::pem::parse(b"-----BEGIN X-----\nQU!D\n-----END X-----\n");
+    assert_eq!(err.unwrap_err(), Error::InvalidFormat);
+
+    let err = crate::multibase::decode(b"mQU!D").unwrap_err();
+    assert_eq!(err.offset(), Some(3));
+
+    let err: Box<dyn core::error::Error> = Box::new(err);
+    assert_eq!(err.to_string(), "invalid byte 0x21 at offset 3");
+  }
+
   #[test]
   #[ignore]
   fn keep_for_disassembly() {
-    std::hint::black_box((super::decode as usize, super::encode as usize));
+    let decode = super::decode as fn(&'static [u8]) -> _;
+    let encode = super::encode as fn(&'static [u8]) -> _;
+    core::hint::black_box((decode as usize, encode as usize));
   }
 }
@@ -0,0 +1,197 @@
+//! RFC 2045 MIME encoding and decoding.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::prelude::*;
+use core::simd::LaneCount;
+use core::simd::SupportedLaneCount;
+
+use crate::simd;
+use crate::Engine;
+use crate::Error;
+use crate::LineEnding;
+
+/// What [RFC 2045 §6.8] asks of MIME encoders: lines of at most 76
+/// characters, separated by CRLF.
+///
+/// [RFC 2045 §6.8]: https://datatracker.ietf.org/doc/html/rfc2045#section-6.8
+const MIME: Engine = Engine::STANDARD.wrap(76).line_ending(LineEnding::CrLf);
+
+/// Encodes arbitrary data as base64 for a MIME body, in 76-character lines
+/// that each end in CRLF.
+///
+/// ```
+/// let text = vb64::encode_mime(&[0xfb; 58]);
+/// let lines = text.split_terminator("\r\n").collect::<Vec<_>>();
+/// assert_eq!(lines.len(), 2);
+/// assert_eq!(lines[0].len(), 76);
+/// assert_eq!(lines[1], "+w==");
+/// assert!(text.ends_with("\r\n"));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+pub fn encode_mime(data: &[u8]) -> String {
+  MIME.encode(data)
+}
+
+/// Encodes arbitrary data as base64 for a MIME body, like [`encode_mime()`],
+/// and appends it to `out`, returning how many bytes were appended.
+pub fn encode_mime_to(data: &[u8], out: &mut String) -> usize {
+  MIME.encode_to_string(data, out)
+}
+
+/// Decodes some base64 `data` to a fresh vector, ignoring any characters
+/// outside of the base64 alphabet.
+///
+/// This is the behavior [RFC 2045 §6.8] requires of MIME decoders, which must
+/// skip over line breaks and any other stray characters in message bodies.
+/// Errors are still reported for invalid padding or truncated input.
+///
+/// ```
+/// let data = b"aGVsbG8g\r\nd29y!bGQ=\r\n";
+/// assert_eq!(vb64::decode_mime(data).unwrap(), b"hello world");
+/// ```
+///
+/// [RFC 2045 §6.8]: https://datatracker.ietf.org/doc/html/rfc2045#section-6.8
+pub fn decode_mime(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_mime_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Decodes some base64 data, ignoring any characters outside of the base64
+/// alphabet, and appends it to `out`.
+pub fn decode_mime_to(data: &[u8], out: &mut Vec<u8>) -> Result<usize, Error> {
+  // Most MIME bodies are wrapped at 76 columns with nothing else in them, which
+  // we can decode without copying them first.
+  let (luts, padding) = (&simd::STANDARD, crate::Padding::Optional);
+  let start = out.len();
+  if crate::decode_wrapped(data, out, luts, padding, false) {
+    return Ok(out.len() - start);
+  }
+
+  let mut compacted = Vec::with_capacity(data.len());
+  compact(data, &mut compacted, Keep::Alphabet(&simd::STANDARD));
+  crate::decode_with(&compacted, out, &simd::STANDARD, true)
+    .map_err(Error::unlocated)
+}
+
+/// Which bytes [`compact()`] keeps.
+#[derive(Copy, Clone)]
+pub(crate) enum Keep<'a> {
+  /// Bytes in the alphabet described by these tables, and `=`.
+  Alphabet(&'a simd::Luts),
+  /// Anything but ASCII whitespace.
+  NonWhitespace,
+}
+
+/// Appends the bytes of `data` that `keep` selects to `out`.
+pub(crate) fn compact(data: &[u8], out: &mut Vec<u8>, keep: Keep) {
+  if cfg!(target_feature = "avx2") {
+    compact_tunable::<32>(data, out, keep)
+  } else {
+    compact_tunable::<16>(data, out, keep)
+  }
+}
+
+fn compact_tunable<const N: usize>(data: &[u8], out: &mut Vec<u8>, keep: Keep)
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let keep = |ascii: Simd<u8, N>| match keep {
+    Keep::Alphabet(luts) => {
+      simd::valid_lanes(ascii, luts) | ascii.simd_eq(Simd::splat(b'='))
+    }
+    Keep::NonWhitespace => !simd::whitespace(ascii),
+  };
+
+  // Most chunks will be entirely valid, since line breaks are sparse, so we
+  // only filter byte-by-byte when we have to.
+  let mut chunks = data.chunks_exact(N);
+  for chunk in &mut chunks {
+    let mask = keep(Simd::from_slice(chunk));
+    if mask.all() {
+      out.extend_from_slice(chunk);
+      continue;
+    }
+
+    for (i, &b) in chunk.iter().enumerate() {
+      if mask.test(i) {
+        out.push(b);
+      }
+    }
+  }
+
+  let rest = chunks.remainder();
+  let mut buf = [0; N];
+  buf[..rest.len()].copy_from_slice(rest);
+  let mask = keep(Simd::from_array(buf));
+  for (i, &b) in rest.iter().enumerate() {
+    if mask.test(i) {
+      out.push(b);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn wrapped() {
+    use base64::prelude::*;
+    for (i, _, dec) in crate::tests::random_tests() {
+      let enc = BASE64_STANDARD.encode(&dec);
+      let wrapped = enc
+        .as_bytes()
+        .chunks(76)
+        .flat_map(|line| line.iter().copied().chain(*b"\r\n"))
+        .collect::<Vec<_>>();
+
+      assert_eq!(crate::decode_mime(&wrapped).unwrap(), dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn encode() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let text = crate::encode_mime(&dec);
+      let mut lines = text.split_terminator("\r\n").peekable();
+      while let Some(line) = lines.next() {
+        assert!(!line.contains('\n'), "case {i}");
+        if lines.peek().is_some() {
+          assert_eq!(line.len(), 76, "case {i}");
+        }
+      }
+      assert_eq!(text.replace("\r\n", "").as_bytes(), enc, "case {i}");
+      assert_eq!(
+        crate::decode_mime(text.as_bytes()).unwrap(),
+        dec,
+        "case {i}"
+      );
+    }
+
+    let mut out = "Content-Transfer-Encoding: base64\r\n\r\n".to_string();
+    assert_eq!(crate::encode_mime_to(b"hello", &mut out), 10);
+    assert!(out.ends_with("\r\n\r\naGVsbG8=\r\n"));
+  }
+
+  #[test]
+  fn whitespace() {
+    let data = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+    let mut out = Vec::new();
+    compact(&data, &mut out, Keep::NonWhitespace);
+
+    let expected = data.iter().copied().filter(|b| !b.is_ascii_whitespace());
+    assert_eq!(out, expected.collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn junk() {
+    let data = b"\t Q U J D\x00\xffRE\r\n==\r\n";
+    assert_eq!(crate::decode_mime(data).unwrap(), b"ABCD");
+    assert_eq!(crate::decode_mime(b"!@#$%").unwrap(), b"");
+  }
+}
@@ -0,0 +1,243 @@
+//! [Multibase], self-describing base-encoded strings.
+//!
+//! Multibase puts a one-character prefix in front of the encoded data that
+//! says which encoding it uses, which is how IPFS CIDs and friends are
+//! written. Only the encodings this crate implements are supported.
+//!
+//! ```
+//! use vb64::multibase::Base;
+//!
+//! let text = vb64::multibase::encode(Base::Base32, b"hello");
+//! assert_eq!(text, "bnbswy3dp");
+//!
+//! let (base, data) = vb64::multibase::decode(text.as_bytes()).unwrap();
+//! assert_eq!(base, Base::Base32);
+//! assert_eq!(data, b"hello");
+//! ```
+//!
+//! [Multibase]: https://github.com/multiformats/multibase
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::simd;
+use crate::Error;
+
+/// A multibase encoding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Base {
+  /// Lowercase hex, `f`.
+  Base16,
+  /// Uppercase hex, `F`.
+  Base16Upper,
+  /// Lowercase base32 without padding, `b`.
+  Base32,
+  /// Uppercase base32 without padding, `B`.
+  Base32Upper,
+  /// Lowercase base32 with padding, `c`.
+  Base32Pad,
+  /// Uppercase base32 with padding, `C`.
+  Base32PadUpper,
+  /// Lowercase base32hex without padding, `v`.
+  Base32Hex,
+  /// Uppercase base32hex without padding, `V`.
+  Base32HexUpper,
+  /// Lowercase base32hex with padding, `t`.
+  Base32HexPad,
+  /// Uppercase base32hex with padding, `T`.
+  Base32HexPadUpper,
+  /// Bitcoin's base58, `z`.
+  Base58Btc,
+  /// Base64 without padding, `m`.
+  Base64,
+  /// Base64 with padding, `M`.
+  Base64Pad,
+  /// URL-safe base64 without padding, `u`.
+  Base64Url,
+  /// URL-safe base64 with padding, `U`.
+  Base64UrlPad,
+}
+
+impl Base {
+  /// Every supported encoding.
+  pub const ALL: [Self; 15] = [
+    Self::Base16,
+    Self::Base16Upper,
+    Self::Base32,
+    Self::Base32Upper,
+    Self::Base32Pad,
+    Self::Base32PadUpper,
+    Self::Base32Hex,
+    Self::Base32HexUpper,
+    Self::Base32HexPad,
+    Self::Base32HexPadUpper,
+    Self::Base58Btc,
+    Self::Base64,
+    Self::Base64Pad,
+    Self::Base64Url,
+    Self::Base64UrlPad,
+  ];
+
+  /// Returns the prefix character for this encoding.
+  pub const fn code(self) -> char {
+    match self {
+      Self::Base16 => 'f',
+      Self::Base16Upper => 'F',
+      Self::Base32 => 'b',
+      Self::Base32Upper => 'B',
+      Self::Base32Pad => 'c',
+      Self::Base32PadUpper => 'C',
+      Self::Base32Hex => 'v',
+      Self::Base32HexUpper => 'V',
+      Self::Base32HexPad => 't',
+      Self::Base32HexPadUpper => 'T',
+      Self::Base58Btc => 'z',
+      Self::Base64 => 'm',
+      Self::Base64Pad => 'M',
+      Self::Base64Url => 'u',
+      Self::Base64UrlPad => 'U',
+    }
+  }
+
+  /// Looks up an encoding by its prefix character.
+  pub const fn from_code(code: char) -> Option<Self> {
+    let mut i = 0;
+    while i < Self::ALL.len() {
+      if Self::ALL[i].code() == code {
+        return Some(Self::ALL[i]);
+      }
+      i += 1;
+    }
+    None
+  }
+}
+
+/// Encodes arbitrary data with the given encoding, including its prefix.
+pub fn encode(base: Base, data: &[u8]) -> String {
+  let mut out = vec![base.code() as u8];
+  match base {
+    Base::Base16 => crate::hex::encode_to(data, &mut out),
+    Base::Base16Upper => crate::hex::encode_upper_to(data, &mut out),
+    Base::Base32 => base32(data, &mut out, &simd::BASE32_LOWER, false),
+    Base::Base32Upper => base32(data, &mut out, &simd::BASE32, false),
+    Base::Base32Pad => base32(data, &mut out, &simd::BASE32_LOWER, true),
+    Base::Base32PadUpper => base32(data, &mut out, &simd::BASE32, true),
+    Base::Base32Hex => base32(data, &mut out, &simd::BASE32_HEX_LOWER, false),
+    Base::Base32HexUpper => base32(data, &mut out, &simd::BASE32_HEX, false),
+    Base::Base32HexPad => base32(data, &mut out, &simd::BASE32_HEX_LOWER, true),
+    Base::Base32HexPadUpper => base32(data, &mut out, &simd::BASE32_HEX, true),
+    Base::Base58Btc => crate::base58::encode_to(data, &mut out),
+    Base::Base64 => drop(crate::encode_nopad_to(data, &mut out)),
+    Base::Base64Pad => drop(crate::encode_to(data, &mut out)),
+    Base::Base64Url => drop(crate::encode_url_nopad_to(data, &mut out)),
+    Base::Base64UrlPad => drop(crate::encode_url_to(data, &mut out)),
+  }
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some multibase text, returning the encoding named by its prefix
+/// along with the decoded data.
+pub fn decode(text: &[u8]) -> Result<(Base, Vec<u8>), Error> {
+  let (&code, data) = text.split_first().ok_or(Error::InvalidFormat)?;
+  let base = Base::from_code(code as char).ok_or(Error::InvalidByte {
+    offset: 0,
+    byte: code,
+  })?;
+
+  let mut out = Vec::new();
+  match base {
+    Base::Base16 | Base::Base16Upper => crate::hex::decode_to(data, &mut out),
+    Base::Base32 => unbase32(data, &mut out, &simd::BASE32_LOWER, false),
+    Base::Base32Upper => unbase32(data, &mut out, &simd::BASE32, false),
+    Base::Base32Pad => unbase32(data, &mut out, &simd::BASE32_LOWER, true),
+    Base::Base32PadUpper => unbase32(data, &mut out, &simd::BASE32, true),
+    Base::Base32Hex => unbase32(data, &mut out, &simd::BASE32_HEX_LOWER, false),
+    Base::Base32HexUpper => unbase32(data, &mut out, &simd::BASE32_HEX, false),
+    Base::Base32HexPad => {
+      unbase32(data, &mut out, &simd::BASE32_HEX_LOWER, true)
+    }
+    Base::Base32HexPadUpper => {
+      unbase32(data, &mut out, &simd::BASE32_HEX, true)
+    }
+    Base::Base58Btc => crate::base58::decode_to(data, &mut out),
+    Base::Base64 => crate::decode_nopad_to(data, &mut out).map(drop),
+    Base::Base64Pad => crate::decode_to(data, &mut out).map(drop),
+    Base::Base64Url => crate::decode_url_nopad_to(data, &mut out).map(drop),
+    Base::Base64UrlPad => crate::decode_url_to(data, &mut out).map(drop),
+  }
+  .map_err(|e| e.shift(1))?;
+
+  Ok((base, out))
+}
+
+fn base32(data: &[u8], out: &mut Vec<u8>, luts: &simd::Luts, pad: bool) {
+  crate::base32::encode_with(data, out, luts, pad)
+}
+
+fn unbase32(
+  data: &[u8],
+  out: &mut Vec<u8>,
+  luts: &simd::Luts,
+  pad: bool,
+) -> Result<(), Error> {
+  crate::base32::decode_with(data, out, luts, pad)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn known() {
+    // Test vectors from the multibase spec.
+    let vectors = [
+      (Base::Base16, "f796573206d616e692021"),
+      (Base::Base16Upper, "F796573206D616E692021"),
+      (Base::Base32, "bpfsxgidnmfxgsibb"),
+      (Base::Base32Upper, "BPFSXGIDNMFXGSIBB"),
+      (Base::Base32Pad, "cpfsxgidnmfxgsibb"),
+      (Base::Base32Hex, "vf5in683dc5n6i811"),
+      (Base::Base32HexPadUpper, "TF5IN683DC5N6I811"),
+      (Base::Base58Btc, "z7paNL19xttacUY"),
+      (Base::Base64, "meWVzIG1hbmkgIQ"),
+      (Base::Base64Pad, "MeWVzIG1hbmkgIQ=="),
+      (Base::Base64Url, "ueWVzIG1hbmkgIQ"),
+      (Base::Base64UrlPad, "UeWVzIG1hbmkgIQ=="),
+    ];
+
+    for (base, text) in vectors {
+      assert_eq!(encode(base, b"yes mani !"), text, "{base:?}");
+      assert_eq!(
+        decode(text.as_bytes()).unwrap(),
+        (base, b"yes mani !".to_vec()),
+        "{base:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn roundtrip() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      for base in Base::ALL {
+        assert_eq!(Base::from_code(base.code()), Some(base));
+
+        let enc = encode(base, &dec);
+        assert_eq!(
+          decode(enc.as_bytes()).unwrap(),
+          (base, dec.clone()),
+          "case {i}: {base:?}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn errors() {
+    assert!(decode(b"").is_err());
+    assert!(decode(b"?abc").is_err());
+    assert!(decode(b"bNBSWY3DP").is_err());
+    assert!(decode(b"z0OIl").is_err());
+  }
+}
@@ -0,0 +1,372 @@
+//! RFC 7468 PEM encoding.
+//!
+//! PEM is the textual format used for certificates and keys, which wraps
+//! base64 in a pair of `-----BEGIN LABEL-----` and `-----END LABEL-----` lines.
+//!
+//! ```
+//! let text = vb64::pem::encode("MESSAGE", b"hello");
+//! assert_eq!(text, "-----BEGIN MESSAGE-----\naGVsbG8=\n-----END MESSAGE-----\n");
+//!
+//! let pem = vb64::pem::parse(text.as_bytes()).unwrap();
+//! assert_eq!(pem.label(), "MESSAGE");
+//! assert_eq!(pem.contents(), b"hello");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+#[cfg(feature = "std")]
+use crate::Decoder;
+use crate::Engine;
+use crate::Error;
+
+/// The number of base64 characters per line, per RFC 7468.
+const LINE_LEN: usize = 64;
+
+/// A single decoded PEM block.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pem {
+  label: String,
+  contents: Vec<u8>,
+}
+
+impl Pem {
+  /// Creates a new PEM block with the given label and contents.
+  pub fn new(label: impl Into<String>, contents: impl Into<Vec<u8>>) -> Self {
+    Self {
+      label: label.into(),
+      contents: contents.into(),
+    }
+  }
+
+  /// Returns this block's label, such as `CERTIFICATE`.
+  pub fn label(&self) -> &str {
+    &self.label
+  }
+
+  /// Returns this block's decoded contents.
+  pub fn contents(&self) -> &[u8] {
+    &self.contents
+  }
+
+  /// Consumes this block, returning its decoded contents.
+  pub fn into_contents(self) -> Vec<u8> {
+    self.contents
+  }
+
+  /// Encodes this block as PEM text.
+  pub fn encode(&self) -> String {
+    encode(&self.label, &self.contents)
+  }
+}
+
+/// Encodes `data` as a PEM block with the given label.
+///
+/// The output uses `\n` line endings, wraps the body at 64 columns, and ends
+/// in a newline.
+pub fn encode(label: &str, data: &[u8]) -> String {
+  let mut out = String::new();
+  out.push_str("-----BEGIN ");
+  out.push_str(label);
+  out.push_str("-----\n");
+
+  Engine::STANDARD
+    .wrap(LINE_LEN)
+    .encode_to_string(data, &mut out);
+
+  out.push_str("-----END ");
+  out.push_str(label);
+  out.push_str("-----\n");
+  out
+}
+
+/// Parses the first PEM block in `text`.
+///
+/// Any text before the `-----BEGIN` line is ignored, as is anything after the
+/// matching `-----END` line. Within the body, leading and trailing whitespace
+/// on each line is ignored, and lines may have any length.
+pub fn parse(text: &[u8]) -> Result<Pem, Error> {
+  match parse_one(text)? {
+    Some((pem, _)) => Ok(pem),
+    None => Err(Error::InvalidFormat),
+  }
+}
+
+/// Parses every PEM block in `text`, such as in a certificate chain.
+pub fn parse_all(text: &[u8]) -> Result<Vec<Pem>, Error> {
+  let mut pems = Vec::new();
+  let mut text = text;
+  while let Some((pem, rest)) = parse_one(text)? {
+    pems.push(pem);
+    text = rest;
+  }
+  Ok(pems)
+}
+
+const BEGIN: &[u8] = b"-----BEGIN ";
+const END: &[u8] = b"-----END ";
+const DASHES: &[u8] = b"-----";
+
+/// Parses the first PEM block in `text`, returning it and the text after it.
+fn parse_one(text: &[u8]) -> Result<Option<(Pem, &[u8])>, Error> {
+  let Some(start) = find(text, BEGIN) else {
+    return Ok(None);
+  };
+  let text = &text[start + BEGIN.len()..];
+
+  let (header, mut text) = split_line(text);
+  let label = parse_label(header)?;
+
+  let mut body = Vec::with_capacity(text.len());
+  loop {
+    if text.is_empty() {
+      return Err(Error::InvalidFormat);
+    }
+
+    let (line, rest) = split_line(text);
+    text = rest;
+
+    let line = trim(line);
+    if let Some(footer) = line.strip_prefix(END) {
+      if footer.strip_suffix(DASHES) != Some(label) {
+        return Err(Error::InvalidFormat);
+      }
+      break;
+    }
+    body.extend_from_slice(line);
+  }
+
+  let pem = Pem {
+    label: String::from_utf8(label.to_vec())
+      .map_err(|_| Error::InvalidFormat)?,
+    contents: crate::decode(&body).map_err(Error::unlocated)?,
+  };
+  Ok(Some((pem, text)))
+}
+
+/// Extracts the label from what follows `-----BEGIN ` on a header line.
+fn parse_label(header: &[u8]) -> Result<&[u8], Error> {
+  let label = trim(header)
+    .strip_suffix(DASHES)
+    .ok_or(Error::InvalidFormat)?;
+  if !label.iter().all(|&b| b.is_ascii_graphic() || b == b' ') {
+    return Err(Error::InvalidFormat);
+  }
+  Ok(label)
+}
+
+/// An iterator over the PEM blocks in a [`BufRead`], such as a CA bundle with
+/// hundreds of certificates.
+///
+/// This reads a line at a time, and decodes each block's body as it goes, so
+/// only the block being read is held in memory. Like [`parse_all()`], it skips
+/// any text between blocks.
+///
+/// Malformed blocks are reported as [`io::ErrorKind::InvalidData`] errors
+/// wrapping an [`Error`], after which the iterator stops.
+///
+/// ```
+/// use vb64::pem::Pem;
+/// use vb64::pem::PemReader;
+///
+/// let bundle = [Pem::new("A", "first"), Pem::new("B", "second")]
+///   .map(|pem| pem.encode())
+///   .join("# comment\n");
+/// let reader = PemReader::new(bundle.as_bytes());
+/// let pems = reader.collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(pems, [Pem::new("A", "first"), Pem::new("B", "second")]);
+/// ```
+#[cfg(feature = "std")]
+pub struct PemReader<R> {
+  inner: R,
+  /// The line being looked at.
+  line: Vec<u8>,
+  /// Whether we've hit the end of the input or an error.
+  done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> PemReader<R> {
+  /// Creates an iterator over the PEM blocks in `inner`.
+  pub fn new(inner: R) -> Self {
+    Self {
+      inner,
+      line: Vec::new(),
+      done: false,
+    }
+  }
+
+  /// Returns the inner reader.
+  pub fn into_inner(self) -> R {
+    self.inner
+  }
+
+  /// Reads the next line into `line`, returning `false` at the end of the
+  /// input.
+  fn read_line(&mut self) -> io::Result<bool> {
+    self.line.clear();
+    Ok(self.inner.read_until(b'\n', &mut self.line)? > 0)
+  }
+
+  /// Reads the next block, if there is one.
+  fn read_pem(&mut self) -> io::Result<Option<Pem>> {
+    let label = loop {
+      if !self.read_line()? {
+        return Ok(None);
+      }
+      if let Some(start) = find(&self.line, BEGIN) {
+        break parse_label(&self.line[start + BEGIN.len()..])?.to_vec();
+      }
+    };
+
+    let mut decoder = Decoder::new();
+    let mut contents = Vec::new();
+    loop {
+      if !self.read_line()? {
+        return Err(Error::InvalidFormat.into());
+      }
+
+      let line = trim(&self.line);
+      if let Some(footer) = line.strip_prefix(END) {
+        if footer.strip_suffix(DASHES) != Some(&label) {
+          return Err(Error::InvalidFormat.into());
+        }
+        break;
+      }
+      decoder
+        .update(line, &mut contents)
+        .map_err(Error::unlocated)?;
+    }
+    decoder.finish(&mut contents).map_err(Error::unlocated)?;
+
+    Ok(Some(Pem {
+      label: String::from_utf8(label).map_err(|_| Error::InvalidFormat)?,
+      contents,
+    }))
+  }
+}
+
+#[cfg(feature = "std")]
+impl<R: BufRead> Iterator for PemReader<R> {
+  type Item = io::Result<Pem>;
+
+  fn next(&mut self) -> Option<io::Result<Pem>> {
+    if self.done {
+      return None;
+    }
+    let next = self.read_pem().transpose();
+    self.done = !matches!(next, Some(Ok(_)));
+    next
+  }
+}
+
+/// Splits off the first line of `text`, excluding its line ending.
+pub(crate) fn split_line(text: &[u8]) -> (&[u8], &[u8]) {
+  match text.iter().position(|&b| b == b'\n') {
+    Some(nl) => (&text[..nl], &text[nl + 1..]),
+    None => (text, &[]),
+  }
+}
+
+/// Trims ASCII whitespace from both ends of `line`.
+pub(crate) fn trim(line: &[u8]) -> &[u8] {
+  let start = line.iter().position(|b| !b.is_ascii_whitespace());
+  let end = line.iter().rposition(|b| !b.is_ascii_whitespace());
+  match (start, end) {
+    (Some(start), Some(end)) => &line[start..=end],
+    _ => &[],
+  }
+}
+
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn roundtrip() {
+    for len in [0, 1, 47, 48, 49, 96, 1000] {
+      let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+      let text = encode("TEST DATA", &data);
+
+      for line in text.lines() {
+        assert!(line.len() <= LINE_LEN, "len {len}");
+      }
+
+      let pem = parse(text.as_bytes()).unwrap();
+      assert_eq!(pem.label(), "TEST DATA");
+      assert_eq!(pem.contents(), data);
+      assert_eq!(pem.encode(), text);
+    }
+  }
+
+  #[test]
+  fn lax() {
+    let text = b"Subject: whatever\r\n\
+      -----BEGIN CERTIFICATE-----\r\n\
+      \taGVs\r\n\
+      bG8=  \r\n\
+      -----END CERTIFICATE-----\r\n\
+      trailing";
+    let pem = parse(text).unwrap();
+    assert_eq!(pem.label(), "CERTIFICATE");
+    assert_eq!(pem.contents(), b"hello");
+  }
+
+  #[test]
+  fn chain() {
+    let text = [encode("A", b"first"), encode("B", b"second")].concat();
+    let pems = parse_all(text.as_bytes()).unwrap();
+    assert_eq!(pems, [Pem::new("A", "first"), Pem::new("B", "second")]);
+  }
+
+  #[test]
+  fn errors() {
+    assert!(parse(b"no pem here").is_err());
+    assert!(parse(b"-----BEGIN A-----\naGVsbG8=\n").is_err());
+    assert!(parse(b"-----BEGIN A-----\naGVsbG8=\n-----END B-----\n").is_err());
+    assert!(parse(b"-----BEGIN A-----\naGV!bG8=\n-----END A-----\n").is_err());
+  }
+
+  #[test]
+  fn reader() {
+    let pems = (0..100)
+      .map(|i| Pem::new(format!("BLOCK {i}"), vec![i as u8; i * 7]))
+      .collect::<Vec<_>>();
+    let text = pems
+      .iter()
+      .map(Pem::encode)
+      .collect::<Vec<_>>()
+      .join("\r\n");
+    for cap in [1, 7, 64, 8192] {
+      let reader = io::BufReader::with_capacity(cap, text.as_bytes());
+      let got = PemReader::new(reader).collect::<io::Result<Vec<_>>>();
+      assert_eq!(got.unwrap(), pems, "capacity {cap}");
+    }
+
+    let text = b"-----BEGIN A-----\r\n\taGVs\r\nbG8=  \r\n-----END A-----";
+    let mut reader = PemReader::new(&text[..]);
+    assert_eq!(reader.next().unwrap().unwrap(), Pem::new("A", "hello"));
+    assert!(reader.next().is_none());
+
+    for bad in [
+      &b"-----BEGIN A-----\naGVsbG8=\n"[..],
+      b"-----BEGIN A-----\naGVsbG8=\n-----END B-----\n",
+      b"-----BEGIN A-----\naGV!bG8=\n-----END A-----\n",
+      b"-----BEGIN A-----\naGVs\nbG8==\n-----END A-----\n",
+    ] {
+      let mut reader = PemReader::new(bad);
+      let err = reader.next().unwrap().unwrap_err();
+      assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+      assert!(reader.next().is_none());
+    }
+    assert!(PemReader::new(&b"no pem here"[..]).next().is_none());
+  }
+}
@@ -0,0 +1,278 @@
+//! URL percent-encoding, per [RFC 3986 §2.1].
+//!
+//! Which characters get escaped depends on where in a URL the result is going
+//! to end up, so encoding takes an [`AsciiSet`] of characters to escape. The
+//! sets from the [WHATWG URL standard] are provided as constants.
+//!
+//! ```
+//! use vb64::percent::AsciiSet;
+//!
+//! let text = vb64::percent::encode(b"a b&c/d", &AsciiSet::COMPONENT);
+//! assert_eq!(text, "a%20b%26c%2Fd");
+//! assert_eq!(vb64::percent::decode(text.as_bytes()).unwrap(), b"a b&c/d");
+//! ```
+//!
+//! [RFC 3986 §2.1]: https://datatracker.ietf.org/doc/html/rfc3986#section-2.1
+//! [WHATWG URL standard]: https://url.spec.whatwg.org/#percent-encoded-bytes
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::prelude::*;
+
+use crate::read_slice_padded;
+use crate::simd;
+use crate::Error;
+
+/// The number of bytes we classify at a time.
+const N: usize = 16;
+
+/// A set of ASCII characters to percent-encode.
+///
+/// Non-ASCII bytes are always percent-encoded, regardless of the set.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AsciiSet {
+  bitmap: [u8; 16],
+}
+
+impl AsciiSet {
+  /// The empty set.
+  pub const EMPTY: Self = Self { bitmap: [0; 16] };
+
+  /// The C0 control characters, `0x00..=0x1f`, and `0x7f`.
+  pub const CONTROLS: Self = Self {
+    bitmap: [
+      0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80,
+    ],
+  };
+
+  /// The fragment percent-encode set.
+  pub const FRAGMENT: Self = Self::CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`');
+
+  /// The query percent-encode set.
+  pub const QUERY: Self = Self::CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>');
+
+  /// The path percent-encode set.
+  pub const PATH: Self = Self::QUERY.add(b'?').add(b'`').add(b'{').add(b'}');
+
+  /// The userinfo percent-encode set.
+  pub const USERINFO: Self = Self::PATH
+    .add(b'/')
+    .add(b':')
+    .add(b';')
+    .add(b'=')
+    .add(b'@')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'|');
+
+  /// The component percent-encode set, which is what JavaScript's
+  /// `encodeURIComponent()` uses, except that it also escapes `!'()*`.
+  pub const COMPONENT: Self = Self::USERINFO
+    .add(b'$')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b',');
+
+  /// Every character other than ASCII letters and digits.
+  pub const NON_ALPHANUMERIC: Self = {
+    let mut set = Self::EMPTY;
+    let mut c = 0;
+    while c < 128 {
+      if !(c as u8).is_ascii_alphanumeric() {
+        set = set.add(c as u8);
+      }
+      c += 1;
+    }
+    set
+  };
+
+  /// Returns this set with `byte` added to it.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `byte` is not ASCII.
+  pub const fn add(mut self, byte: u8) -> Self {
+    assert!(byte.is_ascii(), "AsciiSet can only contain ASCII");
+    self.bitmap[(byte >> 3) as usize] |= 1 << (byte & 7);
+    self
+  }
+
+  /// Returns this set with `byte` removed from it.
+  pub const fn remove(mut self, byte: u8) -> Self {
+    if byte.is_ascii() {
+      self.bitmap[(byte >> 3) as usize] &= !(1 << (byte & 7));
+    }
+    self
+  }
+
+  /// Returns whether `byte` would be percent-encoded by this set.
+  pub const fn contains(&self, byte: u8) -> bool {
+    !byte.is_ascii() || self.bitmap[(byte >> 3) as usize] & 1 << (byte & 7) != 0
+  }
+}
+
+/// Decodes some percent-encoded `data` to a fresh vector.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Percent-encodes arbitrary data, escaping every byte in `set`.
+pub fn encode(data: &[u8], set: &AsciiSet) -> String {
+  let mut out = Vec::new();
+  encode_to(data, set, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some percent-encoded data and appends it to `out`.
+///
+/// Every `%` must be followed by two hex digits, of either case. All other
+/// bytes are copied as-is.
+pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  let start = out.len();
+  out.reserve(data.len());
+
+  let mut i = 0;
+  while i < data.len() {
+    // Fast path: copy entire chunks with no escapes.
+    if let Some(chunk) = data.get(i..i + N) {
+      let chunk = Simd::<u8, N>::from_slice(chunk);
+      if !chunk.simd_eq(Simd::splat(b'%')).any() {
+        out.extend_from_slice(chunk.as_array());
+        i += N;
+        continue;
+      }
+    }
+
+    if data[i] != b'%' {
+      out.push(data[i]);
+      i += 1;
+      continue;
+    }
+
+    let digit =
+      |j: usize| data.get(j).and_then(|&c| (c as char).to_digit(16)).ok_or(j);
+    match (digit(i + 1), digit(i + 2)) {
+      (Ok(hi), Ok(lo)) => out.push((hi << 4 | lo) as u8),
+      (Err(j), _) | (_, Err(j)) => {
+        out.truncate(start);
+        return Err(match data.get(j) {
+          Some(&byte) => Error::InvalidByte { offset: j, byte },
+          None => Error::InvalidLength,
+        });
+      }
+    }
+    i += 3;
+  }
+
+  Ok(())
+}
+
+/// Percent-encodes arbitrary data, escaping every byte in `set`, and appends
+/// it to `out`.
+///
+/// Escapes use uppercase hex digits, as RFC 3986 recommends.
+pub fn encode_to(data: &[u8], set: &AsciiSet, out: &mut Vec<u8>) {
+  out.reserve(data.len());
+
+  for chunk in data.chunks(N) {
+    let len = chunk.len();
+    let chunk = if len == N {
+      Simd::<u8, N>::from_slice(chunk)
+    } else {
+      unsafe { read_slice_padded::<N>(chunk, 0) }
+    };
+
+    let escapes = simd::percent_escapes(chunk, &set.bitmap);
+    if !escapes.any() {
+      out.extend_from_slice(&chunk.as_array()[..len]);
+      continue;
+    }
+
+    // Work out every lane's hex digits up front with a pair of shuffles, and
+    // then pick between them and the original byte.
+    let (hi, lo) = simd::hex_digits(chunk, &simd::HEX_UPPER);
+    for i in 0..len {
+      if escapes.test(i) {
+        out.extend_from_slice(&[b'%', hi[i], lo[i]]);
+      } else {
+        out.push(chunk[i]);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sets() {
+    for b in 0..=255u8 {
+      let expected = !b.is_ascii_alphanumeric();
+      assert_eq!(AsciiSet::NON_ALPHANUMERIC.contains(b), expected, "{b:#04x}");
+      assert_eq!(
+        AsciiSet::CONTROLS.contains(b),
+        !b.is_ascii() || b.is_ascii_control(),
+        "{b:#04x}"
+      );
+    }
+
+    assert!(AsciiSet::COMPONENT.contains(b'/'));
+    assert!(!AsciiSet::PATH.contains(b'/'));
+    assert!(!AsciiSet::EMPTY.add(b'a').remove(b'a').contains(b'a'));
+  }
+
+  #[test]
+  fn roundtrip() {
+    let sets = [
+      AsciiSet::EMPTY,
+      AsciiSet::CONTROLS,
+      AsciiSet::PATH,
+      AsciiSet::COMPONENT,
+      AsciiSet::NON_ALPHANUMERIC,
+    ];
+
+    for (i, _, dec) in crate::tests::random_tests() {
+      for set in &sets {
+        let enc = encode(&dec, &set.add(b'%'));
+        let mut chars = enc.bytes();
+        while let Some(c) = chars.next() {
+          if c == b'%' {
+            chars.next();
+            chars.next();
+          } else {
+            assert!(!set.contains(c), "case {i}: {c:#04x}");
+          }
+        }
+
+        assert_eq!(decode(enc.as_bytes()).unwrap(), dec, "case {i}");
+      }
+    }
+  }
+
+  #[test]
+  fn known() {
+    let set = AsciiSet::NON_ALPHANUMERIC;
+    assert_eq!(encode("ü/ß".as_bytes(), &set), "%C3%BC%2F%C3%9F");
+    assert_eq!(decode(b"%c3%bc%2f").unwrap(), "ü/".as_bytes());
+
+    assert!(decode(b"%").is_err());
+    assert!(decode(b"100%").is_err());
+    assert!(decode(b"%zz").is_err());
+  }
+}
@@ -0,0 +1,106 @@
+//! Python bindings, as a faster stand-in for the standard `base64` module.
+//!
+//! Requires the `python` feature; build the extension module with `maturin`,
+//! which picks up the settings in `pyproject.toml`:
+//!
+//! ```python
+//! import vb64
+//!
+//! assert vb64.b64encode(b"hello") == b"aGVsbG8="
+//! assert vb64.b64decode(memoryview(b"aGVsbG8=")) == b"hello"
+//! ```
+//!
+//! Both functions accept anything that supports the buffer protocol, and do
+//! their work with the GIL released, so other Python threads can keep running
+//! while a large blob is being processed.
+
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+/// Encodes a bytes-like object as standard, padded base64, returning `bytes`.
+#[pyfunction]
+fn b64encode<'py>(
+  py: Python<'py>,
+  data: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyBytes>> {
+  let text = with_bytes(py, data, |data| crate::encode(data).into_bytes())?;
+  Ok(PyBytes::new(py, &text))
+}
+
+/// Decodes a bytes-like object holding standard base64, returning `bytes`.
+///
+/// Raises `ValueError` if the input isn't valid base64.
+#[pyfunction]
+fn b64decode<'py>(
+  py: Python<'py>,
+  data: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyBytes>> {
+  let data = with_bytes(py, data, |data| crate::decode(data))?
+    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+  Ok(PyBytes::new(py, &data))
+}
+
+/// Runs `f` on the contents of `data` with the GIL released.
+///
+/// Only `bytes` is used in place, since it's immutable. Anything else is copied
+/// first: even a read-only buffer, like `memoryview(bytearray).toreadonly()`,
+/// may be a view of something other threads can change while we're not holding
+/// the GIL.
+fn with_bytes<R: Send>(
+  py: Python,
+  data: &Bound<PyAny>,
+  f: impl FnOnce(&[u8]) -> R + Send,
+) -> PyResult<R> {
+  if let Ok(bytes) = data.cast_exact::<PyBytes>() {
+    let bytes = bytes.as_bytes();
+    return Ok(py.detach(|| f(bytes)));
+  }
+
+  let copy = PyBuffer::<u8>::get(data)?.to_vec(py)?;
+  Ok(py.detach(|| f(&copy)))
+}
+
+/// The `vb64` Python module.
+#[pymodule]
+fn vb64(m: &Bound<PyModule>) -> PyResult<()> {
+  m.add_function(wrap_pyfunction!(b64encode, m)?)?;
+  m.add_function(wrap_pyfunction!(b64decode, m)?)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use pyo3::types::PyByteArray;
+  use pyo3::types::PyMemoryView;
+
+  use super::*;
+
+  #[test]
+  fn python() {
+    Python::initialize();
+    Python::attach(|py| {
+      for (i, enc, dec) in crate::tests::random_tests() {
+        let data = PyBytes::new(py, &dec);
+        let got = b64encode(py, &data).unwrap();
+        assert_eq!(got.as_bytes(), enc, "case {i}");
+
+        let text = PyByteArray::new(py, enc);
+        let view = PyMemoryView::from(&text).unwrap();
+        let got = b64decode(py, &view).unwrap();
+        assert_eq!(got.as_bytes(), dec, "case {i}");
+      }
+
+      // A read-only view of something mutable still gets copied.
+      let text = PyByteArray::new(py, b"aGVsbG8=");
+      let view = PyMemoryView::from(&text).unwrap();
+      let view = view.call_method0("toreadonly").unwrap();
+      assert_eq!(b64decode(py, &view).unwrap().as_bytes(), b"hello");
+
+      let err = b64decode(py, &PyBytes::new(py, b"QUJD!")).unwrap_err();
+      assert!(err.is_instance_of::<PyValueError>(py));
+      assert!(b64encode(py, &"not bytes".into_pyobject(py).unwrap()).is_err());
+    });
+  }
+}
@@ -0,0 +1,241 @@
+//! Quoted-printable, per [RFC 2045 §6.7].
+//!
+//! Quoted-printable leaves most printable ASCII alone, and escapes everything
+//! else as `=XX`. Lines are kept to 76 characters with soft line breaks, which
+//! are a `=` at the end of a line.
+//!
+//! ```
+//! let text = vb64::qp::encode("café = coffee".as_bytes());
+//! assert_eq!(text, "caf=C3=A9 =3D coffee");
+//!
+//! let data = vb64::qp::decode(text.as_bytes()).unwrap();
+//! assert_eq!(data, "café = coffee".as_bytes());
+//! ```
+//!
+//! [RFC 2045 §6.7]: https://datatracker.ietf.org/doc/html/rfc2045#section-6.7
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::Simd;
+
+use crate::simd;
+use crate::Error;
+
+/// The number of bytes we classify at a time.
+const N: usize = 16;
+
+/// The maximum length of an encoded line, not counting the line break.
+const MAX_LINE: usize = 76;
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Decodes some quoted-printable `data` to a fresh vector.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_to(data, &mut out)?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as quoted-printable.
+pub fn encode(data: &[u8]) -> String {
+  let mut out = Vec::new();
+  encode_to(data, &mut out);
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes some quoted-printable data and appends it to `out`.
+///
+/// Soft line breaks are removed, and hard line breaks are kept as-is. Both
+/// `\r\n` and `\n` are accepted as line breaks, and whitespace at the end of a
+/// line is ignored, since it may have been added in transit. Lowercase hex
+/// digits are accepted in escapes.
+pub fn decode_to(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  let start = out.len();
+  let result = decode_impl(data, out);
+  if result.is_err() {
+    out.truncate(start);
+  }
+  result
+}
+
+fn decode_impl(data: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+  out.reserve(data.len());
+
+  // Everything before `keep` was escaped, so it's not trailing whitespace
+  // that we should strip.
+  let mut keep = out.len();
+  let strip = |out: &mut Vec<u8>, keep: usize| {
+    while out.len() > keep && matches!(out.last(), Some(b' ' | b'\t')) {
+      out.pop();
+    }
+  };
+
+  let mut i = 0;
+  while i < data.len() {
+    // Fast path: copy entire chunks with no escapes or line breaks.
+    if let Some(chunk) = data.get(i..i + N) {
+      if !simd::qp_special(Simd::<u8, N>::from_slice(chunk)).any() {
+        out.extend_from_slice(chunk);
+        i += N;
+        continue;
+      }
+    }
+
+    match data[i] {
+      b'=' => {
+        let rest = &data[i + 1..];
+        if let [hi, lo, ..] = rest {
+          if let (Some(hi), Some(lo)) = (hex_digit(*hi), hex_digit(*lo)) {
+            out.push(hi << 4 | lo);
+            keep = out.len();
+            i += 3;
+            continue;
+          }
+        }
+
+        // Otherwise, this must be a soft line break.
+        let ws = rest
+          .iter()
+          .take_while(|b| matches!(b, b' ' | b'\t'))
+          .count();
+        i += 1 + ws;
+        match &rest[ws..] {
+          [b'\r', b'\n', ..] => i += 2,
+          [b'\n', ..] => i += 1,
+          [] => {}
+          _ => {
+            return Err(Error::InvalidByte {
+              offset: i,
+              byte: data[i],
+            })
+          }
+        }
+      }
+      b @ (b'\r' | b'\n') => {
+        strip(out, keep);
+        out.push(b);
+        i += 1;
+      }
+      b => {
+        out.push(b);
+        i += 1;
+      }
+    }
+  }
+
+  strip(out, keep);
+  Ok(())
+}
+
+/// Encodes arbitrary data as quoted-printable and appends it to `out`.
+///
+/// `\r\n` in `data` is encoded as a hard line break; every other control
+/// character, including a lone `\r` or `\n`, is escaped. Soft line breaks use
+/// `\r\n`.
+pub fn encode_to(data: &[u8], out: &mut Vec<u8>) {
+  out.reserve(data.len() + data.len() / 8);
+
+  // The number of characters on the current line.
+  let mut col = 0;
+  let mut i = 0;
+  while i < data.len() {
+    // Fast path: copy entire chunks that don't need escaping and fit on the
+    // current line. The last byte can't be whitespace, since it might be
+    // followed by a line break.
+    if let Some(chunk) = data.get(i..i + N) {
+      if col + N < MAX_LINE
+        && simd::qp_literal(Simd::<u8, N>::from_slice(chunk)).all()
+        && !matches!(chunk[N - 1], b' ' | b'\t')
+      {
+        out.extend_from_slice(chunk);
+        col += N;
+        i += N;
+        continue;
+      }
+    }
+
+    let b = data[i];
+    if b == b'\r' && data.get(i + 1) == Some(&b'\n') {
+      out.extend_from_slice(b"\r\n");
+      col = 0;
+      i += 2;
+      continue;
+    }
+
+    let literal = match b {
+      b' ' | b'\t' => !matches!(&data[i + 1..], [] | [b'\r', b'\n', ..]),
+      b'=' => false,
+      b => b.is_ascii_graphic(),
+    };
+
+    // Leave room for the `=` of a soft line break.
+    let width = if literal { 1 } else { 3 };
+    if col + width >= MAX_LINE {
+      out.extend_from_slice(b"=\r\n");
+      col = 0;
+    }
+
+    if literal {
+      out.push(b);
+    } else {
+      out.extend_from_slice(&[
+        b'=',
+        HEX[(b >> 4) as usize],
+        HEX[(b & 0xf) as usize],
+      ]);
+    }
+    col += width;
+    i += 1;
+  }
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+  (c as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn known() {
+    assert_eq!(encode(b"a=b"), "a=3Db");
+    assert_eq!(encode(b"trailing \r\nspace "), "trailing=20\r\nspace=20");
+    assert_eq!(encode(b"lone\nnewline"), "lone=0Anewline");
+
+    assert_eq!(decode(b"a=3db").unwrap(), b"a=b");
+    assert_eq!(decode(b"soft=\r\nbreak").unwrap(), b"softbreak");
+    assert_eq!(decode(b"soft= \nbreak=").unwrap(), b"softbreak");
+    assert_eq!(decode(b"padded  \r\nline=20 ").unwrap(), b"padded\r\nline ");
+  }
+
+  #[test]
+  fn roundtrip() {
+    let mut cases = crate::tests::random_tests()
+      .into_iter()
+      .map(|(_, _, dec)| dec)
+      .collect::<Vec<_>>();
+    cases.push(b"The quick brown fox jumps over the lazy dog. ".repeat(10));
+    cases.push(b"line one \r\nline two\t\r\n\r\n".repeat(10));
+
+    for (i, dec) in cases.into_iter().enumerate() {
+      let enc = encode(&dec);
+      for line in enc.split("\r\n") {
+        assert!(line.len() <= MAX_LINE, "case {i}: {line:?}");
+        assert!(!line.ends_with([' ', '\t']), "case {i}: {line:?}");
+      }
+      assert_eq!(decode(enc.as_bytes()).unwrap(), dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn errors() {
+    assert!(decode(b"=").is_ok());
+    assert!(decode(b"=4").is_err());
+    assert!(decode(b"=XY").is_err());
+
+    let mut out = b"keep".to_vec();
+    assert!(decode_to(b"abc=Q", &mut out).is_err());
+    assert_eq!(out, b"keep");
+  }
+}
@@ -0,0 +1,110 @@
+//! Finding base64 inside other text.
+
+use core::iter::FusedIterator;
+use core::ops::Range;
+
+use crate::simd;
+
+/// Finds the runs of standard base64 characters in `haystack` that are at
+/// least `min_len` bytes long, such as encoded blobs in a log file.
+///
+/// Each run is as long as possible, and includes any padding that properly
+/// ends it. Runs are only candidates: they aren't checked for a valid length,
+/// and ordinary words are runs too, which is what `min_len` is for.
+///
+/// ```
+/// let log = b"token=c2VjcmV0IHN0dWZm; user=bob";
+/// let runs = vb64::find_base64(log, 8).collect::<Vec<_>>();
+/// assert_eq!(runs, [6..22]);
+/// assert_eq!(vb64::decode(&log[runs[0].clone()]).unwrap(), b"secret stuff");
+/// ```
+pub fn find_base64(haystack: &[u8], min_len: usize) -> FindBase64<'_> {
+  FindBase64 {
+    haystack,
+    pos: 0,
+    min_len,
+  }
+}
+
+/// The iterator returned by [`find_base64()`].
+#[derive(Clone, Debug)]
+pub struct FindBase64<'a> {
+  haystack: &'a [u8],
+  pos: usize,
+  min_len: usize,
+}
+
+impl Iterator for FindBase64<'_> {
+  type Item = Range<usize>;
+
+  fn next(&mut self) -> Option<Range<usize>> {
+    while self.pos < self.haystack.len() {
+      let rest = &self.haystack[self.pos..];
+      let start = self.pos + find_lane(rest, true)?;
+      let rest = &self.haystack[start..];
+      let len = find_lane(rest, false).unwrap_or(rest.len());
+
+      let pads = match (len % 4, &rest[len..]) {
+        (2, [b'=', b'=', ..]) => 2,
+        (3, [b'=', ..]) => 1,
+        _ => 0,
+      };
+
+      self.pos = start + len + pads;
+      if len + pads >= self.min_len {
+        return Some(start..self.pos);
+      }
+    }
+    self.pos = self.haystack.len();
+    None
+  }
+}
+
+impl FusedIterator for FindBase64<'_> {}
+
+/// Finds the first byte of `data` that is (or, if `valid` is false, isn't) in
+/// the standard alphabet.
+fn find_lane(data: &[u8], valid: bool) -> Option<usize> {
+  const N: usize = 16;
+  for (i, chunk) in data.chunks(N).enumerate() {
+    // The padding is valid, so it never shows up when looking for invalid
+    // bytes; we have to check that we don't stop on it in the other case.
+    let ascii = unsafe { crate::read_slice_padded::<N>(chunk, b'A') };
+    let mut lanes = simd::valid_lanes(ascii, &simd::STANDARD);
+    if !valid {
+      lanes = !lanes;
+    }
+    let bits = lanes.to_bitmask();
+    if bits != 0 {
+      let offset = i * N + bits.trailing_zeros() as usize;
+      return (offset < data.len()).then_some(offset);
+    }
+  }
+
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn find() {
+    let text = b"-- QUJD ab, QUJDRA== QUJDRA=== x QUJDREU=QUJD/+9z";
+    let runs = find_base64(text, 0).collect::<Vec<_>>();
+    assert_eq!(runs, [3..7, 8..10, 12..20, 21..29, 31..32, 33..41, 41..49]);
+    let runs = find_base64(text, 5).collect::<Vec<_>>();
+    assert_eq!(runs, [12..20, 21..29, 33..41, 41..49]);
+
+    // Runs that cross vector boundaries, and ones that end the haystack.
+    let mut text = vec![b'!'; 100];
+    text[10..50].fill(b'Q');
+    text[90..].fill(b'w');
+    let runs = find_base64(&text, 4).collect::<Vec<_>>();
+    assert_eq!(runs, [10..50, 90..100]);
+
+    assert_eq!(find_base64(b"", 0).count(), 0);
+    assert_eq!(find_base64(b"!!!!", 0).count(), 0);
+    assert_eq!(find_base64(b"====", 0).count(), 0);
+  }
+}
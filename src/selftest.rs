@@ -0,0 +1,246 @@
+//! Startup self-test for the SIMD kernels.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The error returned by [`self_test()`], naming the check that failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SelfTestError {
+  check: &'static str,
+}
+
+impl SelfTestError {
+  /// Returns a short description of the check that failed.
+  pub fn check(&self) -> &'static str {
+    self.check
+  }
+}
+
+impl fmt::Display for SelfTestError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "vb64 self-test failed: {}", self.check)
+  }
+}
+
+impl core::error::Error for SelfTestError {}
+
+/// Runs known-answer tests through the SIMD kernels that this build selected,
+/// returning an error if any of them produce the wrong answer.
+///
+/// The kernels are picked at compile time based on the target features, so a
+/// miscompilation, or a CPU that doesn't actually implement those features
+/// correctly, shows up here. This takes around a millisecond, which makes it
+/// cheap enough to call at startup.
+///
+/// ```
+/// vb64::self_test().unwrap();
+/// ```
+pub fn self_test() -> Result<(), SelfTestError> {
+  let check = |ok: bool, check| match ok {
+    true => Ok(()),
+    false => Err(SelfTestError { check }),
+  };
+
+  // RFC 4648 §10.
+  let vectors = [
+    ("", ""),
+    ("f", "Zg=="),
+    ("fo", "Zm8="),
+    ("foo", "Zm9v"),
+    ("foob", "Zm9vYg=="),
+    ("fooba", "Zm9vYmE="),
+    ("foobar", "Zm9vYmFy"),
+  ];
+  for (dec, enc) in vectors {
+    check(crate::encode(dec.as_bytes()) == enc, "base64 encode")?;
+    let decoded = crate::decode(enc.as_bytes()).ok();
+    check(decoded.as_deref() == Some(dec.as_bytes()), "base64 decode")?;
+    check(crate::is_base64(enc.as_bytes()), "base64 validate")?;
+  }
+
+  // Every byte value, at every length and alignment up to a few vectors'
+  // worth, so that both the bulk loops and the tails get exercised.
+  let data = (0..=255).collect::<Vec<u8>>();
+  let mut enc = Vec::new();
+  let mut dec = Vec::new();
+  for len in 0..100 {
+    for start in [0, 1, 7, 100] {
+      let data = &data[start..start + len];
+
+      enc.clear();
+      crate::encode_to(data, &mut enc);
+      check(enc == encode_scalar(data, STANDARD), "base64 encode")?;
+
+      dec.clear();
+      let ok = crate::decode_to(&enc, &mut dec).is_ok();
+      check(ok && dec == data, "base64 decode")?;
+
+      enc.clear();
+      crate::encode_url_nopad_to(data, &mut enc);
+      let mut expected = encode_scalar(data, URL_SAFE);
+      expected.retain(|&b| b != b'=');
+      check(enc == expected, "base64url encode")?;
+
+      dec.clear();
+      let ok = crate::decode_url_nopad_to(&enc, &mut dec).is_ok();
+      check(ok && dec == data, "base64url decode")?;
+    }
+  }
+
+  // Every byte that isn't in the alphabet must be caught, wherever it lands
+  // in a vector.
+  for b in 0..=255u8 {
+    for i in [b as usize % 64, 63 - b as usize % 64] {
+      // A `=` at the very end is padding.
+      let valid = STANDARD.contains(&b) || (b == b'=' && i == 63);
+      let mut data = [b'A'; 64];
+      data[i] = b;
+
+      dec.clear();
+      let res = crate::decode_to(data, &mut dec);
+      check(res.is_ok() == valid, "base64 validation")?;
+      check(
+        valid || res.unwrap_err().offset() == Some(i),
+        "error offset",
+      )?;
+    }
+  }
+
+  // The other SIMD codecs.
+  let hex = crate::hex::encode(&data);
+  check(hex.len() == 512 && hex.starts_with("000102"), "hex encode")?;
+  check(
+    crate::hex::decode(hex.as_bytes()).ok() == Some(data),
+    "hex decode",
+  )?;
+  let base32 = crate::base32::encode(b"foobar");
+  check(base32 == "MZXW6YTBOI======", "base32 encode")?;
+  let decoded = crate::base32::decode(base32.as_bytes()).ok();
+  check(decoded.as_deref() == Some(b"foobar"), "base32 decode")?;
+
+  Ok(())
+}
+
+const STANDARD: &[u8; 64] =
+  b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] =
+  b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A simple scalar encoder to check the SIMD one against.
+pub(crate) fn encode_scalar(data: &[u8], alphabet: &[u8; 64]) -> Vec<u8> {
+  let mut out = Vec::new();
+  for chunk in data.chunks(3) {
+    let mut buf = [0; 3];
+    buf[..chunk.len()].copy_from_slice(chunk);
+    let bits = u32::from_be_bytes([0, buf[0], buf[1], buf[2]]);
+    for i in 0..4 {
+      if i <= chunk.len() {
+        out.push(alphabet[(bits >> (18 - 6 * i) & 63) as usize]);
+      } else {
+        out.push(b'=');
+      }
+    }
+  }
+  out
+}
+
+/// A simple scalar decoder to check the SIMD one against, with the same rules
+/// as [`crate::decode()`]: padding is optional, but if present must round the
+/// input up to a multiple of four, and unused trailing bits are ignored.
+#[cfg(test)]
+fn decode_scalar(data: &[u8], alphabet: &[u8; 64]) -> Option<Vec<u8>> {
+  let unpadded = match data {
+    [p @ .., b'=', b'='] | [p @ .., b'='] => {
+      if data.len() % 4 != 0 {
+        return None;
+      }
+      p
+    }
+    p => p,
+  };
+  if unpadded.len() % 4 == 1 {
+    return None;
+  }
+
+  let mut out = Vec::new();
+  let (mut acc, mut bits) = (0u32, 0);
+  for &c in unpadded {
+    let sextet = alphabet.iter().position(|&a| a == c)?;
+    acc = acc << 6 | sextet as u32;
+    bits += 6;
+    if bits >= 8 {
+      bits -= 8;
+      out.push((acc >> bits) as u8);
+    }
+  }
+  Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use proptest::prelude::*;
+
+  use super::*;
+
+  /// Lengths on either side of the boundaries between whole vectors of input
+  /// and output, where the tail handling kicks in.
+  const BOUNDARIES: &[usize] = &[
+    0, 1, 2, 3, 4, 11, 12, 13, 15, 16, 17, 23, 24, 25, 31, 32, 33, 35, 36, 37,
+    47, 48, 49, 63, 64, 65, 95, 96, 97, 127, 128, 129,
+  ];
+
+  fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+      prop::sample::select(BOUNDARIES)
+        .prop_flat_map(|len| prop::collection::vec(any::<u8>(), len)),
+      prop::collection::vec(any::<u8>(), 0..1024),
+    ]
+  }
+
+  /// Base64-ish text: mostly alphabet characters, with some padding and junk
+  /// thrown in.
+  fn text() -> impl Strategy<Value = Vec<u8>> {
+    let byte = prop_oneof![
+      8 => prop::sample::select(&STANDARD[..]),
+      1 => Just(b'='),
+      1 => any::<u8>(),
+    ];
+    prop::sample::select(BOUNDARIES)
+      .prop_flat_map(move |len| prop::collection::vec(byte.clone(), len))
+  }
+
+  proptest! {
+    #[test]
+    fn roundtrip(data in bytes()) {
+      let enc = crate::encode(&data);
+      prop_assert_eq!(enc.as_bytes(), encode_scalar(&data, STANDARD));
+      prop_assert_eq!(crate::decode(enc.as_bytes()).unwrap(), &data[..]);
+
+      let enc = crate::encode_url_nopad(&data);
+      let mut expected = encode_scalar(&data, URL_SAFE);
+      expected.retain(|&b| b != b'=');
+      prop_assert_eq!(enc.as_bytes(), expected);
+      let dec = crate::decode_url_nopad(enc.as_bytes()).unwrap();
+      prop_assert_eq!(dec, &data[..]);
+    }
+
+    #[test]
+    fn chunked(data in bytes(), chunk in prop::sample::select(BOUNDARIES)) {
+      // Encoding in chunks of whole groups is the same as encoding all at
+      // once.
+      let chunk = (chunk / 3 * 3).max(3);
+      let mut enc = Vec::new();
+      for piece in data.chunks(chunk) {
+        crate::encode_to(piece, &mut enc);
+      }
+      prop_assert_eq!(enc, crate::encode(&data).into_bytes());
+    }
+
+    #[test]
+    fn decode(text in text()) {
+      let expected = decode_scalar(&text, STANDARD);
+      prop_assert_eq!(crate::decode(&text).ok(), expected.clone());
+      prop_assert_eq!(crate::is_base64(&text), expected.is_some());
+    }
+  }
+}
@@ -0,0 +1,415 @@
+//! Serializing byte strings as base64 with `serde`.
+//!
+//! Requires the `serde` feature. Use this module with `#[serde(with)]` on
+//! fields that hold bytes, like `Vec<u8>` or `[u8; N]`:
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! struct Key {
+//!   #[serde(with = "vb64::serde")]
+//!   bytes: Vec<u8>,
+//! }
+//!
+//! let key = Key { bytes: b"hello".to_vec() };
+//! let json = serde_json::to_string(&key).unwrap();
+//! assert_eq!(json, r#"{"bytes":"aGVsbG8="}"#);
+//!
+//! let key: Key = serde_json::from_str(&json).unwrap();
+//! assert_eq!(key.bytes, b"hello");
+//! ```
+//!
+//! Formats that aren't human-readable, like bincode, have their own way to
+//! store bytes, so this uses that instead of base64.
+//!
+//! The [`option`] and [`vec`] submodules handle `Option<Vec<u8>>` and
+//! `Vec<Vec<u8>>` fields, and the other alphabets each have a module with the
+//! same layout:
+//!
+//! ```
+//! # use serde_derive::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! struct Token {
+//!   #[serde(with = "vb64::serde::urlsafe_nopad")]
+//!   sig: [u8; 4],
+//!   #[serde(with = "vb64::serde::option")]
+//!   nonce: Option<Vec<u8>>,
+//!   #[serde(with = "vb64::serde::vec")]
+//!   certs: Vec<Vec<u8>>,
+//! }
+//!
+//! let token = Token {
+//!   sig: [0xfb, 0xff, 0xbf, 0x00],
+//!   nonce: None,
+//!   certs: vec![b"hi".to_vec(), b"yo".to_vec()],
+//! };
+//! let json = serde_json::to_string(&token).unwrap();
+//! let want = r#"{"sig":"-_-_AA","nonce":null,"certs":["aGk=","eW8="]}"#;
+//! assert_eq!(json, want);
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use ::serde::de;
+use ::serde::Deserialize;
+use ::serde::Deserializer;
+use ::serde::Serialize;
+use ::serde::Serializer;
+
+use crate::Engine;
+
+/// Generates `serialize()` and `deserialize()` for `$flavor`, along with
+/// `option` and `vec` modules for fields that hold more than one value.
+macro_rules! codec {
+  ($flavor:ty, $desc:literal) => {
+    #[doc = concat!("Serializes `bytes` as ", $desc, " base64.")]
+    pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      T: AsRef<[u8]> + ?Sized,
+      S: ::serde::Serializer,
+    {
+      crate::serde::serialize_one::<$flavor, _, _>(bytes, serializer)
+    }
+
+    #[doc = concat!("Deserializes ", $desc, " base64 into any type that")]
+    /// can be built from a `Vec<u8>`, including fixed-size arrays.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+      T: TryFrom<Vec<u8>>,
+      D: ::serde::Deserializer<'de>,
+    {
+      crate::serde::deserialize_one::<$flavor, _, _>(deserializer)
+    }
+
+    /// For `Option` fields, such as `Option<Vec<u8>>`.
+    pub mod option {
+      #[doc = concat!("Serializes `bytes`, if present, as ", $desc, " base64.")]
+      pub fn serialize<T, S>(
+        bytes: &Option<T>,
+        serializer: S,
+      ) -> Result<S::Ok, S::Error>
+      where
+        T: AsRef<[u8]>,
+        S: ::serde::Serializer,
+      {
+        crate::serde::serialize_option::<$flavor, _, _>(bytes, serializer)
+      }
+
+      #[doc = concat!("Deserializes ", $desc, " base64, if present.")]
+      pub fn deserialize<'de, T, D>(
+        deserializer: D,
+      ) -> Result<Option<T>, D::Error>
+      where
+        T: TryFrom<Vec<u8>>,
+        D: ::serde::Deserializer<'de>,
+      {
+        crate::serde::deserialize_option::<$flavor, _, _>(deserializer)
+      }
+    }
+
+    /// For sequences of byte strings, such as `Vec<Vec<u8>>`.
+    pub mod vec {
+      #[doc = concat!("Serializes each of `items` as ", $desc, " base64.")]
+      pub fn serialize<T, S>(
+        items: &[T],
+        serializer: S,
+      ) -> Result<S::Ok, S::Error>
+      where
+        T: AsRef<[u8]>,
+        S: ::serde::Serializer,
+      {
+        crate::serde::serialize_vec::<$flavor, _, _>(items, serializer)
+      }
+
+      #[doc = concat!("Deserializes a sequence of ", $desc, " base64 strings.")]
+      pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+      where
+        T: TryFrom<Vec<u8>>,
+        D: ::serde::Deserializer<'de>,
+      {
+        crate::serde::deserialize_vec::<$flavor, _, _>(deserializer)
+      }
+    }
+  };
+}
+
+codec!(crate::serde::Standard, "standard, padded");
+
+/// Like [`vb64::serde`][crate::serde], but for the standard alphabet without
+/// padding.
+pub mod standard_nopad {
+  codec!(crate::serde::StandardNoPad, "standard, unpadded");
+}
+
+/// Like [`vb64::serde`][crate::serde], but for the URL-safe alphabet.
+pub mod urlsafe {
+  codec!(crate::serde::UrlSafe, "URL-safe, padded");
+}
+
+/// Like [`vb64::serde`][crate::serde], but for the URL-safe alphabet without
+/// padding, as used by JWTs.
+pub mod urlsafe_nopad {
+  codec!(crate::serde::UrlSafeNoPad, "URL-safe, unpadded");
+}
+
+/// Which engine one of the modules above uses.
+trait Flavor {
+  const ENGINE: Engine;
+}
+
+enum Standard {}
+enum StandardNoPad {}
+enum UrlSafe {}
+enum UrlSafeNoPad {}
+
+impl Flavor for Standard {
+  const ENGINE: Engine = Engine::STANDARD;
+}
+impl Flavor for StandardNoPad {
+  const ENGINE: Engine = Engine::STANDARD_NOPAD;
+}
+impl Flavor for UrlSafe {
+  const ENGINE: Engine = Engine::URL_SAFE;
+}
+impl Flavor for UrlSafeNoPad {
+  const ENGINE: Engine = Engine::URL_SAFE_NOPAD;
+}
+
+/// A byte string that (de)serializes as `F`'s flavor of base64, so that we
+/// can lean on `serde`'s own impls for containers.
+struct Encoded<F, T>(T, PhantomData<F>);
+
+impl<F: Flavor, T: AsRef<[u8]> + ?Sized> Serialize for Encoded<F, &T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_with(&F::ENGINE, self.0.as_ref(), serializer)
+  }
+}
+
+impl<'de, F: Flavor, T: TryFrom<Vec<u8>>> Deserialize<'de> for Encoded<F, T> {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    deserialize_with(&F::ENGINE, deserializer).map(|t| Encoded(t, PhantomData))
+  }
+}
+
+fn serialize_one<F, T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+  F: Flavor,
+  T: AsRef<[u8]> + ?Sized,
+  S: Serializer,
+{
+  serialize_with(&F::ENGINE, bytes.as_ref(), serializer)
+}
+
+fn deserialize_one<'de, F, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+  F: Flavor,
+  T: TryFrom<Vec<u8>>,
+  D: Deserializer<'de>,
+{
+  deserialize_with(&F::ENGINE, deserializer)
+}
+
+fn serialize_option<F, T, S>(
+  bytes: &Option<T>,
+  serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+  F: Flavor,
+  T: AsRef<[u8]>,
+  S: Serializer,
+{
+  let bytes = bytes.as_ref().map(|b| Encoded::<F, _>(b, PhantomData));
+  bytes.serialize(serializer)
+}
+
+fn deserialize_option<'de, F, T, D>(
+  deserializer: D,
+) -> Result<Option<T>, D::Error>
+where
+  F: Flavor,
+  T: TryFrom<Vec<u8>>,
+  D: Deserializer<'de>,
+{
+  let bytes = Option::<Encoded<F, T>>::deserialize(deserializer)?;
+  Ok(bytes.map(|e| e.0))
+}
+
+fn serialize_vec<F, T, S>(items: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+  F: Flavor,
+  T: AsRef<[u8]>,
+  S: Serializer,
+{
+  serializer.collect_seq(items.iter().map(|b| Encoded::<F, _>(b, PhantomData)))
+}
+
+fn deserialize_vec<'de, F, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+  F: Flavor,
+  T: TryFrom<Vec<u8>>,
+  D: Deserializer<'de>,
+{
+  let items = Vec::<Encoded<F, T>>::deserialize(deserializer)?;
+  Ok(items.into_iter().map(|e| e.0).collect())
+}
+
+fn serialize_with<S: Serializer>(
+  engine: &Engine,
+  bytes: &[u8],
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  if serializer.is_human_readable() {
+    serializer.serialize_str(&engine.encode(bytes))
+  } else {
+    serializer.serialize_bytes(bytes)
+  }
+}
+
+fn deserialize_with<'de, T, D>(
+  engine: &Engine,
+  deserializer: D,
+) -> Result<T, D::Error>
+where
+  T: TryFrom<Vec<u8>>,
+  D: Deserializer<'de>,
+{
+  let visitor = Visitor {
+    engine,
+    _ph: PhantomData,
+  };
+  if deserializer.is_human_readable() {
+    deserializer.deserialize_str(visitor)
+  } else {
+    deserializer.deserialize_byte_buf(visitor)
+  }
+}
+
+struct Visitor<'a, T> {
+  engine: &'a Engine,
+  _ph: PhantomData<fn() -> T>,
+}
+
+impl<T: TryFrom<Vec<u8>>> Visitor<'_, T> {
+  fn build<E: de::Error>(&self, bytes: Vec<u8>) -> Result<T, E> {
+    let len = bytes.len();
+    T::try_from(bytes).map_err(|_| E::invalid_length(len, self))
+  }
+}
+
+impl<'de, T: TryFrom<Vec<u8>>> de::Visitor<'de> for Visitor<'_, T> {
+  type Value = T;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("base64-encoded bytes")
+  }
+
+  fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+    let bytes = self.engine.decode(s).map_err(E::custom)?;
+    self.build(bytes)
+  }
+
+  fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<T, E> {
+    self.build(bytes.to_vec())
+  }
+
+  fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<T, E> {
+    self.build(bytes)
+  }
+
+  fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+    let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+    while let Some(byte) = seq.next_element()? {
+      bytes.push(byte);
+    }
+    self.build(bytes)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_derive::Deserialize;
+  use serde_derive::Serialize;
+  use serde_test::Configure;
+  use serde_test::Token;
+
+  #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+  struct Data {
+    #[serde(with = "crate::serde")]
+    vec: Vec<u8>,
+    #[serde(with = "crate::serde")]
+    array: [u8; 4],
+  }
+
+  fn tokens(vec: Token, array: Token) -> [Token; 6] {
+    let name = "Data";
+    [
+      Token::Struct { name, len: 2 },
+      Token::Str("vec"),
+      vec,
+      Token::Str("array"),
+      array,
+      Token::StructEnd,
+    ]
+  }
+
+  #[test]
+  fn data() {
+    let data = Data {
+      vec: b"hello".to_vec(),
+      array: *b"ABCD",
+    };
+    let readable = tokens(Token::Str("aGVsbG8="), Token::Str("QUJDRA=="));
+    serde_test::assert_tokens(&data.clone().readable(), &readable);
+    let compact = tokens(Token::Bytes(b"hello"), Token::Bytes(b"ABCD"));
+    serde_test::assert_tokens(&data.compact(), &compact);
+
+    serde_test::assert_de_tokens_error::<serde_test::Readable<Data>>(
+      &tokens(Token::Str("aGVs!G8="), Token::Str("QUJDRA=="))[..3],
+      "invalid byte 0x21 at offset 4",
+    );
+    serde_test::assert_de_tokens_error::<serde_test::Readable<Data>>(
+      &tokens(Token::Str(""), Token::Str("QUJD"))[..5],
+      "invalid length 3, expected base64-encoded bytes",
+    );
+  }
+
+  #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+  struct Nested {
+    #[serde(with = "crate::serde::urlsafe_nopad::option")]
+    some: Option<Vec<u8>>,
+    #[serde(with = "crate::serde::option")]
+    none: Option<[u8; 2]>,
+    #[serde(with = "crate::serde::standard_nopad::vec")]
+    vec: Vec<Vec<u8>>,
+  }
+
+  #[test]
+  fn nested() {
+    let data = Nested {
+      some: Some(vec![0xfb, 0xff]),
+      none: None,
+      vec: vec![b"a".to_vec(), b"bc".to_vec()],
+    };
+    let name = "Nested";
+    serde_test::assert_tokens(
+      &data.readable(),
+      &[
+        Token::Struct { name, len: 3 },
+        Token::Str("some"),
+        Token::Some,
+        Token::Str("-_8"),
+        Token::Str("none"),
+        Token::None,
+        Token::Str("vec"),
+        Token::Seq { len: Some(2) },
+        Token::Str("YQ"),
+        Token::Str("YmM"),
+        Token::SeqEnd,
+        Token::StructEnd,
+      ],
+    );
+  }
+}
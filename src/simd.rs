@@ -1,19 +1,460 @@
 //! Core SIMD implementation.
 
 use core::fmt;
-use std::simd::prelude::*;
-use std::simd::LaneCount;
-use std::simd::SimdElement;
-use std::simd::SupportedLaneCount;
+use core::simd::prelude::*;
+use core::simd::LaneCount;
+use core::simd::SimdElement;
+use core::simd::SupportedLaneCount;
 
 use crate::util::invert_index;
 use crate::util::tiled;
 
+/// Lookup tables that specialize the kernels below to a particular alphabet.
+///
+/// The standard and URL-safe alphabets only differ in their last two
+/// characters, so they can share the same perfect hashes, provided that we
+/// adjust the offset tables and the range check.
+#[derive(Copy, Clone, Debug)]
+pub struct Luts {
+  /// The character that decodes to zero, used to pad out partial chunks.
+  pub zero: u8,
+  /// Pairs of characters `(from, to)`, such that `from` is decoded as if it
+  /// were `to`. This is applied before `decode`.
+  pub aliases: &'static [(u8, u8)],
+  /// Tables for converting ASCII into sextets.
+  pub decode: DecodeLut,
+  /// Tables for converting sextets back into ASCII.
+  pub encode: EncodeLut,
+}
+
+/// See [`decode()`].
+#[derive(Copy, Clone, Debug)]
+pub enum DecodeLut {
+  Hashed {
+    /// The character that the perfect hash moves into bucket 1, since its
+    /// high nybble is shared with characters that need a different offset.
+    special: u8,
+    /// Offsets added to an ASCII byte to get a sextet, indexed by perfect
+    /// hash.
+    offsets: [u8; 8],
+    /// Range-check tables, indexed by low and high nybble respectively.
+    valid_lo: [u8; 16],
+    valid_hi: [u8; 16],
+  },
+  /// Runs of consecutive characters that map to consecutive sextets, for
+  /// alphabets that don't fit the perfect hash. Each run is a triple of its
+  /// first character, its length, and the offset added to get a sextet.
+  Ranges {
+    ranges: [(u8, u8, u8); 64],
+    len: usize,
+  },
+}
+
+/// See [`encode()`].
+#[derive(Copy, Clone, Debug)]
+pub enum EncodeLut {
+  /// Offsets subtracted from a sextet to get an ASCII byte, indexed by the
+  /// encoder's perfect hash.
+  Hashed([u8; 8]),
+  /// The whole alphabet, split into four rows of sixteen characters, for
+  /// alphabets that don't fit the perfect hash.
+  Table([[u8; 16]; 4]),
+}
+
+/// Tables for the standard alphabet, `A-Za-z0-9+/`.
+pub const STANDARD: Luts = Luts {
+  zero: b'A',
+  aliases: &[],
+  decode: DecodeLut::Hashed {
+    special: b'/',
+    offsets: [!0, 16, 19, 4, 191, 191, 185, 185],
+    valid_lo: [
+      0b10101, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001,
+      0b10001, 0b10001, 0b10011, 0b11010, 0b11011, 0b11011, 0b11011, 0b11010,
+    ],
+    valid_hi: [
+      0b10000, 0b10000, 0b00001, 0b00010, 0b00100, 0b01000, 0b00100, 0b01000,
+      0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000,
+    ],
+  },
+  encode: EncodeLut::Hashed([191, 185, 185, 4, 4, 19, 16, !0]),
+};
+
+/// Tables for the URL-safe alphabet, `A-Za-z0-9-_`.
+///
+/// Unlike `/`, `_` lives in the same row as `P..=Z`, so the 0x7_ row needs its
+/// own bit in the range check.
+pub const URL_SAFE: Luts = Luts {
+  zero: b'A',
+  aliases: &[],
+  decode: DecodeLut::Hashed {
+    special: b'_',
+    offsets: [!0, 224, 17, 4, 191, 191, 185, 185],
+    valid_lo: [
+      0b010101, 0b010001, 0b010001, 0b010001, 0b010001, 0b010001, 0b010001,
+      0b010001, 0b010001, 0b010001, 0b010011, 0b111011, 0b111011, 0b111010,
+      0b111011, 0b110011,
+    ],
+    valid_hi: [
+      0b010000, 0b010000, 0b000001, 0b000010, 0b000100, 0b001000, 0b000100,
+      0b100000, 0b010000, 0b010000, 0b010000, 0b010000, 0b010000, 0b010000,
+      0b010000, 0b010000,
+    ],
+  },
+  encode: EncodeLut::Hashed([191, 185, 185, 4, 4, 17, 224, !0]),
+};
+
+/// Tables for decoding either of the standard and URL-safe alphabets, even
+/// when mixed together. Encoding produces the standard alphabet.
+pub const MIXED: Luts = Luts {
+  aliases: &[(b'-', b'+'), (b'_', b'/')],
+  ..STANDARD
+};
+
+/// Tables for the base32 alphabet, `A-Z2-7`.
+pub const BASE32: Luts = build(b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567", &[]);
+
+/// Tables for the lowercase base32 alphabet, `a-z2-7`, as used by multibase.
+pub const BASE32_LOWER: Luts = build(b"abcdefghijklmnopqrstuvwxyz234567", &[]);
+
+/// Tables for the base32hex alphabet, `0-9A-V`.
+pub const BASE32_HEX: Luts = build(b"0123456789ABCDEFGHIJKLMNOPQRSTUV", &[]);
+
+/// Tables for the lowercase base32hex alphabet, `0-9a-v`.
+pub const BASE32_HEX_LOWER: Luts =
+  build(b"0123456789abcdefghijklmnopqrstuv", &[]);
+
+/// Tables for Crockford's base32 alphabet, `0-9A-Z` without `ILOU`.
+///
+/// Decoding is case-insensitive, and accepts `O` as `0`, and `I` and `L` as
+/// `1`.
+pub const CROCKFORD: Luts = {
+  const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+  const ALIASES: [(u8, u8); 6] = [
+    (b'O', 0),
+    (b'o', 0),
+    (b'I', 1),
+    (b'i', 1),
+    (b'L', 1),
+    (b'l', 1),
+  ];
+  const EXTRAS: [(u8, u8); 28] = array!(28; |i| {
+    if i < 22 {
+      (ALPHABET[10 + i].to_ascii_lowercase(), 10 + i as u8)
+    } else {
+      ALIASES[i - 22]
+    }
+  });
+
+  build(ALPHABET, &EXTRAS)
+};
+
+/// Tables for lowercase hex. Decoding also accepts uppercase.
+pub const HEX: Luts = build(
+  b"0123456789abcdef",
+  &[
+    (b'A', 10),
+    (b'B', 11),
+    (b'C', 12),
+    (b'D', 13),
+    (b'E', 14),
+    (b'F', 15),
+  ],
+);
+
+/// Tables for uppercase hex. Decoding also accepts lowercase.
+pub const HEX_UPPER: Luts = build(
+  b"0123456789ABCDEF",
+  &[
+    (b'a', 10),
+    (b'b', 11),
+    (b'c', 12),
+    (b'd', 13),
+    (b'e', 14),
+    (b'f', 15),
+  ],
+);
+
+/// Tables for uuencode, which maps each sextet `n` to the character `n + 32`,
+/// except that it uses `` ` `` rather than space for zero.
+///
+/// Decoding accepts either; space is handled as an alias, since it is not
+/// printable.
+pub const UU: Luts = Luts {
+  aliases: &[(b' ', b'`')],
+  ..build(
+    b"`!\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_",
+    &[],
+  )
+};
+
+/// Like [`Luts::with_extras()`], but for alphabets known to be valid.
+const fn build(alphabet: &[u8], extras: &[(u8, u8)]) -> Luts {
+  match Luts::with_extras(alphabet, extras) {
+    Some(luts) => luts,
+    None => panic!("invalid alphabet"),
+  }
+}
+
+impl Luts {
+  /// Builds tables for an arbitrary alphabet.
+  ///
+  /// This is a `const fn`, so that tables for alphabets known at compile time
+  /// can be baked into the binary, just like the built-in ones.
+  ///
+  /// Returns `None` if `alphabet` is not made up of 64 distinct printable
+  /// ASCII characters other than `=`.
+  pub const fn new(alphabet: &[u8; 64]) -> Option<Luts> {
+    let mut i = 0;
+    while i < 64 {
+      if alphabet[i] == b'=' {
+        return None;
+      }
+      i += 1;
+    }
+
+    Self::with_extras(alphabet, &[])
+  }
+
+  /// Builds tables for an alphabet with at most 64 characters, such as a
+  /// base32 one.
+  ///
+  /// `extras` are additional `(character, digit)` pairs that are accepted when
+  /// decoding, but never produced when encoding. Returns `None` if the
+  /// characters are not distinct and printable, or if a digit is out of range.
+  ///
+  /// Unlike [`Luts::new()`], this allows `=`, since not every encoding uses it
+  /// for padding.
+  pub const fn with_extras(
+    alphabet: &[u8],
+    extras: &[(u8, u8)],
+  ) -> Option<Luts> {
+    if alphabet.is_empty() || alphabet.len() > 64 {
+      return None;
+    }
+
+    let mut sextets = [!0u8; 128];
+    let mut i = 0;
+    while i < alphabet.len() + extras.len() {
+      let (c, sextet) = if i < alphabet.len() {
+        (alphabet[i], i as u8)
+      } else {
+        extras[i - alphabet.len()]
+      };
+      if !c.is_ascii_graphic()
+        || sextets[c as usize] != !0
+        || sextet as usize >= alphabet.len()
+      {
+        return None;
+      }
+      sextets[c as usize] = sextet;
+      i += 1;
+    }
+
+    Some(Luts {
+      zero: alphabet[0],
+      aliases: &[],
+      decode: match Self::decode_hashed(&sextets) {
+        Some(decode) => decode,
+        None => Self::decode_ranges(&sextets),
+      },
+      encode: Self::encode(alphabet),
+    })
+  }
+
+  /// Tries to fit the perfect hash to an alphabet, given as a map from ASCII
+  /// to sextets.
+  const fn decode_hashed(sextets: &[u8; 128]) -> Option<DecodeLut> {
+    // Every character in a row of the ASCII table needs to share an offset,
+    // except for at most one, which becomes the special character. If there
+    // isn't one, we pick a control character, which the hash leaves alone.
+    let mut special = 0x10;
+    let mut offsets = [!0; 8];
+    let mut row = 2;
+    while row < 8 {
+      // The first two distinct offsets in this row, how many characters use
+      // them, and the last character to use them.
+      let mut seen = [(0u8, 0usize, 0u8); 2];
+      let mut c = row * 16;
+      while c < row * 16 + 16 {
+        let sextet = sextets[c];
+        let offset = sextet.wrapping_sub(c as u8);
+        if sextet == !0 {
+          // Not in the alphabet.
+        } else if seen[0].1 == 0 || seen[0].0 == offset {
+          seen[0] = (offset, seen[0].1 + 1, c as u8);
+        } else if seen[1].1 == 0 || seen[1].0 == offset {
+          seen[1] = (offset, seen[1].1 + 1, c as u8);
+        } else {
+          return None;
+        }
+        c += 1;
+      }
+
+      let (common, odd) = match seen {
+        [a, (_, 0, _)] => (a, None),
+        [a, b] if b.1 == 1 => (a, Some(b)),
+        [a, b] if a.1 == 1 => (b, Some(a)),
+        _ => return None,
+      };
+
+      offsets[row] = common.0;
+      if let Some((offset, _, c)) = odd {
+        if special != 0x10 {
+          return None;
+        }
+        special = c;
+        offsets[1] = offset;
+      }
+      row += 1;
+    }
+
+    // Each row gets its own bit in the range check; bit 6 marks rows that are
+    // entirely invalid.
+    let mut valid_lo = [0b1000000; 16];
+    let mut valid_hi = [0b1000000; 16];
+    let mut row = 2;
+    while row < 8 {
+      valid_hi[row] = 1 << (row - 2);
+      let mut lo = 0;
+      while lo < 16 {
+        if sextets[row * 16 + lo] == !0 {
+          valid_lo[lo] |= 1 << (row - 2);
+        }
+        lo += 1;
+      }
+      row += 1;
+    }
+
+    Some(DecodeLut::Hashed {
+      special,
+      offsets,
+      valid_lo,
+      valid_hi,
+    })
+  }
+
+  /// Splits an alphabet, given as a map from ASCII to sextets, into runs.
+  const fn decode_ranges(sextets: &[u8; 128]) -> DecodeLut {
+    let mut ranges = [(0, 0, 0); 64];
+    let mut len = 0;
+    let mut c = 0;
+    while c < 128 {
+      let sextet = sextets[c];
+      if sextet == !0 {
+        c += 1;
+        continue;
+      }
+
+      let mut end = c + 1;
+      while end < 128 && sextets[end] == sextet.wrapping_add((end - c) as u8) {
+        end += 1;
+      }
+
+      ranges[len] = (c as u8, (end - c) as u8, sextet.wrapping_sub(c as u8));
+      len += 1;
+      c = end;
+    }
+
+    DecodeLut::Ranges { ranges, len }
+  }
+
+  /// Builds the encoding tables for an alphabet.
+  const fn encode(alphabet: &[u8]) -> EncodeLut {
+    // Try to fit the alphabet to the encoder's perfect hash; this only works
+    // for alphabets that are laid out like the standard one.
+    let mut offsets = [!0u8; 8];
+    let mut table = [[0; 16]; 4];
+    let mut hashed = true;
+    let mut sextet = 0;
+    while sextet < alphabet.len() {
+      let c = alphabet[sextet];
+      let hash = encode_hash(sextet as u8) as usize;
+      let offset = (sextet as u8).wrapping_sub(c);
+      if offsets[hash] == !0 {
+        offsets[hash] = offset;
+      } else if offsets[hash] != offset {
+        hashed = false;
+      }
+
+      table[sextet / 16][sextet % 16] = c;
+      sextet += 1;
+    }
+
+    if hashed {
+      EncodeLut::Hashed(offsets)
+    } else {
+      EncodeLut::Table(table)
+    }
+  }
+}
+
 /// Decodes `ascii` as base64. Returns the results of the decoding in the low
 /// 3/4 of the returned vector, as well as whether decoding completed
 /// successfully.
 #[inline]
-pub fn decode<const N: usize>(ascii: Simd<u8, N>) -> (Simd<u8, N>, bool)
+pub fn decode<const N: usize>(
+  ascii: Simd<u8, N>,
+  luts: &Luts,
+) -> (Simd<u8, N>, bool)
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let (sextets, valid) = to_sextets(ascii, luts);
+
+  // Now we need to shift everything a little bit, since each byte has two high
+  // bits it shouldn't that we need to delete. One thing we can do is to split
+  // the vector into two alternating vectors, convert them to vectors of u16,
+  // shift each lane by a specified amount, and then shuffle-OR them back
+  // together. I learned this trick from Danila Kutenin.
+  //
+  // What we're basically going to do is the following. Below letters represent
+  // the decoded message and dots are extraneous zeros. (Bits below are ordered
+  // little-endian.)
+  //
+  // start:
+  //  aaaaaa.. bbbbbb.. cccccc.. dddddd.. eeeeee.. ffffff.. gggggg.. hhhhhh..
+  //
+  // zext to u16:
+  //  aaaaaa.......... bbbbbb.......... cccccc.......... dddddd..........
+  //  eeeeee.......... ffffff.......... gggggg.......... hhhhhh..........
+  //
+  // u16 shift:
+  //  ..aaaaaa|........ ....bbbb|bb...... ......cc|cccc.... ........|dddddd..
+  //  ..eeeeee|........ ....ffff|ff...... ......gg|gggg.... ........|hhhhhh..
+  //
+  // u16 d3einterleave:
+  //  ..aaaaaa ....bbbb ......cc ........ ..eeeeee ....ffff ......gg ........
+  //  ........ bb...... cccc.... dddddd.. ........ ff...... gggg.... hhhhhh..
+  //
+  // u8 rotate:
+  //  ..aaaaaa ....bbbb ......cc ........ ..eeeeee ....ffff ......gg ........
+  //  bb...... cccc.... dddddd.. ........ ff...... gggg.... hhhhhh.. ........
+  //
+  // u8 or:
+  //  bbaaaaaa ccccbbbb ddddddcc ........ ffeeeeee ggggffff hhhhhhgg ........
+  //
+  // u8 shuffle:
+  //  bbaaaaaa ccccbbbb ddddddcc ffeeeeee ggggffff hhhhhhgg ........ ........
+
+  let shifted = sextets.cast::<u16>() << tiled(&[2, 4, 6, 8]);
+
+  let lo = shifted.cast::<u8>();
+  let hi = (shifted >> Simd::splat(8)).cast::<u8>();
+  let decoded_chunks = lo | hi.rotate_lanes_left::<1>();
+
+  let output = swizzle!(N; decoded_chunks, array!(N; |i| i + i / 3));
+
+  (output, valid)
+}
+
+/// Converts each lane of `ascii` into the sextet it represents. Also returns
+/// whether every lane was in the alphabet.
+#[inline]
+pub fn to_sextets<const N: usize>(
+  ascii: Simd<u8, N>,
+  luts: &Luts,
+) -> (Simd<u8, N>, bool)
 where
   LaneCount<N>: SupportedLaneCount,
 {
@@ -32,7 +473,7 @@ where
   // to the corresponding value we need to offset `ascii` by.
 
   /*
-  use std::ops::RangeInclusive;
+  use core::ops::RangeInclusive;
   let in_range = |bytes: Simd<u8, N>, range: RangeInclusive<u8>| {
     bytes.simd_ge(Simd::splat(*range.start()))
       & bytes.simd_le(Simd::splat(*range.end()))
@@ -72,81 +513,106 @@ where
   //
   // This perfect hash function is described at
   // https://github.com/WojciechMula/base64simd/issues/3.
+  //
+  // For the URL-safe alphabet, the same trick works, except that `_` is in
+  // the 0x5_ row, so we need to subtract 4 to move it into bucket 1.
+  //
+  // Custom alphabets that cannot be fit to this hash fall back to the
+  // comparison-based approach above.
+  //
+  // Alphabets with aliases (such as one that accepts both `+/` and `-_`) are
+  // handled by rewriting the aliases into their canonical forms first, which
+  // makes them valid as far as the range check is concerned.
 
-  let hashes = (ascii >> Simd::splat(4))
-    + Simd::simd_eq(ascii, Simd::splat(b'/'))
-      .to_int()
-      .cast::<u8>();
+  let mut ascii = ascii;
+  for &(from, to) in luts.aliases {
+    ascii = ascii
+      .simd_eq(Simd::splat(from))
+      .select(Simd::splat(to), ascii);
+  }
 
-  let sextets =
-    ascii + tiled(&[!0, 16, 19, 4, 191, 191, 185, 185]).swizzle_dyn(hashes);
+  match &luts.decode {
+    DecodeLut::Hashed {
+      special,
+      offsets,
+      valid_lo,
+      valid_hi,
+    } => {
+      let hashes = (ascii >> Simd::splat(4))
+        - mask_splat(ascii.simd_eq(Simd::splat(*special)), (special >> 4) - 1);
 
-  // We also need to do a range check to reject invalid characters.
+      let sextets = ascii + tiled(offsets).swizzle_dyn(hashes);
 
-  const LO_LUT: Simd<u8, 16> = Simd::from_array([
-    0b10101, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001,
-    0b10001, 0b10001, 0b10011, 0b11010, 0b11011, 0b11011, 0b11011, 0b11010,
-  ]);
+      // We also need to do a range check to reject invalid characters.
 
-  const HI_LUT: Simd<u8, 16> = Simd::from_array([
-    0b10000, 0b10000, 0b00001, 0b00010, 0b00100, 0b01000, 0b00100, 0b01000,
-    0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000,
-  ]);
+      let lo_lut = Simd::from_array(*valid_lo);
+      let hi_lut = Simd::from_array(*valid_hi);
 
-  let lo = swizzle::<16, N>(LO_LUT, ascii & Simd::splat(0x0f));
-  let hi = swizzle::<16, N>(HI_LUT, ascii >> Simd::splat(4));
-  let valid = (lo & hi).reduce_or() == 0;
+      let lo = swizzle::<16, N>(lo_lut, ascii & Simd::splat(0x0f));
+      let hi = swizzle::<16, N>(hi_lut, ascii >> Simd::splat(4));
+      let valid = (lo & hi).reduce_or() == 0;
 
-  // Now we need to shift everything a little bit, since each byte has two high
-  // bits it shouldn't that we need to delete. One thing we can do is to split
-  // the vector into two alternating vectors, convert them to vectors of u16,
-  // shift each lane by a specified amount, and then shuffle-OR them back
-  // together. I learned this trick from Danila Kutenin.
-  //
-  // What we're basically going to do is the following. Below letters represent
-  // the decoded message and dots are extraneous zeros. (Bits below are ordered
-  // little-endian.)
-  //
-  // start:
-  //  aaaaaa.. bbbbbb.. cccccc.. dddddd.. eeeeee.. ffffff.. gggggg.. hhhhhh..
-  //
-  // zext to u16:
-  //  aaaaaa.......... bbbbbb.......... cccccc.......... dddddd..........
-  //  eeeeee.......... ffffff.......... gggggg.......... hhhhhh..........
-  //
-  // u16 shift:
-  //  ..aaaaaa|........ ....bbbb|bb...... ......cc|cccc.... ........|dddddd..
-  //  ..eeeeee|........ ....ffff|ff...... ......gg|gggg.... ........|hhhhhh..
-  //
-  // u16 d3einterleave:
-  //  ..aaaaaa ....bbbb ......cc ........ ..eeeeee ....ffff ......gg ........
-  //  ........ bb...... cccc.... dddddd.. ........ ff...... gggg.... hhhhhh..
-  //
-  // u8 rotate:
-  //  ..aaaaaa ....bbbb ......cc ........ ..eeeeee ....ffff ......gg ........
-  //  bb...... cccc.... dddddd.. ........ ff...... gggg.... hhhhhh.. ........
-  //
-  // u8 or:
-  //  bbaaaaaa ccccbbbb ddddddcc ........ ffeeeeee ggggffff hhhhhhgg ........
-  //
-  // u8 shuffle:
-  //  bbaaaaaa ccccbbbb ddddddcc ffeeeeee ggggffff hhhhhhgg ........ ........
+      (sextets, valid)
+    }
+    DecodeLut::Ranges { ranges, len } => {
+      // `c - start < len` is a range check that only needs one comparison,
+      // since it wraps around for `c < start`.
+      let mut offsets = Simd::splat(0);
+      let mut matched = Mask::splat(false);
+      for &(start, len, offset) in &ranges[..*len] {
+        let in_range = (ascii - Simd::splat(start)).simd_lt(Simd::splat(len));
+        offsets |= mask_splat(in_range, offset);
+        matched |= in_range;
+      }
 
-  let shifted = sextets.cast::<u16>() << tiled(&[2, 4, 6, 8]);
+      (ascii + offsets, matched.all())
+    }
+  }
+}
 
-  let lo = shifted.cast::<u8>();
-  let hi = (shifted >> Simd::splat(8)).cast::<u8>();
-  let decoded_chunks = lo | hi.rotate_lanes_left::<1>();
+/// Returns which lanes of `ascii` are in the alphabet described by `luts`.
+///
+/// This performs the same range check as [`decode()`], but per-lane.
+#[inline]
+pub fn valid_lanes<const N: usize>(
+  ascii: Simd<u8, N>,
+  luts: &Luts,
+) -> Mask<i8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let mut ascii = ascii;
+  for &(from, to) in luts.aliases {
+    ascii = ascii
+      .simd_eq(Simd::splat(from))
+      .select(Simd::splat(to), ascii);
+  }
 
-  let output = swizzle!(N; decoded_chunks, array!(N; |i| i + i / 3));
+  match &luts.decode {
+    DecodeLut::Hashed {
+      valid_lo, valid_hi, ..
+    } => {
+      let lo_lut = Simd::from_array(*valid_lo);
+      let hi_lut = Simd::from_array(*valid_hi);
 
-  (output, valid)
+      let lo = swizzle::<16, N>(lo_lut, ascii & Simd::splat(0x0f));
+      let hi = swizzle::<16, N>(hi_lut, ascii >> Simd::splat(4));
+      (lo & hi).simd_eq(Simd::splat(0))
+    }
+    DecodeLut::Ranges { ranges, len } => {
+      let mut matched = Mask::splat(false);
+      for &(start, len, _) in &ranges[..*len] {
+        matched |= (ascii - Simd::splat(start)).simd_lt(Simd::splat(len));
+      }
+      matched
+    }
+  }
 }
 
 /// Encodes the low 3/4 bytes of `data` as base64. The high quarter of the
 /// input is ignored.
 #[inline]
-pub fn encode<const N: usize>(data: Simd<u8, N>) -> Simd<u8, N>
+pub fn encode<const N: usize>(data: Simd<u8, N>, luts: &Luts) -> Simd<u8, N>
 where
   LaneCount<N>: SupportedLaneCount,
 {
@@ -167,6 +633,19 @@ where
   // Now we have what is essentially a u6 array that looks like this:
   //  aaaaaa.. bbbbbb.. cccccc.. dddddd.. eeeeee.. ffffff.. gggggg.. hhhhhh..
 
+  from_sextets(sextets, luts)
+}
+
+/// Converts each lane of `sextets`, which must all be less than 64, into the
+/// corresponding character of the alphabet.
+#[inline]
+pub fn from_sextets<const N: usize>(
+  sextets: Simd<u8, N>,
+  luts: &Luts,
+) -> Simd<u8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
   // We need to split into five ranges: 0x00..=0x19, 0x1a..=0x33, 0x34..=0x3d,
   // 0x3e, and 0x3f. If we (saturating) subtract 0x1a from each range, we get
   //
@@ -194,14 +673,335 @@ where
   // If we shift the high nybbles down, this contrivance is a perfect hash, just
   // like in the encoding function.
 
+  let offsets = match &luts.encode {
+    EncodeLut::Hashed(offsets) => offsets,
+    EncodeLut::Table(table) => {
+      // Alphabets that don't fit the hash above need a full 64-byte lookup
+      // table. We do this by looking up each sextet in each 16-byte row of the
+      // table; indices that are out-of-range for a row produce zeros, so we can
+      // OR the results together.
+      let mut ascii = Simd::splat(0);
+      for (i, row) in table.iter().enumerate() {
+        let indices = sextets - Simd::splat(i as u8 * 16);
+        ascii |= swizzle::<16, N>(Simd::from_array(*row), indices);
+      }
+      return ascii;
+    }
+  };
+
   let hashes = (sextets.saturating_sub(Simd::splat(0x0a))
     + mask_splat(sextets.simd_ge(Simd::splat(0x34)), 0x0f)
     + mask_splat(sextets.simd_ge(Simd::splat(0x3e)), 0x1c))
     >> Simd::splat(4);
 
-  let offsets = tiled(&[191, 185, 185, 4, 4, 19, 16, !0]).swizzle_dyn(hashes);
+  sextets - tiled(offsets).swizzle_dyn(hashes)
+}
+
+/// Decodes `ascii` as base32. Returns the results of the decoding in the low
+/// 5/8 of the returned vector, as well as whether decoding completed
+/// successfully.
+#[inline]
+pub fn decode32<const N: usize>(
+  ascii: Simd<u8, N>,
+  luts: &Luts,
+) -> (Simd<u8, N>, bool)
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let (quintets, valid) = to_sextets(ascii, luts);
+
+  // Every eight quintets make up five bytes. Each quintet lands in at most two
+  // adjacent bytes, so we widen to u16 and shift each one into position within
+  // a two-byte window. (Bits below are ordered big-endian, to match the
+  // output.)
+  //
+  // start:
+  //  ...aaaaa ...bbbbb ...ccccc ...ddddd ...eeeee ...fffff ...ggggg ...hhhhh
+  //
+  // u16 shift, windows starting at byte 0, 0, 1, 1, 2, 3, 3, 4:
+  //  aaaaa... ........ .....bbb bb...... ..ccccc. ........ .......d dddd....
+  //  ....eeee e....... .fffff.. ........ ......gg ggg..... ...hhhhh ........
+  //
+  // Quintets that share a window can be merged with an OR, leaving five
+  // windows, one per byte:
+  //  aaaaabbb bb...... ..cccccd dddd.... ....eeee e....... .fffffgg ggg.....
+  //  ...hhhhh ........
+  //
+  // Each output byte is then the high half of its own window, ORed with the
+  // low half of the previous one.
+
+  let shifted = quintets.cast::<u16>() << tiled(&[11, 6, 9, 4, 7, 10, 5, 8]);
+  let merged = shifted
+    | (shifted & tiled(&[0, !0, 0, !0, 0, 0, !0, 0])).rotate_lanes_left::<1>();
+
+  let hi = (merged >> Simd::splat(8)).cast::<u8>();
+  let lo = merged.cast::<u8>();
+
+  let hi = swizzle!(N; hi, array!(N; |i| match i % 5 {
+    _ if i / 5 >= N / 8 => N,
+    j => i / 5 * 8 + [0, 2, 4, 5, 7][j],
+  }));
+  let lo = swizzle!(N; lo, array!(N; |i| match i % 5 {
+    _ if i / 5 >= N / 8 => N,
+    0 => N,
+    j => i / 5 * 8 + [0, 2, 4, 5][j - 1],
+  }));
+
+  (hi | lo, valid)
+}
+
+/// Encodes the low 5/8 bytes of `data` as base32. The rest of the input is
+/// ignored.
+#[inline]
+pub fn encode32<const N: usize>(data: Simd<u8, N>, luts: &Luts) -> Simd<u8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // This is the reverse of `decode32()`: gather the two-byte window that each
+  // quintet lives in, and shift it back down.
+  let hi = swizzle!(N; data, array!(N; |i| {
+    i / 8 * 5 + [0, 0, 1, 1, 2, 3, 3, 4][i % 8]
+  }));
+  let lo = swizzle!(N; data, array!(N; |i| {
+    i / 8 * 5 + [0, 0, 1, 1, 2, 3, 3, 4][i % 8] + 1
+  }));
+
+  let windows = (hi.cast::<u16>() << Simd::splat(8)) | lo.cast::<u16>();
+  let quintets = (windows >> tiled(&[11, 6, 9, 4, 7, 10, 5, 8])).cast::<u8>()
+    & Simd::splat(0x1f);
+
+  from_sextets(quintets, luts)
+}
+
+/// Decodes `ascii` as hex. Returns the results of the decoding in the low half
+/// of the returned vector, as well as whether decoding completed successfully.
+#[inline]
+pub fn decode16<const N: usize>(
+  ascii: Simd<u8, N>,
+  luts: &Luts,
+) -> (Simd<u8, N>, bool)
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let (nybbles, valid) = to_sextets(ascii, luts);
+
+  // Shift every high nybble into place, and then OR each pair of lanes
+  // together.
+  let shifted = nybbles << tiled(&[4, 0]);
+  let hi =
+    swizzle!(N; shifted, array!(N; |i| if i < N / 2 { 2 * i } else { N }));
+  let lo =
+    swizzle!(N; shifted, array!(N; |i| if i < N / 2 { 2 * i + 1 } else { N }));
+
+  (hi | lo, valid)
+}
+
+/// Encodes the low half of `data` as hex. The high half of the input is
+/// ignored.
+#[inline]
+pub fn encode16<const N: usize>(data: Simd<u8, N>, luts: &Luts) -> Simd<u8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // Duplicate each byte, and then keep the high nybble of the first copy and
+  // the low nybble of the second.
+  let doubled = swizzle!(N; data, array!(N; |i| i / 2));
+  let nybbles = (doubled >> tiled(&[4, 0])) & Simd::splat(0x0f);
+
+  from_sextets(nybbles, luts)
+}
+
+/// Converts each lane of `words` into five Ascii85 digits, most significant
+/// first.
+#[inline]
+pub fn encode85<const N: usize>(words: Simd<u32, N>) -> [Simd<u8, N>; 5]
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // Division by a constant compiles down to a multiply and a shift, so this is
+  // cheaper than it looks.
+  let mut words = words;
+  let mut digits = [Simd::splat(0); 5];
+  for digit in digits.iter_mut().rev() {
+    *digit = (words % Simd::splat(85)).cast::<u8>() + Simd::splat(b'!');
+    words /= Simd::splat(85);
+  }
+  digits
+}
+
+/// Converts five Ascii85 digits per lane, most significant first, into the
+/// words they represent. Also returns whether every digit was in range and
+/// every word fit in 32 bits.
+#[inline]
+pub fn decode85<const N: usize>(
+  digits: [Simd<u8, N>; 5],
+) -> (Simd<u32, N>, bool)
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let mut words = Simd::<u64, N>::splat(0);
+  let mut valid = Mask::splat(true);
+  for digit in digits {
+    let digit = digit - Simd::splat(b'!');
+    valid &= digit.simd_lt(Simd::splat(85));
+    words = words * Simd::splat(85) + digit.cast::<u64>();
+  }
+
+  let fits = words.simd_le(Simd::splat(u32::MAX as u64));
+  (words.cast::<u32>(), valid.all() && fits.all())
+}
+
+/// Returns which lanes of `data` may appear as-is in quoted-printable text:
+/// printable ASCII other than `=`, plus space and tab.
+#[inline]
+pub fn qp_literal<const N: usize>(data: Simd<u8, N>) -> Mask<i8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let printable = (data - Simd::splat(b' ')).simd_lt(Simd::splat(0x7f - b' '));
+  (printable & data.simd_ne(Simd::splat(b'=')))
+    | data.simd_eq(Simd::splat(b'\t'))
+}
+
+/// Returns which lanes of `ascii` are ASCII whitespace, in the sense of
+/// [`u8::is_ascii_whitespace()`].
+#[inline]
+pub fn whitespace<const N: usize>(ascii: Simd<u8, N>) -> Mask<i8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // `\t`, `\n`, `\x0c` and `\r` are 9, 10, 12 and 13; `\x0b` is not included.
+  let control = (ascii - Simd::splat(b'\t')).simd_lt(Simd::splat(5));
+  (control & ascii.simd_ne(Simd::splat(0x0b)))
+    | ascii.simd_eq(Simd::splat(b' '))
+}
+
+/// Returns which lanes of `ascii` need special handling when decoding
+/// quoted-printable text: escapes and line breaks.
+#[inline]
+pub fn qp_special<const N: usize>(ascii: Simd<u8, N>) -> Mask<i8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  ascii.simd_eq(Simd::splat(b'='))
+    | ascii.simd_eq(Simd::splat(b'\r'))
+    | ascii.simd_eq(Simd::splat(b'\n'))
+}
+
+/// Returns which lanes of `data` need to be percent-encoded, given a bitmap of
+/// ASCII characters to escape. Non-ASCII bytes are always escaped.
+#[inline]
+pub fn percent_escapes<const N: usize>(
+  data: Simd<u8, N>,
+  bitmap: &[u8; 16],
+) -> Mask<i8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // The high five bits of an ASCII byte select a byte of the bitmap, and the
+  // low three select a bit of it. Non-ASCII bytes index past the end of the
+  // table, which produces zero, so they need their own check.
+  let rows =
+    swizzle::<16, N>(Simd::from_array(*bitmap), data >> Simd::splat(3));
+  let bits = Simd::splat(1) << (data & Simd::splat(7));
+  (rows & bits).simd_ne(Simd::splat(0)) | data.simd_ge(Simd::splat(0x80))
+}
+
+/// Returns the high and low hex digits of each lane of `data`.
+#[inline]
+pub fn hex_digits<const N: usize>(
+  data: Simd<u8, N>,
+  luts: &Luts,
+) -> (Simd<u8, N>, Simd<u8, N>)
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let hi = from_sextets(data >> Simd::splat(4), luts);
+  let lo = from_sextets(data & Simd::splat(0x0f), luts);
+  (hi, lo)
+}
+
+/// Splits every five bytes of `data` into four ten-bit values, most
+/// significant first. The values are in the low `N / 5 * 4` lanes of the
+/// result.
+#[inline]
+pub fn split10<const N: usize>(data: Simd<u8, N>) -> Simd<u16, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // Like `encode32()`, gather the two-byte window that each value lives in,
+  // and shift it down into place.
+  let hi = swizzle!(N; data, array!(N; |i| i / 4 * 5 + i % 4));
+  let lo = swizzle!(N; data, array!(N; |i| i / 4 * 5 + i % 4 + 1));
+
+  let windows = (hi.cast::<u16>() << Simd::splat(8)) | lo.cast::<u16>();
+  (windows >> tiled(&[6, 4, 2, 0])) & Simd::splat(0x3ff)
+}
+
+/// Maps ten-bit values onto the emoji alphabet used by `crate::emoji`, and
+/// returns the last two bytes of each one's UTF-8 encoding. The first two are
+/// always `F0 9F`.
+#[inline]
+pub fn emoji_utf8<const N: usize>(
+  values: Simd<u16, N>,
+) -> (Simd<u8, N>, Simd<u8, N>)
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // The alphabet is three runs of code points; see `crate::emoji`.
+  let values = values.cast::<u32>();
+  let base = values.simd_lt(Simd::splat(848)).select(
+    Simd::splat(0x1f300),
+    values
+      .simd_lt(Simd::splat(918))
+      .select(Simd::splat(0x1f680 - 848), Simd::splat(0x1f910 - 918)),
+  );
+  let code = values + base;
+
+  let b2 = ((code >> Simd::splat(6)) & Simd::splat(0x3f)) | Simd::splat(0x80);
+  let b3 = (code & Simd::splat(0x3f)) | Simd::splat(0x80);
+  (b2.cast::<u8>(), b3.cast::<u8>())
+}
+
+/// A scalar version of the encoder's perfect hash.
+const fn encode_hash(sextet: u8) -> u8 {
+  let mut hash = sextet.saturating_sub(0x0a);
+  if sextet >= 0x34 {
+    hash += 0x0f;
+  }
+  if sextet >= 0x3e {
+    hash += 0x1c;
+  }
+  hash >> 4
+}
+
+/// Replaces each byte of `ascii` that appears in `from` with the byte at the
+/// same index in `to`. The bytes in `from` must have distinct low nybbles.
+#[inline]
+pub fn translate<const N: usize>(
+  ascii: Simd<u8, N>,
+  from: [u8; 2],
+  to: [u8; 2],
+) -> Simd<u8, N>
+where
+  LaneCount<N>: SupportedLaneCount,
+{
+  // We look up the low nybble of each byte in two tables: one containing the
+  // byte we expect to see with that nybble, and one containing what to XOR it
+  // with to get the replacement. Bytes that don't match what we expect are
+  // left alone.
+  let mut expected = [0; 16];
+  let mut deltas = [0; 16];
+  for i in 0..2 {
+    expected[(from[i] & 0xf) as usize] = from[i];
+    deltas[(from[i] & 0xf) as usize] = from[i] ^ to[i];
+  }
+
+  let lo = ascii & Simd::splat(0x0f);
+  let expected = swizzle::<16, N>(Simd::from_array(expected), lo);
+  let deltas = swizzle::<16, N>(Simd::from_array(deltas), lo);
 
-  sextets - offsets
+  ascii ^ (deltas & ascii.simd_eq(expected).to_int().cast::<u8>())
 }
 
 /// Shorthand for mask.select(splat(val), splat(0)).
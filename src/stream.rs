@@ -0,0 +1,803 @@
+//! Incremental codecs, for data that arrives in pieces.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::Engine;
+use crate::Error;
+
+/// How much data [`Encoder`] encodes at a time when wrapping lines. A
+/// multiple of three, so that we never have to pad in the middle.
+const CHUNK: usize = 768;
+
+/// A push-based decoder, for base64 that arrives in chunks, such as from a
+/// socket.
+///
+/// Chunks can be split anywhere, including in the middle of a group of four
+/// characters or of its padding. Only a partial group is buffered between
+/// calls; everything else is decoded with the same SIMD kernels as
+/// [`Engine::decode()`].
+///
+/// ```
+/// use vb64::Decoder;
+///
+/// let mut dec = Decoder::new();
+/// let mut out = Vec::new();
+/// dec.update(b"aGVs", &mut out).unwrap();
+/// dec.update(b"bG8gd2", &mut out).unwrap();
+/// dec.update(b"9ybGQ=", &mut out).unwrap();
+/// dec.finish(&mut out).unwrap();
+/// assert_eq!(out, b"hello world");
+/// ```
+///
+/// The input is checked by the same rules as [`Engine::decode()`], and
+/// offsets in errors count from the start of the first chunk. Since an error
+/// may only become apparent at the end, though, such as a missing padding
+/// character, a decoder may report a different error than decoding the whole
+/// input at once would when there is more than one thing wrong with it.
+#[derive(Copy, Clone, Debug)]
+pub struct Decoder<'a> {
+  engine: &'a Engine,
+  /// A partial group left over from the last chunk.
+  tail: [u8; 4],
+  tail_len: usize,
+  /// Where each character in `tail` was in the input, for reporting errors.
+  tail_offsets: [usize; 4],
+  /// Whether `tail` contains padding, after which only more padding (and
+  /// whitespace, if allowed) may follow.
+  padded: bool,
+  /// How many bytes of input we've seen.
+  consumed: usize,
+  /// How many bytes we've decoded, and how many we may.
+  decoded: usize,
+  limit: usize,
+  /// The error that stopped decoding, which every later call returns.
+  error: Option<Error>,
+}
+
+impl Decoder<'static> {
+  /// Creates a decoder for standard base64.
+  pub fn new() -> Self {
+    Self::with_engine(&Engine::STANDARD)
+  }
+}
+
+impl Default for Decoder<'static> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<'a> Decoder<'a> {
+  /// Creates a decoder that follows the given engine's rules.
+  pub fn with_engine(engine: &'a Engine) -> Self {
+    Self {
+      engine,
+      tail: [0; 4],
+      tail_len: 0,
+      tail_offsets: [0; 4],
+      padded: false,
+      consumed: 0,
+      decoded: 0,
+      limit: usize::MAX,
+      error: None,
+    }
+  }
+
+  /// Caps how many bytes this decoder will produce in total.
+  ///
+  /// Once the input would decode to more than `max_len` bytes, decoding fails
+  /// with [`Error::LimitExceeded`], before any memory is allocated for the
+  /// excess, as in [`crate::decode_with_limit()`].
+  ///
+  /// ```
+  /// let mut dec = vb64::Decoder::new().limit(4);
+  /// let mut out = Vec::new();
+  /// assert!(dec.update(b"QUJD", &mut out).is_ok());
+  /// assert!(dec.update(b"REVG", &mut out).is_err());
+  /// assert_eq!(out, b"ABC");
+  /// ```
+  pub fn limit(self, max_len: usize) -> Self {
+    Self {
+      limit: max_len,
+      ..self
+    }
+  }
+
+  /// Decodes the next chunk of input, appending whatever it completes to
+  /// `out`, and returns how many bytes were appended.
+  ///
+  /// If this fails, nothing is appended, and this and every later call return
+  /// the same error.
+  #[cfg(feature = "alloc")]
+  pub fn update(
+    &mut self,
+    data: impl AsRef<[u8]>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize, Error> {
+    self.update_with(data.as_ref(), out)
+  }
+
+  /// Like [`Decoder::update()`], but writes to the start of `out` instead of
+  /// appending to a `Vec`, so that nothing is allocated.
+  ///
+  /// Together with the decoder itself, which is a small, fixed-size value,
+  /// this is enough to decode a large payload chunk by chunk on a device with
+  /// no heap at all:
+  ///
+  /// ```
+  /// let mut dec = vb64::Decoder::new();
+  /// let mut buf = [0; 12];
+  /// let mut firmware = Vec::new();
+  /// for chunk in [&b"aGVsbG8g"[..], b"d29y", b"bGQ="] {
+  ///   let n = dec.update_to_slice(chunk, &mut buf).unwrap();
+  ///   firmware.extend_from_slice(&buf[..n]); // Flash it, say.
+  /// }
+  /// let n = dec.finish_to_slice(&mut buf).unwrap();
+  /// firmware.extend_from_slice(&buf[..n]);
+  /// assert_eq!(firmware, b"hello world");
+  /// ```
+  ///
+  /// `out` must have room for `(data.len() + 3) / 4 * 3` bytes, or this fails
+  /// with [`Error::BufferTooSmall`] without consuming any input. Unlike other
+  /// errors, that one doesn't stick.
+  pub fn update_to_slice(
+    &mut self,
+    data: impl AsRef<[u8]>,
+    out: &mut [u8],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    if self.error.is_none() && out.len() < (data.len() + 3) / 4 * 3 {
+      return Err(Error::BufferTooSmall);
+    }
+    self.update_with(data, &mut Slice { buf: out, len: 0 })
+  }
+
+  fn update_with(
+    &mut self,
+    data: &[u8],
+    out: &mut impl Sink,
+  ) -> Result<usize, Error> {
+    if let Some(e) = self.error {
+      return Err(e);
+    }
+
+    let start = out.len();
+    let base = self.consumed;
+    self.consumed += data.len();
+    match self.update_inner(data, base, out) {
+      Ok(()) => Ok(out.len() - start),
+      Err(e) => {
+        out.truncate(start);
+        self.error = Some(e);
+        Err(e)
+      }
+    }
+  }
+
+  /// Decodes whatever is left over, appending it to `out`, and returns how
+  /// many bytes were appended.
+  ///
+  /// This is where missing or malformed padding, and input that ends partway
+  /// through a group, are detected.
+  #[cfg(feature = "alloc")]
+  pub fn finish(self, out: &mut Vec<u8>) -> Result<usize, Error> {
+    self.finish_with(out)
+  }
+
+  /// Like [`Decoder::finish()`], but writes to the start of `out` instead of
+  /// appending to a `Vec`, so that nothing is allocated; see
+  /// [`Decoder::update_to_slice()`].
+  ///
+  /// `out` must have room for whatever is left over, which is at most three
+  /// bytes, or this fails with [`Error::BufferTooSmall`].
+  pub fn finish_to_slice(self, out: &mut [u8]) -> Result<usize, Error> {
+    if self.error.is_none() && out.len() < self.tail_decoded_len() {
+      return Err(Error::BufferTooSmall);
+    }
+    self.finish_with(&mut Slice { buf: out, len: 0 })
+  }
+
+  fn finish_with(mut self, out: &mut impl Sink) -> Result<usize, Error> {
+    if let Some(e) = self.error {
+      return Err(e);
+    }
+
+    self.reserve(self.tail_decoded_len())?;
+    let start = out.len();
+    let tail = self.tail;
+    out
+      .decode(self.engine, &tail[..self.tail_len])
+      .map_err(|e| self.relocate(e))?;
+    Ok(out.len() - start)
+  }
+
+  /// Returns the most input that [`Decoder::update_to_slice()`] accepts with
+  /// `out_len` bytes of room.
+  #[cfg(any(feature = "std", feature = "embedded-io"))]
+  pub(crate) fn max_input(&self, out_len: usize) -> usize {
+    (out_len / 3 * 4).saturating_sub(3)
+  }
+
+  /// Returns the most that the leftover partial group can decode to.
+  fn tail_decoded_len(&self) -> usize {
+    let tail = &self.tail[..self.tail_len];
+    crate::max_decoded_len(tail.iter().filter(|&&b| b != b'=').count())
+  }
+
+  fn update_inner(
+    &mut self,
+    data: &[u8],
+    base: usize,
+    out: &mut impl Sink,
+  ) -> Result<(), Error> {
+    // Finish off the group left over from last time, if any.
+    let mut i = 0;
+    while i < data.len() && (self.tail_len > 0 || self.padded) {
+      self.push(data[i], base + i, out)?;
+      i += 1;
+    }
+
+    // Decode as many whole groups as we can in one go. Padding ends the
+    // input, so it and anything after it go through `push()`.
+    let rest = &data[i..];
+    let body = rest.iter().position(|&b| b == b'=').unwrap_or(rest.len());
+    let (end, groups) = self.whole_groups(&rest[..body]);
+    if groups > 0 {
+      self.reserve(groups * 3)?;
+      out
+        .decode(self.engine, &rest[..end])
+        .map_err(|e| e.shift(base + i))?;
+    }
+
+    for (j, &b) in rest.iter().enumerate().skip(end) {
+      self.push(b, base + i + j, out)?;
+    }
+    Ok(())
+  }
+
+  /// Adds one character, found at `offset` in the input, to the partial group,
+  /// decoding the group if that completes it.
+  fn push(
+    &mut self,
+    b: u8,
+    offset: usize,
+    out: &mut impl Sink,
+  ) -> Result<(), Error> {
+    if self.engine.skips_whitespace() && b.is_ascii_whitespace() {
+      return Ok(());
+    }
+    if self.padded && (b != b'=' || self.tail_len == 4) {
+      return Err(Error::InvalidPadding);
+    }
+
+    self.tail[self.tail_len] = b;
+    self.tail_offsets[self.tail_len] = offset;
+    self.tail_len += 1;
+    self.padded |= b == b'=';
+
+    if self.tail_len == 4 && !self.padded {
+      self.reserve(3)?;
+      out
+        .decode(self.engine, &self.tail)
+        .map_err(|e| self.relocate(e))?;
+      self.tail_len = 0;
+    }
+    Ok(())
+  }
+
+  /// Returns the length of the longest prefix of `data` that holds whole
+  /// groups, and how many groups that is.
+  fn whole_groups(&self, data: &[u8]) -> (usize, usize) {
+    if !self.engine.skips_whitespace() {
+      return (data.len() / 4 * 4, data.len() / 4);
+    }
+
+    let chars = data.iter().filter(|b| !b.is_ascii_whitespace()).count();
+    let mut extra = chars % 4;
+    let mut end = data.len();
+    while extra > 0 {
+      end -= 1;
+      if !data[end].is_ascii_whitespace() {
+        extra -= 1;
+      }
+    }
+    (end, chars / 4)
+  }
+
+  /// Counts `len` more bytes against the limit.
+  fn reserve(&mut self, len: usize) -> Result<(), Error> {
+    match self.decoded.checked_add(len) {
+      Some(total) if total <= self.limit => {
+        self.decoded = total;
+        Ok(())
+      }
+      _ => Err(Error::LimitExceeded),
+    }
+  }
+
+  /// Maps an offset into `tail` in `e` to one into the input.
+  fn relocate(&self, e: Error) -> Error {
+    match e {
+      Error::InvalidByte { offset, byte } => Error::InvalidByte {
+        offset: self.tail_offsets[offset],
+        byte,
+      },
+      e => e,
+    }
+  }
+}
+
+/// A push-based encoder, for encoding data in pieces in bounded memory, such
+/// as a large file.
+///
+/// At most two bytes, the start of a partial group, are buffered between
+/// calls; padding is only emitted by [`Encoder::finish()`].
+///
+/// ```
+/// use vb64::Encoder;
+///
+/// let mut enc = Encoder::new();
+/// let mut out = Vec::new();
+/// enc.update(b"hel", &mut out);
+/// enc.update(b"lo w", &mut out);
+/// enc.update(b"orld", &mut out);
+/// enc.finish(&mut out);
+/// assert_eq!(out, b"aGVsbG8gd29ybGQ=");
+/// ```
+///
+/// If the engine wraps its output, lines are broken across calls just as
+/// [`Engine::encode()`] would break them.
+#[derive(Copy, Clone, Debug)]
+pub struct Encoder<'a> {
+  engine: &'a Engine,
+  /// A partial group left over from the last chunk.
+  tail: [u8; 3],
+  tail_len: usize,
+  /// How far into the current line we are, if the engine wraps.
+  col: usize,
+}
+
+impl Encoder<'static> {
+  /// Creates an encoder for standard, padded base64.
+  pub fn new() -> Self {
+    Self::with_engine(&Engine::STANDARD)
+  }
+}
+
+impl Default for Encoder<'static> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<'a> Encoder<'a> {
+  /// Creates an encoder with the given engine's alphabet, padding, and line
+  /// wrapping.
+  pub fn with_engine(engine: &'a Engine) -> Self {
+    Self {
+      engine,
+      tail: [0; 3],
+      tail_len: 0,
+      col: 0,
+    }
+  }
+
+  /// Encodes the next chunk of data, appending whatever whole groups it
+  /// completes to `out`, and returns how many bytes were appended.
+  #[cfg(feature = "alloc")]
+  pub fn update(&mut self, data: impl AsRef<[u8]>, out: &mut Vec<u8>) -> usize {
+    self.update_with(data.as_ref(), out)
+  }
+
+  /// Like [`Encoder::update()`], but writes to the start of `out` instead of
+  /// appending to a `Vec`, so that nothing is allocated.
+  ///
+  /// `out` must have room for everything this call produces: the whole groups
+  /// it completes, plus any line endings. Otherwise, this fails with
+  /// [`Error::BufferTooSmall`] without consuming any input.
+  ///
+  /// ```
+  /// let mut enc = vb64::Encoder::new();
+  /// let mut buf = [0; 8];
+  /// assert_eq!(enc.update_to_slice(b"hello", &mut buf), Ok(4));
+  /// assert_eq!(&buf[..4], b"aGVs");
+  /// assert!(enc.update_to_slice(b" world", &mut buf[..4]).is_err());
+  /// assert_eq!(enc.update_to_slice(b" world", &mut buf), Ok(8));
+  /// assert_eq!(&buf, b"bG8gd29y");
+  /// assert_eq!(enc.finish_to_slice(&mut buf), Ok(4));
+  /// assert_eq!(&buf[..4], b"bGQ=");
+  /// ```
+  pub fn update_to_slice(
+    &mut self,
+    data: impl AsRef<[u8]>,
+    out: &mut [u8],
+  ) -> Result<usize, Error> {
+    let data = data.as_ref();
+    let groups = (self.tail_len + data.len()) / 3;
+    if out.len() < self.wrapped_len(groups * 4, false) {
+      return Err(Error::BufferTooSmall);
+    }
+    Ok(self.update_with(data, &mut Slice { buf: out, len: 0 }))
+  }
+
+  fn update_with(&mut self, mut data: &[u8], out: &mut impl Sink) -> usize {
+    let start = out.len();
+
+    // Finish off the group left over from last time, if there is one.
+    if self.tail_len > 0 {
+      let n = data.len().min(3 - self.tail_len);
+      self.tail[self.tail_len..self.tail_len + n].copy_from_slice(&data[..n]);
+      self.tail_len += n;
+      data = &data[n..];
+
+      if self.tail_len < 3 {
+        return 0;
+      }
+      self.tail_len = 0;
+      let tail = self.tail;
+      self.emit(&tail, out);
+    }
+
+    let (body, rest) = data.split_at(data.len() / 3 * 3);
+    self.emit(body, out);
+
+    self.tail[..rest.len()].copy_from_slice(rest);
+    self.tail_len = rest.len();
+    out.len() - start
+  }
+
+  /// Encodes any leftover partial group, with its padding, appending it to
+  /// `out`, and returns how many bytes were appended.
+  #[cfg(feature = "alloc")]
+  pub fn finish(self, out: &mut Vec<u8>) -> usize {
+    self.finish_with(out)
+  }
+
+  /// Like [`Encoder::finish()`], but writes to the start of `out` instead of
+  /// appending to a `Vec`, so that nothing is allocated; see
+  /// [`Encoder::update_to_slice()`].
+  ///
+  /// `out` must have room for the last group and any line ending, or this
+  /// fails with [`Error::BufferTooSmall`].
+  pub fn finish_to_slice(self, out: &mut [u8]) -> Result<usize, Error> {
+    let len = crate::encoded_len(self.tail_len, self.engine.pads()).unwrap();
+    if out.len() < self.wrapped_len(len, true) {
+      return Err(Error::BufferTooSmall);
+    }
+    Ok(self.finish_with(&mut Slice { buf: out, len: 0 }))
+  }
+
+  fn finish_with(mut self, out: &mut impl Sink) -> usize {
+    let start = out.len();
+    let tail = self.tail;
+    self.emit(&tail[..self.tail_len], out);
+    if self.col > 0 {
+      out.extend(self.engine.eol().as_bytes());
+    }
+    out.len() - start
+  }
+
+  /// Returns the most input that [`Encoder::update_to_slice()`] accepts with
+  /// `out_len` bytes of room.
+  #[cfg(any(feature = "std", feature = "embedded-io"))]
+  pub(crate) fn max_input(&self, out_len: usize) -> usize {
+    let line_len = self.engine.line_len();
+    if line_len == 0 {
+      return (out_len / 4 * 3).saturating_sub(self.tail_len);
+    }
+
+    // Count from the start of the current line: take as many whole lines as
+    // fit, with their line endings, and then as much of the next as is left,
+    // which may be all of the room if the lines are longer than it.
+    let room = self.col + out_len;
+    let lines = room / (line_len + self.engine.eol().len());
+    let rest = room - lines * (line_len + self.engine.eol().len());
+    let chars = lines * line_len + rest.min(line_len - 1) - self.col;
+    (chars / 4 * 3).saturating_sub(self.tail_len)
+  }
+
+  /// Returns how long `len` more characters of text are once broken into
+  /// lines, ending the last one if `last`.
+  fn wrapped_len(&self, len: usize, last: bool) -> usize {
+    let line_len = self.engine.line_len();
+    if line_len == 0 {
+      return len;
+    }
+    let mut lines = (self.col + len) / line_len;
+    if last && (self.col + len) % line_len > 0 {
+      lines += 1;
+    }
+    len + lines * self.engine.eol().len()
+  }
+
+  fn emit(&mut self, data: &[u8], out: &mut impl Sink) {
+    let line_len = self.engine.line_len();
+    if line_len == 0 {
+      out.encode(self.engine, data);
+      return;
+    }
+
+    // Chunks don't line up with lines, so break the lines up ourselves.
+    let mut buf = [0; CHUNK / 3 * 4];
+    for chunk in data.chunks(CHUNK) {
+      let len = self
+        .engine
+        .encode_unwrapped(chunk, crate::as_uninit(&mut buf))
+        .unwrap();
+      let mut text = &buf[..len];
+      while text.len() >= line_len - self.col {
+        let (line, rest) = text.split_at(line_len - self.col);
+        out.extend(line);
+        out.extend(self.engine.eol().as_bytes());
+        self.col = 0;
+        text = rest;
+      }
+      self.col += text.len();
+      out.extend(text);
+    }
+  }
+}
+
+/// Where the codecs put their output: either a `Vec` that grows as needed, or
+/// a slice that was checked to be big enough up front.
+trait Sink {
+  fn len(&self) -> usize;
+  fn truncate(&mut self, len: usize);
+  fn extend(&mut self, bytes: &[u8]);
+
+  /// Decodes `data`, which only contains whitespace if `engine` skips it.
+  fn decode(&mut self, engine: &Engine, data: &[u8]) -> Result<(), Error>;
+
+  /// Encodes `data` with an `engine` that doesn't wrap lines.
+  fn encode(&mut self, engine: &Engine, data: &[u8]);
+}
+
+#[cfg(feature = "alloc")]
+impl Sink for Vec<u8> {
+  fn len(&self) -> usize {
+    Vec::len(self)
+  }
+
+  fn truncate(&mut self, len: usize) {
+    Vec::truncate(self, len)
+  }
+
+  fn extend(&mut self, bytes: &[u8]) {
+    self.extend_from_slice(bytes)
+  }
+
+  fn decode(&mut self, engine: &Engine, data: &[u8]) -> Result<(), Error> {
+    engine.decode_to(data, self)?;
+    Ok(())
+  }
+
+  fn encode(&mut self, engine: &Engine, data: &[u8]) {
+    engine.encode_to(data, self);
+  }
+}
+
+/// A slice whose first `len` bytes have been written.
+struct Slice<'b> {
+  buf: &'b mut [u8],
+  len: usize,
+}
+
+impl Sink for Slice<'_> {
+  fn len(&self) -> usize {
+    self.len
+  }
+
+  fn truncate(&mut self, len: usize) {
+    self.len = len;
+  }
+
+  fn extend(&mut self, bytes: &[u8]) {
+    self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+    self.len += bytes.len();
+  }
+
+  fn decode(&mut self, engine: &Engine, data: &[u8]) -> Result<(), Error> {
+    let out = crate::as_uninit(&mut self.buf[self.len..]);
+    self.len += engine.decode_spaced(data, out)?;
+    Ok(())
+  }
+
+  fn encode(&mut self, engine: &Engine, data: &[u8]) {
+    let out = crate::as_uninit(&mut self.buf[self.len..]);
+    self.len += engine.encode_unwrapped(data, out).unwrap();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode_chunked(
+    engine: &Engine,
+    data: &[u8],
+    chunk: usize,
+  ) -> Result<Vec<u8>, Error> {
+    let mut dec = Decoder::with_engine(engine);
+    let mut out = Vec::new();
+    for chunk in data.chunks(chunk) {
+      dec.update(chunk, &mut out)?;
+    }
+    dec.finish(&mut out)?;
+    Ok(out)
+  }
+
+  #[test]
+  fn decoder() {
+    let wrapped = Engine::STANDARD.wrap(8);
+    for (i, enc, dec) in crate::tests::random_tests().into_iter().step_by(7) {
+      let text = wrapped.encode(&dec);
+      for chunk in [1, 2, 3, 5, 7, 64, 1000] {
+        let got = decode_chunked(&Engine::STANDARD, enc, chunk).unwrap();
+        assert_eq!(got, dec, "case {i}, chunk {chunk}");
+        let got = decode_chunked(&wrapped, text.as_bytes(), chunk).unwrap();
+        assert_eq!(got, dec, "case {i}, chunk {chunk}");
+      }
+    }
+  }
+
+  #[test]
+  fn decoder_errors() {
+    let strict = Engine::STANDARD.strict();
+    let ws = Engine::STANDARD.ignore_whitespace();
+    for chunk in [1, 2, 3, 4, 5, 100] {
+      let decode =
+        |engine: &Engine, data: &[u8]| decode_chunked(engine, data, chunk);
+      assert_eq!(decode(&ws, b"QQ== \n").unwrap(), b"A");
+      assert_eq!(decode(&ws, b"QU\nJ D\n").unwrap(), b"ABC");
+      assert_eq!(
+        decode(&Engine::STANDARD, b"QUJDQU!DQUJD"),
+        Err(Error::InvalidByte {
+          offset: 6,
+          byte: b'!'
+        })
+      );
+      assert_eq!(
+        decode(&ws, b"QUJD\nQU!D"),
+        Err(Error::InvalidByte {
+          offset: 7,
+          byte: b'!'
+        })
+      );
+      assert_eq!(
+        decode(&Engine::STANDARD, b"QQ==QUJD"),
+        Err(Error::InvalidPadding)
+      );
+      assert_eq!(
+        decode(&Engine::STANDARD, b"QQ==="),
+        Err(Error::InvalidPadding)
+      );
+      assert_eq!(
+        decode(&Engine::STANDARD, b"QUJDR"),
+        Err(Error::InvalidLength)
+      );
+      assert_eq!(decode(&strict, b"QUI"), Err(Error::InvalidPadding));
+      assert_eq!(decode(&strict, b"QUJ="), Err(Error::InvalidTrailingBits));
+    }
+  }
+
+  #[test]
+  fn decoder_limit() {
+    for chunk in [1, 3, 4, 100] {
+      let mut dec = Decoder::new().limit(4);
+      let mut out = Vec::new();
+      let res = b"QUJDRA=="
+        .chunks(chunk)
+        .try_for_each(|chunk| dec.update(chunk, &mut out).map(drop));
+      assert!(res.is_ok());
+      assert_eq!(dec.finish(&mut out).unwrap(), 1);
+      assert_eq!(out, b"ABCD");
+
+      let mut dec = Decoder::new().limit(4);
+      let mut out = Vec::new();
+      let res = b"QUJDREVG"
+        .chunks(chunk)
+        .try_for_each(|chunk| dec.update(chunk, &mut out).map(drop));
+      assert_eq!(res, Err(Error::LimitExceeded));
+      assert_eq!(dec.update(b"", &mut out), Err(Error::LimitExceeded));
+    }
+  }
+
+  #[test]
+  fn encoder() {
+    let data = (0..3000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engines = [
+      Engine::STANDARD,
+      Engine::URL_SAFE_NOPAD,
+      Engine::STANDARD.wrap(76),
+      Engine::STANDARD
+        .wrap(64)
+        .line_ending(crate::LineEnding::CrLf),
+    ];
+    for engine in &engines {
+      for len in [0, 1, 2, 3, 56, 57, 58, 769, 3000] {
+        for piece in [1, 2, 3, 5, 57, 769, 3000] {
+          let mut enc = Encoder::with_engine(engine);
+          let mut out = b"x".to_vec();
+          let mut n = 0;
+          for chunk in data[..len].chunks(piece) {
+            n += enc.update(chunk, &mut out);
+          }
+          n += enc.finish(&mut out);
+          assert_eq!(n, out.len() - 1);
+          let want = engine.encode(&data[..len]);
+          assert_eq!(out[1..], *want.as_bytes(), "len {len}, piece {piece}");
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn slices() {
+    let data = (0..3000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+    let engines = [
+      Engine::STANDARD,
+      Engine::URL_SAFE_NOPAD,
+      Engine::STANDARD.wrap(76),
+      Engine::STANDARD
+        .wrap(64)
+        .line_ending(crate::LineEnding::CrLf),
+    ];
+    for engine in &engines {
+      for len in [0, 1, 2, 3, 57, 769, 3000] {
+        let text = engine.encode(&data[..len]);
+        for piece in [1, 2, 5, 57, 1000] {
+          let mut buf = [0; 2000];
+          let mut enc = Encoder::with_engine(engine);
+          let mut out = Vec::new();
+          for chunk in data[..len].chunks(piece) {
+            let n = enc.update_to_slice(chunk, &mut buf).unwrap();
+            out.extend_from_slice(&buf[..n]);
+          }
+          let n = enc.finish_to_slice(&mut buf).unwrap();
+          out.extend_from_slice(&buf[..n]);
+          assert_eq!(out, text.as_bytes(), "len {len}, piece {piece}");
+
+          let mut dec = Decoder::with_engine(engine);
+          let mut out = Vec::new();
+          for chunk in text.as_bytes().chunks(piece) {
+            let n = dec.update_to_slice(chunk, &mut buf).unwrap();
+            out.extend_from_slice(&buf[..n]);
+          }
+          let n = dec.finish_to_slice(&mut buf).unwrap();
+          out.extend_from_slice(&buf[..n]);
+          assert_eq!(out, data[..len], "len {len}, piece {piece}");
+        }
+      }
+    }
+
+    // Running out of room doesn't consume anything.
+    let mut dec = Decoder::new();
+    let mut buf = [0; 6];
+    assert_eq!(
+      dec.update_to_slice(b"QUJDREVG", &mut buf[..5]),
+      Err(Error::BufferTooSmall)
+    );
+    assert_eq!(dec.update_to_slice(b"QUJDREVG", &mut buf), Ok(6));
+    assert_eq!(&buf, b"ABCDEF");
+    assert_eq!(dec.update_to_slice(b"Rw", &mut buf), Ok(0));
+    assert_eq!(
+      dec.finish_to_slice(&mut buf[..0]),
+      Err(Error::BufferTooSmall)
+    );
+
+    // Whitespace is squeezed out without losing track of where errors are.
+    let engine = Engine::STANDARD.ignore_whitespace();
+    let mut text = b"QUJD \n".repeat(100);
+    text[403] = b'!';
+    let mut dec = Decoder::with_engine(&engine);
+    let mut buf = [0; 600];
+    assert_eq!(
+      dec.update_to_slice(&text, &mut buf),
+      Err(Error::InvalidByte {
+        offset: 403,
+        byte: b'!'
+      })
+    );
+  }
+}
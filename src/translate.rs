@@ -0,0 +1,131 @@
+//! Conversion between base64 alphabets without a decode/encode round trip.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::simd::LaneCount;
+use core::simd::Simd;
+use core::simd::SupportedLaneCount;
+
+use crate::simd;
+
+/// Which way [`translate()`] converts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Translation {
+  /// Replaces `+` and `/` with `-` and `_`.
+  StandardToUrlSafe,
+  /// Replaces `-` and `_` with `+` and `/`.
+  UrlSafeToStandard,
+}
+
+/// Converts base64 `data` between the standard and URL-safe alphabets, in
+/// place.
+///
+/// Only the two characters that differ between the alphabets are touched;
+/// everything else, including padding and invalid characters, is left as-is.
+/// To also add or remove padding, see [`set_padding()`].
+///
+/// ```
+/// let mut data = *b"+/+/AAAA";
+/// vb64::translate(&mut data, vb64::Translation::StandardToUrlSafe);
+/// assert_eq!(&data, b"-_-_AAAA");
+/// ```
+pub fn translate(data: &mut [u8], translation: Translation) {
+  let (from, to) = match translation {
+    Translation::StandardToUrlSafe => (*b"+/", *b"-_"),
+    Translation::UrlSafeToStandard => (*b"-_", *b"+/"),
+  };
+
+  if cfg!(target_feature = "avx2") {
+    translate_tunable::<32>(data, from, to)
+  } else {
+    translate_tunable::<16>(data, from, to)
+  }
+}
+
+/// Adds or removes `=` padding at the end of `data`, so that it is padded if
+/// and only if `pad` is true.
+///
+/// ```
+/// let mut data = b"QUI".to_vec();
+/// vb64::set_padding(&mut data, true);
+/// assert_eq!(data, b"QUI=");
+/// vb64::set_padding(&mut data, false);
+/// assert_eq!(data, b"QUI");
+/// ```
+#[cfg(feature = "alloc")]
+pub fn set_padding(data: &mut Vec<u8>, pad: bool) {
+  while data.last() == Some(&b'=') {
+    data.pop();
+  }
+
+  if pad {
+    match data.len() % 4 {
+      2 => data.extend_from_slice(b"=="),
+      3 => data.extend_from_slice(b"="),
+      _ => {}
+    }
+  }
+}
+
+#[inline(always)]
+fn translate_tunable<const N: usize>(
+  data: &mut [u8],
+  from: [u8; 2],
+  to: [u8; 2],
+) where
+  LaneCount<N>: SupportedLaneCount,
+{
+  let mut chunks = data.chunks_exact_mut(N);
+  for chunk in &mut chunks {
+    let ascii = simd::translate::<N>(Simd::from_slice(chunk), from, to);
+    ascii.copy_to_slice(chunk);
+  }
+
+  let rest = chunks.into_remainder();
+  if !rest.is_empty() {
+    let mut buf = [0; N];
+    buf[..rest.len()].copy_from_slice(rest);
+    let ascii = simd::translate(Simd::from_array(buf), from, to);
+    rest.copy_from_slice(&ascii.as_array()[..rest.len()]);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn roundtrip() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let url = crate::encode_url(&dec);
+
+      let mut data = enc.to_vec();
+      translate(&mut data, Translation::StandardToUrlSafe);
+      assert_eq!(data, url.as_bytes(), "case {i}");
+
+      translate(&mut data, Translation::UrlSafeToStandard);
+      assert_eq!(data, enc, "case {i}");
+
+      translate(&mut data, Translation::StandardToUrlSafe);
+      set_padding(&mut data, false);
+      assert_eq!(crate::decode_url_nopad(&data).unwrap(), dec, "case {i}");
+
+      set_padding(&mut data, true);
+      assert_eq!(data, url.as_bytes(), "case {i}");
+    }
+  }
+
+  #[test]
+  fn untouched() {
+    let mut data = (0..=255).collect::<Vec<u8>>();
+    translate(&mut data, Translation::StandardToUrlSafe);
+    for (i, &b) in data.iter().enumerate() {
+      let expected = match i as u8 {
+        b'+' => b'-',
+        b'/' => b'_',
+        b => b,
+      };
+      assert_eq!(b, expected, "{i:#04x}");
+    }
+  }
+}
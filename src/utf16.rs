@@ -0,0 +1,140 @@
+//! Decoding and encoding base64 held in UTF-16, as JavaScript engines and
+//! Windows APIs hand it over.
+
+use alloc::vec::Vec;
+use core::simd::prelude::*;
+
+use crate::Error;
+
+/// How many code units we narrow at a time. A multiple of four, so that only
+/// the last chunk can contain padding.
+const CHUNK: usize = 512;
+
+/// Decodes some base64 `data` held as UTF-16 code units.
+///
+/// The code units are narrowed to bytes a chunk at a time, so there's no need
+/// to build a `String` first. A code unit that isn't ASCII is reported as
+/// [`Error::InvalidByte`], with its offset in code units; units that don't fit
+/// in a byte at all are reported as `0xff`.
+///
+/// ```
+/// let data = "aGVsbG8=".encode_utf16().collect::<Vec<u16>>();
+/// assert_eq!(vb64::decode_utf16(&data).unwrap(), b"hello");
+///
+/// let data = "aGVs\u{3b2}G8=".encode_utf16().collect::<Vec<u16>>();
+/// assert_eq!(vb64::decode_utf16(&data).unwrap_err().offset(), Some(4));
+/// ```
+pub fn decode_utf16(data: &[u16]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  crate::reserve(&mut out, Some(crate::max_decoded_len(data.len())))?;
+
+  // Keep at least one unit for the last chunk, which is where padding and
+  // length checks apply.
+  let body_len = data.len().saturating_sub(1) / CHUNK * CHUNK;
+  let (body, tail) = data.split_at(body_len);
+
+  let mut buf = [0; CHUNK];
+  for (i, chunk) in body.chunks_exact(CHUNK).enumerate() {
+    narrow(chunk, &mut buf);
+    let result = match buf.contains(&b'=') {
+      true => Err(crate::invalid_byte(&buf, &crate::simd::STANDARD)),
+      false => crate::decode_any(&buf, &mut out, &crate::simd::STANDARD),
+    };
+    result.map_err(|e| e.shift(i * CHUNK))?;
+  }
+
+  let buf = &mut buf[..tail.len()];
+  narrow(tail, buf);
+  crate::decode_to(&*buf, &mut out).map_err(|e| e.shift(body_len))?;
+  Ok(out)
+}
+
+/// Encodes arbitrary data as standard, padded base64, as UTF-16 code units.
+///
+/// The data is encoded a chunk at a time and widened straight into the
+/// output, so there's no intermediate `String`.
+///
+/// # Panics
+///
+/// Panics if the encoded data wouldn't fit in memory.
+///
+/// ```
+/// let text = vb64::encode_utf16(b"hello");
+/// assert_eq!(String::from_utf16(&text).unwrap(), "aGVsbG8=");
+/// ```
+pub fn encode_utf16(data: impl AsRef<[u8]>) -> Vec<u16> {
+  let data = data.as_ref();
+  let len =
+    crate::encoded_len(data.len(), true).expect("base64 output too large");
+  let mut out = Vec::with_capacity(len);
+
+  let mut buf = [0; CHUNK];
+  for chunk in data.chunks(CHUNK / 4 * 3) {
+    let n = crate::encode_to_slice(chunk, &mut buf).unwrap();
+    widen(&buf[..n], &mut out);
+  }
+  out
+}
+
+/// Narrows each of `units` into the corresponding byte of `out`, saturating
+/// at `0xff`.
+fn narrow(units: &[u16], out: &mut [u8]) {
+  const N: usize = 16;
+  let mut units = units.chunks_exact(N);
+  let mut out = out.chunks_exact_mut(N);
+  for (units, out) in (&mut units).zip(&mut out) {
+    let wide = Simd::<u16, N>::from_slice(units);
+    wide
+      .simd_min(Simd::splat(0xff))
+      .cast::<u8>()
+      .copy_to_slice(out);
+  }
+
+  for (&unit, out) in units.remainder().iter().zip(out.into_remainder()) {
+    *out = unit.min(0xff) as u8;
+  }
+}
+
+/// Widens each of `bytes` into a code unit at the end of `out`.
+fn widen(bytes: &[u8], out: &mut Vec<u16>) {
+  const N: usize = 16;
+  let mut bytes = bytes.chunks_exact(N);
+  for chunk in &mut bytes {
+    let wide = Simd::<u8, N>::from_slice(chunk).cast::<u16>();
+    out.extend_from_slice(wide.as_array());
+  }
+  out.extend(bytes.remainder().iter().map(|&b| b as u16));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn wide(data: &[u8]) -> Vec<u16> {
+    data.iter().map(|&b| b as u16).collect()
+  }
+
+  #[test]
+  fn utf16() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      assert_eq!(decode_utf16(&wide(enc)).unwrap(), dec, "case {i}");
+      assert_eq!(encode_utf16(&dec), wide(enc), "case {i}");
+    }
+
+    let long = crate::encode(vec![0xa5; 3000]);
+    for bad in [0x80, 0xe9, 0x3b2, 0xfeff, b'=' as u16] {
+      for at in [0, 17, 600, 2000, long.len() - 3] {
+        let mut data = wide(long.as_bytes());
+        data[at] = bad;
+        let err = decode_utf16(&data).unwrap_err();
+        assert_eq!(err.offset(), Some(at), "{bad:#x} at {at}");
+      }
+    }
+
+    assert!(matches!(
+      decode_utf16(&wide(b"QUJDQ")),
+      Err(Error::InvalidLength)
+    ));
+    assert!(decode_utf16(&[]).unwrap().is_empty());
+  }
+}
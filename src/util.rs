@@ -1,10 +1,10 @@
 //! Helper macros.
 
-use std::mem::MaybeUninit;
-use std::simd::LaneCount;
-use std::simd::Simd;
-use std::simd::SimdElement;
-use std::simd::SupportedLaneCount;
+use core::mem::MaybeUninit;
+use core::simd::LaneCount;
+use core::simd::Simd;
+use core::simd::SimdElement;
+use core::simd::SupportedLaneCount;
 
 /// Takes an "index table" and generates an inverted index, i.e. such that
 /// `invert_index(x)[x[i]] == i` whenever both array accesses are in-bounds.
@@ -53,7 +53,7 @@ pub const unsafe fn array_assume_init<T: Copy, const N: usize>(
 /// index.
 macro_rules! array {
   ($N:expr; |$idx:ident| $body:expr) => {{
-    use std::mem::MaybeUninit;
+    use core::mem::MaybeUninit;
 
     let mut array = [MaybeUninit::uninit(); $N];
     let mut i = 0;
@@ -73,7 +73,7 @@ macro_rules! array {
 /// on a const parameter, e.g. an `array!()` call.
 macro_rules! swizzle {
   ($N:ident; $x:expr, $index:expr) => {{
-    use std::simd::*;
+    use core::simd::*;
     struct Swz;
     impl<const $N: usize> Swizzle2<$N, $N> for Swz
     where
@@ -0,0 +1,239 @@
+//! uuencode, the Unix-to-Unix encoding.
+//!
+//! uuencode uses the same bit layout as base64, but a different alphabet, and
+//! splits its output into lines of at most 45 bytes, each prefixed with its
+//! length. The whole thing is wrapped in `begin` and `end` lines.
+//!
+//! ```
+//! let text = vb64::uu::encode("cat.txt", 0o644, b"Cat");
+//! assert_eq!(text, "begin 644 cat.txt\n#0V%T\n`\nend\n");
+//!
+//! let file = vb64::uu::decode(text.as_bytes()).unwrap();
+//! assert_eq!(file.name(), "cat.txt");
+//! assert_eq!(file.mode(), 0o644);
+//! assert_eq!(file.data(), b"Cat");
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::pem::split_line;
+use crate::pem::trim;
+use crate::simd;
+use crate::Error;
+
+/// The number of bytes per line. The length character for this is `M`.
+const LINE_LEN: usize = 45;
+
+/// A decoded uuencoded file.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct File {
+  name: String,
+  mode: u32,
+  data: Vec<u8>,
+}
+
+impl File {
+  /// Creates a new file with the given name, Unix permissions, and contents.
+  pub fn new(
+    name: impl Into<String>,
+    mode: u32,
+    data: impl Into<Vec<u8>>,
+  ) -> Self {
+    Self {
+      name: name.into(),
+      mode,
+      data: data.into(),
+    }
+  }
+
+  /// Returns this file's name, from the `begin` line.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Returns this file's Unix permissions, from the `begin` line.
+  pub fn mode(&self) -> u32 {
+    self.mode
+  }
+
+  /// Returns this file's decoded contents.
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+
+  /// Consumes this file, returning its decoded contents.
+  pub fn into_data(self) -> Vec<u8> {
+    self.data
+  }
+
+  /// Encodes this file as uuencoded text.
+  pub fn encode(&self) -> String {
+    encode(&self.name, self.mode, &self.data)
+  }
+}
+
+/// Encodes `data` as a uuencoded file with the given name and Unix
+/// permissions.
+///
+/// The output uses `\n` line endings and `` ` `` rather than space for zero.
+pub fn encode(name: &str, mode: u32, data: &[u8]) -> String {
+  let mut out = format!("begin {mode:o} {name}\n");
+  out.push_str(&encode_body(data));
+  out.push_str("end\n");
+  out
+}
+
+/// Decodes the first uuencoded file in `text`.
+///
+/// Any text before the `begin` line is ignored. Both space and `` ` `` are
+/// accepted for zero, and lines that are missing trailing characters, which
+/// happens when trailing spaces get stripped, are padded back out.
+pub fn decode(text: &[u8]) -> Result<File, Error> {
+  let mut text = text;
+  let header = loop {
+    if text.is_empty() {
+      return Err(Error::InvalidFormat);
+    }
+
+    let (line, rest) = split_line(text);
+    text = rest;
+    if let Some(header) = trim(line).strip_prefix(b"begin ") {
+      break header;
+    }
+  };
+
+  let header =
+    core::str::from_utf8(header).map_err(|_| Error::InvalidFormat)?;
+  let (mode, name) = header.split_once(' ').ok_or(Error::InvalidFormat)?;
+  let mode = u32::from_str_radix(mode, 8).map_err(|_| Error::InvalidFormat)?;
+
+  let mut data = Vec::new();
+  let text = decode_lines(text, &mut data)?;
+
+  let (end, _) = split_line(text);
+  if trim(end) != b"end" {
+    return Err(Error::InvalidFormat);
+  }
+
+  Ok(File {
+    name: name.to_string(),
+    mode,
+    data,
+  })
+}
+
+/// Encodes `data` as the lines of a uuencoded file, without the `begin` and
+/// `end` lines.
+///
+/// This includes the final zero-length line.
+pub fn encode_body(data: &[u8]) -> String {
+  let mut out = Vec::with_capacity(data.len() / 3 * 4 + data.len() / 45 + 8);
+  for chunk in data.chunks(LINE_LEN) {
+    out.push(b' ' + chunk.len() as u8);
+
+    // Unlike base64, partial groups are padded with zeros, not `=`.
+    let start = out.len();
+    crate::encode_with(chunk, &mut out, &simd::UU, false)
+      .expect("uuencoded output too large");
+    let rem = (out.len() - start) % 4;
+    if rem != 0 {
+      out.resize(out.len() + 4 - rem, b'`');
+    }
+
+    out.push(b'\n');
+  }
+  out.extend_from_slice(b"`\n");
+
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+/// Decodes the lines of a uuencoded file, without the `begin` and `end` lines.
+///
+/// Decoding stops at the first zero-length line, or at the end of `text`.
+pub fn decode_body(text: &[u8]) -> Result<Vec<u8>, Error> {
+  let mut out = Vec::new();
+  decode_lines(text, &mut out)?;
+  Ok(out)
+}
+
+/// Decodes lines into `out` up to and including the first zero-length line,
+/// and returns the text after it.
+fn decode_lines<'a>(
+  mut text: &'a [u8],
+  out: &mut Vec<u8>,
+) -> Result<&'a [u8], Error> {
+  while !text.is_empty() {
+    let (line, rest) = split_line(text);
+    text = rest;
+
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let (&len, chars) = line.split_first().ok_or(Error::InvalidFormat)?;
+    if !(b' '..=b'`').contains(&len) {
+      return Err(Error::InvalidFormat);
+    }
+
+    let len = ((len - b' ') % 64) as usize;
+    if len == 0 {
+      break;
+    }
+
+    // Anything past the expected number of characters is ignored; some
+    // encoders put a checksum there.
+    let mut buf = [b'`'; 84];
+    let needed = len.div_ceil(3) * 4;
+    let chars = &chars[..needed.min(chars.len())];
+    buf[..chars.len()].copy_from_slice(chars);
+
+    let start = out.len();
+    crate::decode_with(&buf[..needed], out, &simd::UU, false)
+      .map_err(Error::unlocated)?;
+    out.truncate(start + len);
+  }
+
+  Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn known() {
+    assert_eq!(encode_body(b"Cat"), "#0V%T\n`\n");
+    assert_eq!(encode_body(b"\0\0\0"), "#````\n`\n");
+    assert_eq!(decode_body(b"#    \n`\n").unwrap(), b"\0\0\0");
+    assert_eq!(decode_body(b"#\n").unwrap(), b"\0\0\0");
+
+    let data = (0..45).collect::<Vec<u8>>();
+    assert_eq!(
+      encode_body(&data),
+      "M``$\"`P0%!@<(\"0H+#`T.#Q`1$A,4%187&!D:&QP='A\\@(2(C)\"4F)R@I*BLL\n`\n"
+    );
+  }
+
+  #[test]
+  fn roundtrip() {
+    for (i, _, dec) in crate::tests::random_tests() {
+      let text = encode("data.bin", 0o600, &dec);
+      for line in text.lines() {
+        assert!(line.len() <= 61, "case {i}");
+      }
+
+      let file = decode(text.as_bytes()).unwrap();
+      assert_eq!(file, File::new("data.bin", 0o600, dec), "case {i}");
+      assert_eq!(file.encode(), text, "case {i}");
+    }
+  }
+
+  #[test]
+  fn errors() {
+    assert!(decode(b"#0V%T\n`\nend\n").is_err());
+    assert!(decode(b"begin 644 a\n#0V%T\n`\n").is_err());
+    assert!(decode(b"begin 999 a\n#0V%T\n`\nend\n").is_err());
+    assert!(decode(b"begin 644 a\n#0V%~\n`\nend\n").is_err());
+    assert!(decode_body(b"\n").is_err());
+  }
+}
@@ -0,0 +1,200 @@
+//! Decoding and encoding data that arrives in several pieces.
+
+use std::io::IoSlice;
+
+use crate::Error;
+
+/// Decodes base64 that is split across several slices, such as the chunks of
+/// an HTTP body, as if they had been concatenated first.
+///
+/// The slices can be split anywhere, even in the middle of a group of four
+/// characters; only the few characters straddling each boundary get copied.
+/// Error offsets refer to the concatenated input.
+///
+/// ```
+/// use std::io::IoSlice;
+///
+/// let bufs = [&b"aGV"[..], b"sbG", b"8="].map(IoSlice::new);
+/// assert_eq!(vb64::decode_vectored(&bufs).unwrap(), b"hello");
+/// ```
+pub fn decode_vectored(bufs: &[IoSlice]) -> Result<Vec<u8>, Error> {
+  let len = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+
+  // Everything but the last group goes through the decoder as-is; the last
+  // group is where padding and length checks apply, so we deal with it
+  // separately, like `decode()` would.
+  let tail_start = match len % 4 {
+    0 => len.saturating_sub(4),
+    r => len - r,
+  };
+
+  let mut tail = [0; 4];
+  let mut tail_len = 0;
+  let mut pos = 0;
+  for buf in bufs {
+    let start = tail_start.saturating_sub(pos).min(buf.len());
+    let rest = &buf[start..];
+    tail[tail_len..tail_len + rest.len()].copy_from_slice(rest);
+    tail_len += rest.len();
+    pos += buf.len();
+  }
+
+  // Errors in the tail that aren't about a particular byte come first, just
+  // like they do in `decode()`; bad bytes are reported in order.
+  let mut tail_out = [0; 3];
+  let mut tail_err = None;
+  let tail_out = match crate::decode_to_slice(&tail[..tail_len], &mut tail_out)
+  {
+    Ok(n) => &tail_out[..n],
+    Err(e @ Error::InvalidByte { .. }) => {
+      tail_err = Some(e.shift(tail_start));
+      &[]
+    }
+    Err(e) => return Err(e),
+  };
+
+  let mut out = Vec::new();
+  crate::reserve(&mut out, Some(crate::max_decoded_len(len)))?;
+
+  let mut group = [0; 4];
+  let mut group_len = 0;
+  let mut pos = 0;
+  for buf in bufs {
+    let end = tail_start.saturating_sub(pos).min(buf.len());
+    let next = pos + buf.len();
+    let mut body = &buf[..end];
+
+    // Finish off a group left over from the previous slice.
+    if group_len > 0 {
+      let n = body.len().min(4 - group_len);
+      group[group_len..group_len + n].copy_from_slice(&body[..n]);
+      group_len += n;
+      body = &body[n..];
+      pos += n;
+
+      if group_len == 4 {
+        crate::decode_any(&group, &mut out, &crate::simd::STANDARD)
+          .map_err(|e| e.shift(pos - 4))?;
+        group_len = 0;
+      }
+    }
+
+    let whole = body.len() / 4 * 4;
+    crate::decode_any(&body[..whole], &mut out, &crate::simd::STANDARD)
+      .map_err(|e| e.shift(pos))?;
+
+    let rest = &body[whole..];
+    group[group_len..group_len + rest.len()].copy_from_slice(rest);
+    group_len += rest.len();
+    pos = next;
+  }
+  debug_assert_eq!(group_len, 0);
+
+  if let Some(e) = tail_err {
+    return Err(e);
+  }
+  out.extend_from_slice(tail_out);
+  Ok(out)
+}
+
+/// Encodes data that is split across several slices as base64, as if they had
+/// been concatenated first.
+///
+/// ```
+/// use std::io::IoSlice;
+///
+/// let bufs = [IoSlice::new(b"he"), IoSlice::new(b"ll"), IoSlice::new(b"o")];
+/// assert_eq!(vb64::encode_vectored(&bufs), "aGVsbG8=");
+/// ```
+pub fn encode_vectored(bufs: &[IoSlice]) -> String {
+  let len = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+  let mut out = Vec::new();
+  crate::reserve(&mut out, crate::encoded_len(len, true))
+    .expect("base64 output too large");
+
+  let mut group = [0; 3];
+  let mut group_len = 0;
+  for buf in bufs {
+    let mut buf = &buf[..];
+
+    // Finish off a group left over from the previous slice.
+    if group_len > 0 {
+      let n = buf.len().min(3 - group_len);
+      group[group_len..group_len + n].copy_from_slice(&buf[..n]);
+      group_len += n;
+      buf = &buf[n..];
+
+      if group_len == 3 {
+        crate::encode_to(group, &mut out);
+        group_len = 0;
+      }
+    }
+
+    let whole = buf.len() / 3 * 3;
+    crate::encode_to(&buf[..whole], &mut out);
+
+    let rest = &buf[whole..];
+    group[group_len..group_len + rest.len()].copy_from_slice(rest);
+    group_len += rest.len();
+  }
+  crate::encode_to(&group[..group_len], &mut out);
+
+  // SAFETY: Encoders only produce ASCII.
+  unsafe { String::from_utf8_unchecked(out) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Splits `data` at each of `cuts`.
+  fn split<'a>(data: &'a [u8], cuts: &[usize]) -> Vec<IoSlice<'a>> {
+    let mut bufs = Vec::new();
+    let mut start = 0;
+    for &cut in cuts {
+      let cut = cut.clamp(start, data.len());
+      bufs.push(IoSlice::new(&data[start..cut]));
+      start = cut;
+    }
+    bufs.push(IoSlice::new(&data[start..]));
+    bufs
+  }
+
+  #[test]
+  fn vectored() {
+    let cuts: [&[usize]; 6] = [
+      &[],
+      &[0],
+      &[1],
+      &[1, 2, 3, 4, 5],
+      &[3, 3, 9],
+      &[5, 70, 71, 200],
+    ];
+    for (i, enc, dec) in crate::tests::random_tests() {
+      for cuts in cuts {
+        let bufs = split(enc, cuts);
+        assert_eq!(decode_vectored(&bufs).unwrap(), dec, "case {i} {cuts:?}");
+        let bufs = split(&dec, cuts);
+        let text = encode_vectored(&bufs);
+        assert_eq!(text.as_bytes(), enc, "case {i} {cuts:?}");
+      }
+    }
+
+    let bad: [&[u8]; 7] = [
+      b"QUJD!UJDQUJD",
+      b"QUJDQUJDQU!D",
+      b"QUJDQU!DQU",
+      b"QU!DQUJDQ",
+      b"QUJDQ=",
+      b"QQ==QUJD",
+      b"QUJDQ",
+    ];
+    for data in bad {
+      let want = crate::decode(data).unwrap_err();
+      for cuts in cuts {
+        let got = decode_vectored(&split(data, cuts)).unwrap_err();
+        assert_eq!(got.to_string(), want.to_string(), "{data:?} {cuts:?}");
+      }
+    }
+  }
+}
@@ -0,0 +1,99 @@
+//! Constant-time comparison against base64, for secrets.
+
+use core::hint;
+
+/// Checks whether some base64 `data` decodes to `expected`, in time that
+/// depends only on the lengths of the inputs, and without allocating.
+///
+/// This follows the same rules as [`crate::decode()`], and is meant for
+/// checking MACs, tokens, and the like: it never stops early at the first
+/// invalid character or mismatched byte, so timing doesn't reveal how much
+/// of a guess was right.
+///
+/// ```
+/// let mac = [0xde, 0xad, 0xbe, 0xef];
+/// assert!(vb64::verify("3q2+7w==", mac));
+/// assert!(!vb64::verify("3q2+7A==", mac));
+/// assert!(!vb64::verify("3q2+", mac));
+/// ```
+pub fn verify(data: impl AsRef<[u8]>, expected: impl AsRef<[u8]>) -> bool {
+  let expected = expected.as_ref();
+  // The lengths, and so the padding, aren't secret.
+  let Ok(data) = crate::unpad(data.as_ref(), true) else {
+    return false;
+  };
+  if crate::max_decoded_len(data.len()) != expected.len() {
+    return false;
+  }
+
+  // Nonzero if any character was invalid or any byte differed.
+  let mut diff = 0u8;
+  for (chars, bytes) in data.chunks(4).zip(expected.chunks(3)) {
+    let mut group = 0u32;
+    for (i, &c) in chars.iter().enumerate() {
+      let (sextet, invalid) = sextet(c);
+      diff |= invalid;
+      group |= (sextet as u32) << (18 - 6 * i);
+    }
+    for (i, &b) in bytes.iter().enumerate() {
+      diff |= (group >> (16 - 8 * i)) as u8 ^ b;
+    }
+  }
+  hint::black_box(diff) == 0
+}
+
+/// Maps a standard base64 character to its value without branching, along
+/// with a byte that is nonzero if it isn't one.
+#[inline(always)]
+fn sextet(c: u8) -> (u8, u8) {
+  let c = c as i16;
+  // Each range adds one more than the value when `c` falls inside it, so
+  // zero means it fell in none of them.
+  let value = (in_range(c, b'A', b'Z') & (c - b'A' as i16 + 1))
+    | (in_range(c, b'a', b'z') & (c - b'a' as i16 + 27))
+    | (in_range(c, b'0', b'9') & (c - b'0' as i16 + 53))
+    | (in_range(c, b'+', b'+') & 63)
+    | (in_range(c, b'/', b'/') & 64);
+  ((value - 1) as u8, ((value - 1) >> 15) as u8)
+}
+
+/// Returns all ones if `lo <= c <= hi`, and zero otherwise.
+#[inline(always)]
+fn in_range(c: i16, lo: u8, hi: u8) -> i16 {
+  ((lo as i16 - 1 - c) & (c - hi as i16 - 1)) >> 15
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn verify_matches_decode() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      assert!(verify(enc, &dec), "case {i}");
+      let unpadded = crate::strip_padding(enc);
+      assert!(verify(unpadded, &dec), "case {i}");
+
+      if let Some(last) = dec.len().checked_sub(1) {
+        let mut wrong = dec.clone();
+        wrong[last] ^= 1;
+        assert!(!verify(enc, &wrong), "case {i}");
+        assert!(!verify(enc, &dec[..last]), "case {i}");
+      }
+    }
+  }
+
+  #[test]
+  fn verify_rejects_invalid() {
+    for c in 0..=255u8 {
+      let data = [b'Q', b'U', b'J', c];
+      let expected = crate::decode(b"QUJD").unwrap();
+      match crate::decode(data) {
+        Ok(decoded) => assert!(verify(data, decoded)),
+        Err(_) => assert!(!verify(data, &expected), "{c:#04x}"),
+      }
+    }
+    assert!(!verify("QUJD=", b"ABC"));
+    assert!(!verify("Q", b""));
+  }
+}
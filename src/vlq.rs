@@ -0,0 +1,160 @@
+//! Base64 VLQ, as used by JavaScript source maps.
+//!
+//! Each value is written as a little-endian sequence of five-bit groups, each
+//! of which is stored in one base64 digit along with a continuation bit. The
+//! lowest bit of the first group is the sign.
+//!
+//! ```
+//! let text = vb64::vlq::encode([0, 16, -1, 1024]);
+//! assert_eq!(text, "AgBDggC");
+//! assert_eq!(vb64::vlq::decode(text.as_bytes()).unwrap(), [0, 16, -1, 1024]);
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::simd::Simd;
+
+use crate::simd;
+use crate::Error;
+
+/// The number of characters we convert to and from sextets at a time.
+const N: usize = 16;
+
+/// Set on every digit of a value except the last one.
+const CONTINUE: u8 = 0b100000;
+
+/// Encodes a sequence of integers as base64 VLQ.
+pub fn encode(values: impl IntoIterator<Item = i64>) -> String {
+  let mut out = String::new();
+  encode_to(values, &mut out);
+  out
+}
+
+/// Encodes a sequence of integers as base64 VLQ, and appends them to `out`.
+pub fn encode_to(values: impl IntoIterator<Item = i64>, out: &mut String) {
+  // We produce digits in batches, and convert them into ASCII with the same
+  // kernel as the regular encoder.
+  let mut digits = [0u8; N + 13];
+  let mut len = 0;
+  for value in values {
+    let mut vlq = (value.unsigned_abs() as u128) << 1 | (value < 0) as u128;
+    loop {
+      let digit = (vlq & 0b11111) as u8;
+      vlq >>= 5;
+      if vlq == 0 {
+        digits[len] = digit;
+        len += 1;
+        break;
+      }
+      digits[len] = digit | CONTINUE;
+      len += 1;
+    }
+
+    if len >= N {
+      flush(&mut digits, &mut len, out);
+    }
+  }
+
+  while len > 0 {
+    flush(&mut digits, &mut len, out);
+  }
+}
+
+/// Converts up to `N` digits at the front of `digits` into ASCII.
+fn flush(digits: &mut [u8; N + 13], len: &mut usize, out: &mut String) {
+  let count = (*len).min(N);
+  let ascii = simd::from_sextets(
+    Simd::<u8, N>::from_slice(&digits[..N]),
+    &simd::STANDARD,
+  );
+  out.push_str(unsafe { core::str::from_utf8_unchecked(&ascii[..count]) });
+
+  digits.copy_within(count.., 0);
+  *len -= count;
+}
+
+/// Decodes a sequence of integers from base64 VLQ.
+///
+/// Returns an error if `text` contains a character outside of the standard
+/// base64 alphabet, if it ends in the middle of a value, or if a value does
+/// not fit in an `i64`.
+pub fn decode(text: &[u8]) -> Result<Vec<i64>, Error> {
+  let mut out = Vec::new();
+  decode_to(text, &mut out)?;
+  Ok(out)
+}
+
+/// Decodes a sequence of integers from base64 VLQ, and appends them to `out`.
+pub fn decode_to(text: &[u8], out: &mut Vec<i64>) -> Result<(), Error> {
+  let mut vlq = 0u128;
+  let mut shift = 0;
+  for (i, chunk) in text.chunks(N).enumerate() {
+    let mut buf = [b'A'; N];
+    buf[..chunk.len()].copy_from_slice(chunk);
+    let (digits, ok) = simd::to_sextets(Simd::from_array(buf), &simd::STANDARD);
+    if !ok {
+      return Err(crate::invalid_byte(chunk, &simd::STANDARD).shift(i * N));
+    }
+
+    for &digit in &digits[..chunk.len()] {
+      if shift > 64 {
+        return Err(Error::InvalidFormat);
+      }
+      vlq |= ((digit & !CONTINUE) as u128) << shift;
+      shift += 5;
+
+      if digit & CONTINUE == 0 {
+        let magnitude = vlq >> 1;
+        let value = if vlq & 1 == 0 {
+          i64::try_from(magnitude).map_err(|_| Error::InvalidFormat)?
+        } else {
+          0i128
+            .checked_sub_unsigned(magnitude)
+            .and_then(|v| i64::try_from(v).ok())
+            .ok_or(Error::InvalidFormat)?
+        };
+
+        out.push(value);
+        vlq = 0;
+        shift = 0;
+      }
+    }
+  }
+
+  if shift != 0 {
+    return Err(Error::InvalidLength);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn known() {
+    // Segments from a real source map.
+    assert_eq!(decode(b"AAAA").unwrap(), [0, 0, 0, 0]);
+    assert_eq!(decode(b"SAAQC").unwrap(), [9, 0, 0, 8, 1]);
+    assert_eq!(decode(b"2HAAA").unwrap(), [123, 0, 0, 0]);
+    assert_eq!(encode([9, 0, 0, 8, 1]), "SAAQC");
+  }
+
+  #[test]
+  fn roundtrip() {
+    let values = (0..1000)
+      .map(|i: i64| i.wrapping_mul(0x9e3779b97f4a7c15u64 as i64) >> (i % 64))
+      .chain([i64::MIN, i64::MAX, -1, 0, 1])
+      .collect::<Vec<_>>();
+
+    let text = encode(values.iter().copied());
+    assert_eq!(decode(text.as_bytes()).unwrap(), values);
+  }
+
+  #[test]
+  fn errors() {
+    assert!(decode(b"g").is_err());
+    assert!(decode(b"AA=A").is_err());
+    assert!(decode(b"gggggggggggggggC").is_err());
+  }
+}
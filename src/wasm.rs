@@ -0,0 +1,56 @@
+//! JavaScript bindings, for use as a faster `atob()` and `btoa()`.
+//!
+//! Requires the `wasm-bindgen` feature. These work on `Uint8Array`s, rather
+//! than the "binary strings" that `atob()` and `btoa()` use, so JavaScript
+//! callers don't need to convert to and from those by hand:
+//!
+//! ```js
+//! import { encode, decode } from "vb64";
+//!
+//! const text = encode(new TextEncoder().encode("hello"));  // "aGVsbG8="
+//! const data = decode(text);  // Uint8Array [104, 101, 108, 108, 111]
+//! ```
+//!
+//! Arguments are copied into the module's memory once, and results are copied
+//! out once; nothing else is copied along the way.
+
+use wasm_bindgen::prelude::*;
+
+/// Encodes a `Uint8Array` as standard, padded base64.
+#[wasm_bindgen]
+pub fn encode(data: &[u8]) -> String {
+  crate::encode(data)
+}
+
+/// Decodes a string of standard base64 into a `Uint8Array`.
+///
+/// Throws an `Error` describing what's wrong with the input if it isn't
+/// valid base64.
+#[wasm_bindgen]
+pub fn decode(data: &str) -> Result<Vec<u8>, JsError> {
+  decode_bytes(data.as_bytes())
+}
+
+/// Decodes a `Uint8Array` holding standard base64 text, such as a chunk of a
+/// `fetch()` response body, into a `Uint8Array`.
+///
+/// This skips converting the text to a string first.
+#[wasm_bindgen(js_name = decodeBytes)]
+pub fn decode_bytes(data: &[u8]) -> Result<Vec<u8>, JsError> {
+  crate::decode(data).map_err(JsError::from)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Only the non-throwing paths can run outside a JavaScript host.
+  #[test]
+  fn wasm() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      assert_eq!(encode(&dec).as_bytes(), enc, "case {i}");
+      assert_eq!(decode_bytes(enc).unwrap(), dec, "case {i}");
+    }
+    assert_eq!(decode("aGVsbG8=").unwrap(), b"hello");
+  }
+}
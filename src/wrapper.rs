@@ -0,0 +1,118 @@
+//! A wrapper that formats and parses its contents as base64.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::str::FromStr;
+
+use crate::Base64Display;
+use crate::Error;
+
+/// Bytes that are formatted and parsed as standard, padded base64.
+///
+/// [`Display`][fmt::Display] encodes the wrapped bytes, and [`FromStr`]
+/// decodes into anything that can be built from a `Vec<u8>`, so binary values
+/// work with [`str::parse()`] and [`ToString::to_string()`], and with anything
+/// built on top of them:
+///
+/// ```
+/// use vb64::Base64;
+///
+/// let key = Base64(b"hello".to_vec());
+/// assert_eq!(key.to_string(), "aGVsbG8=");
+///
+/// let key: Base64<Vec<u8>> = "aGVsbG8=".parse().unwrap();
+/// assert_eq!(key.0, b"hello");
+///
+/// let key: Base64<[u8; 4]> = "3q2+7w==".parse().unwrap();
+/// assert_eq!(*key, [0xde, 0xad, 0xbe, 0xef]);
+/// ```
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Base64<T>(pub T);
+
+impl<T> Base64<T> {
+  /// Unwraps the bytes.
+  pub fn into_inner(self) -> T {
+    self.0
+  }
+}
+
+impl<T> From<T> for Base64<T> {
+  fn from(value: T) -> Self {
+    Self(value)
+  }
+}
+
+impl<T> Deref for Base64<T> {
+  type Target = T;
+  fn deref(&self) -> &T {
+    &self.0
+  }
+}
+
+impl<T> DerefMut for Base64<T> {
+  fn deref_mut(&mut self) -> &mut T {
+    &mut self.0
+  }
+}
+
+impl<T: AsRef<[u8]>> AsRef<[u8]> for Base64<T> {
+  fn as_ref(&self) -> &[u8] {
+    self.0.as_ref()
+  }
+}
+
+impl<T: AsRef<[u8]>> fmt::Display for Base64<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&Base64Display::new(self.0.as_ref()), f)
+  }
+}
+
+impl<T: AsRef<[u8]>> fmt::Debug for Base64<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Base64(\"{self}\")")
+  }
+}
+
+/// Decodes standard base64, failing with [`Error::InvalidLength`] if `T`
+/// can't hold what it decodes to, such as an array of the wrong size.
+impl<T: TryFrom<Vec<u8>>> FromStr for Base64<T> {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self, Error> {
+    let data = crate::decode(s)?;
+    T::try_from(data)
+      .map(Self)
+      .map_err(|_| Error::InvalidLength)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trip() {
+    for (i, enc, dec) in crate::tests::random_tests() {
+      let text = core::str::from_utf8(enc).unwrap();
+      assert_eq!(Base64(&dec).to_string(), text, "case {i}");
+      let parsed = text.parse::<Base64<Vec<u8>>>().unwrap();
+      assert_eq!(parsed.into_inner(), dec, "case {i}");
+    }
+  }
+
+  #[test]
+  fn parse_errors() {
+    assert_eq!(
+      "QU!D".parse::<Base64<Vec<u8>>>(),
+      Err(Error::InvalidByte {
+        offset: 2,
+        byte: b'!'
+      })
+    );
+    assert_eq!("QUJD".parse::<Base64<[u8; 4]>>(), Err(Error::InvalidLength));
+    assert_eq!(*"QUJD".parse::<Base64<[u8; 3]>>().unwrap(), *b"ABC");
+    assert_eq!(format!("{:?}", Base64(b"ABC")), "Base64(\"QUJD\")");
+  }
+}